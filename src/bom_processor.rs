@@ -1,16 +1,22 @@
 use crate::{
-    AutoCorrection, BomData, BomRow, ColumnDictionary, ColumnMapping, OverrideList,
-    PreprocessRules, RegisteredNameEntry, RegisteredNameList, ValidationError, ValidationResult,
+    AutoCorrection, BomData, BomRow, ColumnDictionary, ColumnMapping, ColumnMappingByName,
+    NumberConvention, OverrideList, OverrideMatchField, PreprocessRules, RegisteredNameEntry,
+    RegisteredNameList, ValidationError, ValidationResult, VerbatimColumns,
 };
-use calamine::{open_workbook, Reader, Xls, XlsError, Xlsx, XlsxError};
+use calamine::{open_workbook, Data, Range, Reader, Xls, XlsError, Xlsx, XlsxError};
+use chrono::{Days, NaiveDate};
 use csv::ReaderBuilder;
-use encoding_rs::{SHIFT_JIS, UTF_8};
+use encoding_rs::{Encoding, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8};
+use indexmap::IndexMap;
 use rayon::prelude::*;
-use serde::Serialize;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use serde_json;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::{Read, Seek};
+use std::io::{Cursor, Read, Seek};
 use std::path::Path;
 use thiserror::Error;
 
@@ -24,15 +30,71 @@ pub enum BomProcessorError {
     EncodingError(String),
     #[error("列指定エラー: {0}")]
     ColumnError(String),
+    #[error("ファイルが見つかりません: {0}")]
+    FileNotFound(String),
+    /// 利用者が指定した値（正規表現・ワークシートなど）が不正
+    #[error("入力エラー: {0}")]
+    InvalidInput(String),
 }
 
+impl From<std::io::Error> for BomProcessorError {
+    fn from(err: std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::NotFound => BomProcessorError::FileNotFound(err.to_string()),
+            _ => BomProcessorError::FileReadError(err.to_string()),
+        }
+    }
+}
+
+/// 読み込むファイルサイズの既定の上限（MB。AppSettings.max_file_size_mb 未指定時）
+pub const DEFAULT_MAX_FILE_SIZE_MB: u64 = 500;
+/// これより大きいCSVは一括で読み込まず、文字コードを変換しながら順に読む
+const CSV_STREAMING_THRESHOLD: u64 = 64 * 1024 * 1024;
+const CSV_STREAM_CHUNK_SIZE: usize = 64 * 1024;
 const MAX_SAMPLE_ROWS: usize = 10;
+const MAX_COLUMN_SAMPLES: usize = 5;
+
+/// 範囲展開の対象とする参照記号の既定パターン（英字＋任意の数字。例: "C", "R12"）
+pub const DEFAULT_DESIGNATOR_PATTERN: &str = r"^[A-Za-z]+[0-9]*$";
+
+/// 範囲展開で1つの表記から展開できる幅の既定値
+pub const DEFAULT_MAX_RANGE_SPAN: u32 = 100;
+/// 範囲展開の幅として指定できる上限（1セルの表記から大量の行を作らないため）
+pub const MAX_RANGE_SPAN_LIMIT: u32 = 10_000;
+/// 範囲展開で部品表全体に追加できる行数の上限（超えた範囲は展開せず警告する）
+const MAX_EXPANDED_ROWS: usize = 1_000_000;
+
+/// 範囲展開前の表記を保持する属性名
+pub const RANGE_SOURCE_ATTRIBUTE: &str = "元参照";
+
+/// 読み込み可能な部品表ファイルの拡張子（ファイル選択ダイアログのフィルターと共通）
+pub const SUPPORTED_BOM_EXTENSIONS: &[&str] = &["csv", "tsv", "txt", "xls", "xlsx"];
 
 #[derive(Debug, Clone, Serialize)]
 pub struct FileAnalysis {
     pub headers: Vec<String>,
     pub suggested_mapping: Option<ColumnMapping>,
     pub sample_rows: Vec<Vec<String>>,
+    /// 先頭行がデータ行に見える（ヘッダーなしの指定を提案する）
+    pub header_likely_missing: bool,
+    /// 列ごとのサンプル値（空でない重複なしの値を先頭から最大5件）
+    pub column_samples: Vec<Vec<String>>,
+    /// 推定した列指定の確認を促す警告
+    pub warnings: Vec<String>,
+    /// ブックの文書プロパティ（xlsxのみ。ユーザー定義プロパティは "custom:名前" をキーとする）
+    pub metadata: HashMap<String, String>,
+    /// ブック内の全ワークシート名（Excelのみ。シート選択用）
+    pub sheet_names: Vec<String>,
+    /// 解析したワークシート名（Excelのみ）
+    pub sheet_name: Option<String>,
+}
+
+/// 読み込むワークシートの指定（シート名、または先頭を0とする番号）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SheetSelector {
+    Index(usize),
+    Name(String),
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -41,64 +103,295 @@ pub struct FilePreview {
     pub rows: Vec<Vec<String>>,
 }
 
+/// 元の値と正規化後の値を並べたセル
+#[derive(Debug, Clone, Serialize)]
+pub struct NormalizedPreviewCell {
+    pub raw: String,
+    pub normalized: String,
+    /// 正規化によって値が変わったか
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NormalizedPreview {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<NormalizedPreviewCell>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisteredNameConflict {
+    pub part_model: String,
+    pub manufacturer: Option<String>,
+    pub kept_name: String,
+    pub discarded_name: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct LoadBomResult {
     pub bom: BomData,
     pub corrections: Vec<AutoCorrection>,
+    /// 読み込み後の部品表の列位置に対応する列指定
+    pub column_mapping: ColumnMapping,
+    /// 読み込んだワークシート名（Excelのみ）
+    pub sheet_name: Option<String>,
+    /// 読み込みは継続したが列指定の確認を促す警告
+    pub warnings: Vec<String>,
+    /// 部品番号が空のため読み込まなかった行の番号（データ行の1始まり。全セルが空の行は含めない）
+    pub skipped_empty_rows: Vec<usize>,
+    /// 列を絞り込む前のファイルのヘッダー（analyze_bom_file の headers と同じ並び）
+    pub source_headers: Vec<String>,
 }
 
-/// ファイル拡張子に基づいてBOMファイルを読み込む
-pub async fn load_bom_file(
-    file_path: &str,
-    column_mapping: &ColumnMapping,
-) -> Result<LoadBomResult, BomProcessorError> {
-    let path = Path::new(file_path);
-    let extension = path
+#[derive(Debug, Clone)]
+pub struct PreprocessResult {
+    pub bom: BomData,
+    /// 展開上限を超えたため展開しなかった範囲表記などの警告
+    pub warnings: Vec<String>,
+}
+
+/// 拡張子を取得し、サポート対象かどうかを確認する
+fn supported_extension(file_path: &str) -> Result<String, BomProcessorError> {
+    let extension = Path::new(file_path)
         .extension()
         .and_then(|ext| ext.to_str())
         .unwrap_or("")
         .to_lowercase();
 
+    if SUPPORTED_BOM_EXTENSIONS.contains(&extension.as_str()) {
+        Ok(extension)
+    } else {
+        Err(BomProcessorError::FormatError(
+            "サポートされていないファイル形式です".to_string(),
+        ))
+    }
+}
+
+/// 読み込む前にファイルサイズを確認する（巨大なファイルを誤って選んだ場合に応答しなくなるのを防ぐ）
+pub fn check_file_size(file_path: &str, max_bytes: u64) -> Result<u64, BomProcessorError> {
+    let size = fs::metadata(file_path)?.len();
+    if size > max_bytes {
+        const MB: u64 = 1024 * 1024;
+        return Err(BomProcessorError::FileReadError(format!(
+            "ファイルが大きすぎます（{} MB、上限 {} MB）",
+            size.div_ceil(MB),
+            max_bytes / MB
+        )));
+    }
+    Ok(size)
+}
+
+/// ファイル拡張子に基づいてBOMファイルを読み込む（max_file_size はバイト数）
+/// `sheet` はExcelのみに適用する（未指定時はデータのある先頭のワークシート）
+pub async fn load_bom_file(
+    file_path: &str,
+    column_mapping: &ColumnMapping,
+    max_file_size: u64,
+    sheet: Option<&SheetSelector>,
+) -> Result<LoadBomResult, BomProcessorError> {
+    let extension = supported_extension(file_path)?;
+    let file_size = check_file_size(file_path, max_file_size)?;
+
     match extension.as_str() {
-        "xlsx" | "xls" => load_excel_file(file_path, column_mapping).await,
-        "csv" => load_csv_file(file_path, column_mapping).await,
+        "xlsx" | "xls" => load_excel_file(file_path, column_mapping, sheet).await,
+        "csv" | "tsv" | "txt" => load_csv_file(file_path, column_mapping, file_size).await,
         _ => Err(BomProcessorError::FormatError(
             "サポートされていないファイル形式です".to_string(),
         )),
     }
 }
 
+/// 列名による列指定をヘッダー行に照らして列番号に変換する（大文字小文字・全角半角・空白を無視）
+pub fn resolve_mapping_by_name(
+    headers: &[String],
+    mapping: &ColumnMappingByName,
+) -> Result<ColumnMapping, BomProcessorError> {
+    let normalized: Vec<String> = headers.iter().map(|h| standardize_string(h)).collect();
+    let find = |name: &str| -> Result<usize, BomProcessorError> {
+        let target = standardize_string(name);
+        normalized
+            .iter()
+            .position(|header| *header == target)
+            .ok_or_else(|| {
+                BomProcessorError::ColumnError(format!("列「{}」が見つかりません", name))
+            })
+    };
+
+    Ok(ColumnMapping {
+        part_number: find(&mapping.part_number)?,
+        model_number: find(&mapping.model_number)?,
+        manufacturer: match mapping.manufacturer.as_deref() {
+            Some(name) if !name.trim().is_empty() => Some(find(name)?),
+            _ => None,
+        },
+        quantity: match mapping.quantity.as_deref() {
+            Some(name) if !name.trim().is_empty() => Some(find(name)?),
+            _ => None,
+        },
+        keep_columns: None,
+        has_headers: true,
+        verbatim_columns: Vec::new(),
+        date_columns: Vec::new(),
+        split_column: None,
+        whitespace_modes: Vec::new(),
+    })
+}
+
+/// 読み込み後の部品表の列位置で保持している列指定を、元ファイルの列位置に戻す（列名で対応付ける）
+/// 列の絞り込みは解除し、元ファイルの全列を読み込む列指定を返す
+pub fn remap_to_source_columns(
+    mapping: &ColumnMapping,
+    loaded_headers: &[String],
+    source_headers: &[String],
+) -> Result<ColumnMapping, BomProcessorError> {
+    let normalized: Vec<String> = source_headers
+        .iter()
+        .map(|h| standardize_string(h))
+        .collect();
+    let find = |idx: usize| -> Result<usize, BomProcessorError> {
+        let name = loaded_headers.get(idx).ok_or_else(|| {
+            BomProcessorError::ColumnError(format!("列番号 {} が部品表にありません", idx))
+        })?;
+        let target = standardize_string(name);
+        normalized
+            .iter()
+            .position(|header| *header == target)
+            .ok_or_else(|| {
+                BomProcessorError::ColumnError(format!(
+                    "元ファイルに列「{}」が見つかりません",
+                    name
+                ))
+            })
+    };
+    let find_all = |columns: &[usize]| -> Result<Vec<usize>, BomProcessorError> {
+        columns.iter().map(|&idx| find(idx)).collect()
+    };
+
+    let split_column = match mapping.split_column {
+        Some((idx, delimiter)) => Some((find(idx)?, delimiter)),
+        None => None,
+    };
+    let (part_number, model_number) = match split_column {
+        Some((idx, _)) => (idx, idx),
+        None => (find(mapping.part_number)?, find(mapping.model_number)?),
+    };
+
+    Ok(ColumnMapping {
+        part_number,
+        model_number,
+        manufacturer: mapping.manufacturer.map(find).transpose()?,
+        quantity: mapping.quantity.map(find).transpose()?,
+        keep_columns: None,
+        has_headers: mapping.has_headers,
+        verbatim_columns: find_all(&mapping.verbatim_columns)?,
+        date_columns: find_all(&mapping.date_columns)?,
+        split_column,
+        whitespace_modes: mapping
+            .whitespace_modes
+            .iter()
+            .map(|&(idx, mode)| find(idx).map(|source_idx| (source_idx, mode)))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// `has_headers` はCSVのみに適用する（Excelは常に先頭行をヘッダーとして扱う）
+/// `heuristic_only` が true の場合は辞書を使わず、文字列列の判定と一意性のみで列を推定する
 pub async fn analyze_bom_file(
     file_path: &str,
-    dictionary: &ColumnDictionary,
+    dictionary: &CompiledDictionary,
+    has_headers: bool,
+    heuristic_only: bool,
+    max_file_size: u64,
+    sheet: Option<&SheetSelector>,
 ) -> Result<FileAnalysis, BomProcessorError> {
-    let path = Path::new(file_path);
-    let extension = path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .unwrap_or("")
-        .to_lowercase();
+    let extension = supported_extension(file_path)?;
+    check_file_size(file_path, max_file_size)?;
 
     match extension.as_str() {
-        "xlsx" => analyze_excel_file(file_path, dictionary),
-        "xls" => analyze_excel_file(file_path, dictionary),
-        "csv" => analyze_csv_file(file_path, dictionary).await,
+        "xlsx" | "xls" => analyze_excel_file(file_path, dictionary, heuristic_only, sheet),
+        "csv" | "tsv" | "txt" => {
+            analyze_csv_file(file_path, dictionary, has_headers, heuristic_only).await
+        }
         _ => Err(BomProcessorError::FormatError(
             "サポートされていないファイル形式です".to_string(),
         )),
     }
 }
 
+/// xlsx の文書プロパティ（docProps/core.xml・custom.xml）を読み込む
+/// 読み込めない場合は空を返す（解析自体は継続する）
+fn read_xlsx_metadata(file_path: &str) -> HashMap<String, String> {
+    let read_entry = |archive: &mut zip::ZipArchive<fs::File>, name: &str| -> Option<String> {
+        let mut entry = archive.by_name(name).ok()?;
+        let mut content = String::new();
+        entry.read_to_string(&mut content).ok()?;
+        Some(content)
+    };
+
+    let archive = fs::File::open(file_path)
+        .map_err(|e| e.to_string())
+        .and_then(|file| zip::ZipArchive::new(file).map_err(|e| e.to_string()));
+    match archive {
+        Ok(mut archive) => {
+            let core = read_entry(&mut archive, "docProps/core.xml").unwrap_or_default();
+            let custom = read_entry(&mut archive, "docProps/custom.xml").unwrap_or_default();
+            parse_document_properties(&core, &custom)
+        }
+        Err(e) => {
+            log::warn!("[analyze_file][metadata] path={}, err={}", file_path, e);
+            HashMap::new()
+        }
+    }
+}
+
+/// core.xml の各要素（dc:title, cp:lastModifiedBy, dcterms:modified など）はスネークケースの要素名、
+/// custom.xml のユーザー定義プロパティは "custom:名前" をキーとして値を取り出す
+fn parse_document_properties(core_xml: &str, custom_xml: &str) -> HashMap<String, String> {
+    let mut metadata = HashMap::new();
+
+    let core_pattern = Regex::new(r"<(?:dc|cp|dcterms):(\w+)[^>]*>([^<]*)</").unwrap();
+    for caps in core_pattern.captures_iter(core_xml) {
+        let value = unescape_xml(caps[2].trim());
+        if value.is_empty() {
+            continue;
+        }
+        let mut key = String::new();
+        for c in caps[1].chars() {
+            if c.is_ascii_uppercase() {
+                key.push('_');
+            }
+            key.push(c.to_ascii_lowercase());
+        }
+        metadata.insert(key, value);
+    }
+
+    let custom_pattern =
+        Regex::new(r#"<property\b[^>]*\bname="([^"]*)"[^>]*>\s*<vt:\w+>([^<]*)</vt:\w+>"#).unwrap();
+    for caps in custom_pattern.captures_iter(custom_xml) {
+        metadata.insert(
+            format!("custom:{}", unescape_xml(&caps[1])),
+            unescape_xml(caps[2].trim()),
+        );
+    }
+
+    metadata
+}
+
+fn unescape_xml(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
 pub async fn preview_raw_file(
     file_path: &str,
     limit: usize,
+    max_file_size: u64,
 ) -> Result<FilePreview, BomProcessorError> {
-    let path = Path::new(file_path);
-    let extension = path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .unwrap_or("")
-        .to_lowercase();
+    let extension = supported_extension(file_path)?;
+    check_file_size(file_path, max_file_size)?;
 
     let capped_limit = limit.max(1).min(1000);
 
@@ -113,16 +406,68 @@ pub async fn preview_raw_file(
                 .map_err(|e: XlsError| BomProcessorError::FileReadError(e.to_string()))?;
             preview_excel_workbook(&mut workbook, capped_limit)
         }
-        "csv" => preview_csv_file(file_path, capped_limit).await,
+        "csv" | "tsv" | "txt" => preview_csv_file(file_path, capped_limit).await,
         _ => Err(BomProcessorError::FormatError(
             "サポートされていないファイル形式です".to_string(),
         )),
     }
 }
 
+/// ファイルを一度だけ読み込み、列指定に従った正規化の前後の値をセルごとに並べて返す
+/// 先頭行は列指定の `has_headers` が false の場合もデータ行として扱う
+pub async fn preview_with_normalization(
+    file_path: &str,
+    mapping: &ColumnMapping,
+    limit: usize,
+    max_file_size: u64,
+) -> Result<NormalizedPreview, BomProcessorError> {
+    let capped_limit = limit.clamp(1, 1000);
+    let preview = preview_raw_file(file_path, capped_limit, max_file_size).await?;
+    Ok(build_normalized_preview(preview, mapping, capped_limit))
+}
+
+fn build_normalized_preview(
+    preview: FilePreview,
+    mapping: &ColumnMapping,
+    limit: usize,
+) -> NormalizedPreview {
+    let (headers, raw_rows) = if mapping.has_headers {
+        (preview.headers, preview.rows)
+    } else {
+        let mut rows = preview.rows;
+        let headers = synthesized_headers(preview.headers.len());
+        rows.insert(0, preview.headers);
+        rows.truncate(limit);
+        (headers, rows)
+    };
+
+    let rows = raw_rows
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .enumerate()
+                .map(|(col_idx, raw)| {
+                    let normalized = normalize_cell(col_idx, &raw, mapping)
+                        .map(|(value, _)| value)
+                        .unwrap_or_else(|| raw.clone());
+                    NormalizedPreviewCell {
+                        changed: normalized != raw,
+                        raw,
+                        normalized,
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    NormalizedPreview { headers, rows }
+}
+
 fn analyze_excel_file(
     file_path: &str,
-    dictionary: &ColumnDictionary,
+    dictionary: &CompiledDictionary,
+    heuristic_only: bool,
+    sheet: Option<&SheetSelector>,
 ) -> Result<FileAnalysis, BomProcessorError> {
     let extension = Path::new(file_path)
         .extension()
@@ -134,12 +479,15 @@ fn analyze_excel_file(
         "xlsx" => {
             let mut workbook: Xlsx<_> = open_workbook(file_path)
                 .map_err(|e: XlsxError| BomProcessorError::FileReadError(e.to_string()))?;
-            analyze_excel_workbook(&mut workbook, dictionary)
+            let mut analysis =
+                analyze_excel_workbook(&mut workbook, dictionary, heuristic_only, sheet)?;
+            analysis.metadata = read_xlsx_metadata(file_path);
+            Ok(analysis)
         }
         "xls" => {
             let mut workbook: Xls<_> = open_workbook(file_path)
                 .map_err(|e: XlsError| BomProcessorError::FileReadError(e.to_string()))?;
-            analyze_excel_workbook(&mut workbook, dictionary)
+            analyze_excel_workbook(&mut workbook, dictionary, heuristic_only, sheet)
         }
         _ => Err(BomProcessorError::FormatError(
             "Excelファイルの拡張子が無効です".to_string(),
@@ -149,19 +497,16 @@ fn analyze_excel_file(
 
 fn analyze_excel_workbook<R, RS>(
     workbook: &mut R,
-    dictionary: &ColumnDictionary,
+    dictionary: &CompiledDictionary,
+    heuristic_only: bool,
+    sheet: Option<&SheetSelector>,
 ) -> Result<FileAnalysis, BomProcessorError>
 where
     R: Reader<RS>,
     RS: Read + Seek,
     R::Error: std::fmt::Display,
 {
-    let range = workbook
-        .worksheet_range_at(0)
-        .ok_or_else(|| {
-            BomProcessorError::FileReadError("ワークシートが見つかりません".to_string())
-        })?
-        .map_err(|e: R::Error| BomProcessorError::FileReadError(e.to_string()))?;
+    let (sheet_name, range) = select_sheet(workbook, sheet)?;
 
     let mut headers: Vec<String> = Vec::new();
     let mut sample_rows: Vec<Vec<String>> = Vec::new();
@@ -181,12 +526,27 @@ where
         sample_rows.push(row_values);
     }
 
-    let suggested_mapping = detect_column_mapping(&headers, &sample_rows, dictionary);
+    let suggested_mapping =
+        detect_column_mapping(&headers, &sample_rows, dictionary, heuristic_only);
+
+    let column_samples = collect_column_samples(headers.len(), &sample_rows);
+    let warnings = part_column_warnings(
+        suggested_mapping
+            .as_ref()
+            .map(|mapping| mapping.part_number),
+        &sample_rows,
+    );
 
     Ok(FileAnalysis {
         headers,
         suggested_mapping,
         sample_rows,
+        header_likely_missing: false,
+        column_samples,
+        warnings,
+        metadata: HashMap::new(),
+        sheet_names: workbook.sheet_names(),
+        sheet_name: Some(sheet_name),
     })
 }
 
@@ -199,12 +559,7 @@ where
     RS: Read + Seek,
     R::Error: std::fmt::Display,
 {
-    let range = workbook
-        .worksheet_range_at(0)
-        .ok_or_else(|| {
-            BomProcessorError::FileReadError("ワークシートが見つかりません".to_string())
-        })?
-        .map_err(|e: R::Error| BomProcessorError::FileReadError(e.to_string()))?;
+    let (_, range) = first_non_empty_sheet(workbook)?;
 
     let mut headers: Vec<String> = Vec::new();
     let mut rows: Vec<Vec<String>> = Vec::new();
@@ -225,36 +580,30 @@ where
 
 async fn analyze_csv_file(
     file_path: &str,
-    dictionary: &ColumnDictionary,
+    dictionary: &CompiledDictionary,
+    has_headers: bool,
+    heuristic_only: bool,
 ) -> Result<FileAnalysis, BomProcessorError> {
-    let content =
-        fs::read(file_path).map_err(|e| BomProcessorError::FileReadError(e.to_string()))?;
-
-    let decoded = if content.starts_with(&[0xEF, 0xBB, 0xBF]) {
-        UTF_8.decode(&content[3..]).0
-    } else if content.starts_with(&[0xFF, 0xFE]) {
-        return Err(BomProcessorError::EncodingError(
-            "UTF-16エンコーディングはサポートされていません".to_string(),
-        ));
-    } else {
-        let utf8_result = UTF_8.decode(&content);
-        if utf8_result.2 {
-            utf8_result.0
-        } else {
-            SHIFT_JIS.decode(&content).0
-        }
-    };
+    let content = fs::read(file_path)?;
+
+    let decoded = decode_text(&content)?;
 
     let mut reader = ReaderBuilder::new()
-        .has_headers(true)
+        .delimiter(text_delimiter(file_path, &decoded))
+        .has_headers(has_headers)
+        .flexible(!has_headers)
         .from_reader(decoded.as_bytes());
 
-    let headers = reader
-        .headers()
-        .map_err(|e| BomProcessorError::FileReadError(e.to_string()))?
-        .iter()
-        .map(|h| h.to_string())
-        .collect::<Vec<_>>();
+    let mut headers = if has_headers {
+        reader
+            .headers()
+            .map_err(|e| BomProcessorError::FileReadError(e.to_string()))?
+            .iter()
+            .map(|h| h.to_string())
+            .collect::<Vec<_>>()
+    } else {
+        Vec::new()
+    };
 
     let mut sample_rows = Vec::new();
     for record in reader.records() {
@@ -269,35 +618,135 @@ async fn analyze_csv_file(
         }
     }
 
-    let suggested_mapping = detect_column_mapping(&headers, &sample_rows, dictionary);
+    if !has_headers {
+        let column_count = sample_rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        headers = synthesized_headers(column_count);
+    }
+
+    let suggested_mapping =
+        detect_column_mapping(&headers, &sample_rows, dictionary, heuristic_only).map(|mapping| {
+            ColumnMapping {
+                has_headers,
+                ..mapping
+            }
+        });
+    let header_likely_missing = has_headers && headers_look_like_data(&headers, dictionary);
+
+    let column_samples = collect_column_samples(headers.len(), &sample_rows);
+    let warnings = part_column_warnings(
+        suggested_mapping
+            .as_ref()
+            .map(|mapping| mapping.part_number),
+        &sample_rows,
+    );
 
     Ok(FileAnalysis {
         headers,
         suggested_mapping,
         sample_rows,
+        header_likely_missing,
+        column_samples,
+        warnings,
+        metadata: HashMap::new(),
+        sheet_names: Vec::new(),
+        sheet_name: None,
     })
 }
 
-async fn preview_csv_file(file_path: &str, limit: usize) -> Result<FilePreview, BomProcessorError> {
-    let content =
-        fs::read(file_path).map_err(|e| BomProcessorError::FileReadError(e.to_string()))?;
-
-    let decoded = if content.starts_with(&[0xEF, 0xBB, 0xBF]) {
-        UTF_8.decode(&content[3..]).0
-    } else if content.starts_with(&[0xFF, 0xFE]) {
-        return Err(BomProcessorError::EncodingError(
-            "UTF-16エンコーディングはサポートされていません".to_string(),
-        ));
-    } else {
-        let utf8_result = UTF_8.decode(&content);
-        if utf8_result.2 {
-            utf8_result.0
-        } else {
-            SHIFT_JIS.decode(&content).0
+/// 部品番号列の値がほぼ数字のみで重複が多い場合は、数量列を部品番号に指定した可能性が高い
+fn looks_like_quantity_column(col_idx: usize, rows: &[Vec<String>]) -> bool {
+    let normalized: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| row.iter().map(|value| normalize_token(value)).collect())
+        .collect();
+    let mut numeric = 0usize;
+    let mut total = 0usize;
+    for value in normalized.iter().filter_map(|row| row.get(col_idx)) {
+        if value.is_empty() {
+            continue;
         }
-    };
+        total += 1;
+        if value.chars().all(|c| c.is_ascii_digit()) {
+            numeric += 1;
+        }
+    }
+    total > 0
+        && numeric as f32 / total as f32 > 0.8
+        && compute_uniqueness_ratio(col_idx, &normalized) < 0.5
+}
+
+/// 列指定の確認を促す警告（読み込み・推定は止めない）
+fn part_column_warnings(part_column: Option<usize>, rows: &[Vec<String>]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let Some(col_idx) = part_column {
+        if looks_like_quantity_column(col_idx, rows) {
+            warnings.push(
+                "部品番号列の値の大半が数字のみで重複が多いため、数量列を指定している可能性があります。列指定を確認してください"
+                    .to_string(),
+            );
+        }
+    }
+    warnings
+}
+
+/// サンプル行を列ごとに転置し、空でない重複なしの値を最大 MAX_COLUMN_SAMPLES 件ずつ集める
+fn collect_column_samples(header_count: usize, rows: &[Vec<String>]) -> Vec<Vec<String>> {
+    let column_count = header_count.max(rows.iter().map(|row| row.len()).max().unwrap_or(0));
+    (0..column_count)
+        .map(|idx| {
+            let mut values: Vec<String> = Vec::new();
+            for value in rows.iter().filter_map(|row| row.get(idx)) {
+                if values.len() >= MAX_COLUMN_SAMPLES {
+                    break;
+                }
+                if !value.trim().is_empty() && !values.contains(value) {
+                    values.push(value.clone());
+                }
+            }
+            values
+        })
+        .collect()
+}
+
+/// ヘッダーなしファイル用の列名（列1, 列2, ...）
+fn synthesized_headers(column_count: usize) -> Vec<String> {
+    (1..=column_count).map(|idx| format!("列{}", idx)).collect()
+}
+
+/// 先頭行がヘッダーではなくデータ行に見えるかを判定する
+/// 辞書の列名に一致するセルがなく、半数以上のセルに数字を含む場合にデータ行とみなす
+fn headers_look_like_data(headers: &[String], dictionary: &CompiledDictionary) -> bool {
+    let cells: Vec<&String> = headers.iter().filter(|h| !h.trim().is_empty()).collect();
+    if cells.is_empty() {
+        return false;
+    }
+
+    let matches_dictionary = cells.iter().any(|cell| {
+        let token = normalize_token(cell);
+        !token.is_empty()
+            && ["part_number", "model_number", "manufacturer"]
+                .iter()
+                .flat_map(|column_type| dictionary.patterns(column_type))
+                .any(|pattern| token.contains(pattern.as_str()))
+    });
+    if matches_dictionary {
+        return false;
+    }
+
+    let numeric_cells = cells
+        .iter()
+        .filter(|cell| cell.chars().any(|c| c.is_ascii_digit()))
+        .count();
+    numeric_cells * 2 >= cells.len()
+}
+
+async fn preview_csv_file(file_path: &str, limit: usize) -> Result<FilePreview, BomProcessorError> {
+    let content = fs::read(file_path)?;
+
+    let decoded = decode_text(&content)?;
 
     let mut reader = ReaderBuilder::new()
+        .delimiter(text_delimiter(file_path, &decoded))
         .has_headers(true)
         .from_reader(decoded.as_bytes());
 
@@ -324,6 +773,7 @@ async fn preview_csv_file(file_path: &str, limit: usize) -> Result<FilePreview,
 async fn load_excel_file(
     file_path: &str,
     column_mapping: &ColumnMapping,
+    sheet: Option<&SheetSelector>,
 ) -> Result<LoadBomResult, BomProcessorError> {
     let extension = Path::new(file_path)
         .extension()
@@ -336,13 +786,13 @@ async fn load_excel_file(
             let mut workbook: Xlsx<_> = open_workbook(file_path)
                 .map_err(|e: XlsxError| BomProcessorError::FileReadError(e.to_string()))?;
 
-            load_excel_workbook(&mut workbook, column_mapping)
+            load_excel_workbook(&mut workbook, column_mapping, sheet)
         }
         "xls" => {
             let mut workbook: Xls<_> = open_workbook(file_path)
                 .map_err(|e: XlsError| BomProcessorError::FileReadError(e.to_string()))?;
 
-            load_excel_workbook(&mut workbook, column_mapping)
+            load_excel_workbook(&mut workbook, column_mapping, sheet)
         }
         _ => Err(BomProcessorError::FormatError(
             "Excelファイルの拡張子が無効です".to_string(),
@@ -354,18 +804,14 @@ async fn load_excel_file(
 fn load_excel_workbook<R, RS>(
     workbook: &mut R,
     column_mapping: &ColumnMapping,
+    sheet: Option<&SheetSelector>,
 ) -> Result<LoadBomResult, BomProcessorError>
 where
     R: Reader<RS>,
     RS: Read + Seek,
     R::Error: std::fmt::Display,
 {
-    let range = workbook
-        .worksheet_range_at(0)
-        .ok_or_else(|| {
-            BomProcessorError::FileReadError("ワークシートが見つかりません".to_string())
-        })?
-        .map_err(|e: R::Error| BomProcessorError::FileReadError(e.to_string()))?;
+    let (sheet_name, range) = select_sheet(workbook, sheet)?;
 
     let mut headers = Vec::new();
     let mut raw_rows: Vec<Vec<String>> = Vec::new();
@@ -379,13 +825,107 @@ where
         raw_rows.push(row_values);
     }
 
-    build_bom_from_rows(headers, raw_rows, column_mapping)
+    let mut result = build_bom_from_rows(headers, raw_rows, column_mapping)?;
+    result.sheet_name = Some(sheet_name);
+    Ok(result)
+}
+
+/// 指定されたワークシートを読み込む（未指定の場合はデータのある先頭のワークシート）
+fn select_sheet<R, RS>(
+    workbook: &mut R,
+    sheet: Option<&SheetSelector>,
+) -> Result<(String, Range<Data>), BomProcessorError>
+where
+    R: Reader<RS>,
+    RS: Read + Seek,
+    R::Error: std::fmt::Display,
+{
+    let selector = match sheet {
+        Some(selector) => selector,
+        None => return first_non_empty_sheet(workbook),
+    };
+    let sheet_names = workbook.sheet_names();
+    let index = find_sheet_index(&sheet_names, selector)?;
+    let name = sheet_names[index].clone();
+    let range = workbook
+        .worksheet_range(&name)
+        .map_err(|e: R::Error| BomProcessorError::FileReadError(e.to_string()))?;
+    Ok((name, range))
+}
+
+/// シート名（完全一致、なければ前後の空白と大文字・小文字を無視）または番号からシートの位置を求める
+fn find_sheet_index(
+    sheet_names: &[String],
+    selector: &SheetSelector,
+) -> Result<usize, BomProcessorError> {
+    let found =
+        match selector {
+            SheetSelector::Index(index) => Some(*index).filter(|index| *index < sheet_names.len()),
+            SheetSelector::Name(name) => sheet_names
+                .iter()
+                .position(|sheet| sheet == name)
+                .or_else(|| {
+                    let target = name.trim().to_lowercase();
+                    sheet_names
+                        .iter()
+                        .position(|sheet| sheet.trim().to_lowercase() == target)
+                }),
+        };
+    found.ok_or_else(|| {
+        let requested = match selector {
+            SheetSelector::Index(index) => format!("{}番目", index + 1),
+            SheetSelector::Name(name) => format!("「{}」", name),
+        };
+        BomProcessorError::InvalidInput(format!(
+            "ワークシート{}が見つかりません（シート: {}）",
+            requested,
+            sheet_names.join(", ")
+        ))
+    })
+}
+
+/// 先頭から順にデータのあるワークシートを探す（先頭シートが空でデータが2枚目以降にあるテンプレート対策）
+fn first_non_empty_sheet<R, RS>(
+    workbook: &mut R,
+) -> Result<(String, Range<Data>), BomProcessorError>
+where
+    R: Reader<RS>,
+    RS: Read + Seek,
+    R::Error: std::fmt::Display,
+{
+    let sheet_names = workbook.sheet_names();
+    if sheet_names.is_empty() {
+        return Err(BomProcessorError::FileReadError(
+            "ワークシートが見つかりません".to_string(),
+        ));
+    }
+
+    for (index, name) in sheet_names.iter().enumerate() {
+        let range = workbook
+            .worksheet_range(name)
+            .map_err(|e: R::Error| BomProcessorError::FileReadError(e.to_string()))?;
+        if range.used_cells().next().is_none() {
+            continue;
+        }
+        if index > 0 {
+            log::info!(
+                "[excel] 先頭のワークシートが空のため「{}」を使用します",
+                name
+            );
+        }
+        return Ok((name.clone(), range));
+    }
+
+    Err(BomProcessorError::FileReadError(
+        "データのあるワークシートが見つかりません".to_string(),
+    ))
 }
 
 fn detect_column_mapping(
     headers: &[String],
     rows: &[Vec<String>],
-    dictionary: &ColumnDictionary,
+    dictionary: &CompiledDictionary,
+    heuristic_only: bool,
 ) -> Option<ColumnMapping> {
     let max_columns = headers
         .len()
@@ -395,50 +935,128 @@ fn detect_column_mapping(
         return None;
     }
 
+    let sample = NormalizedSample::new(headers, rows);
     let mut used: HashSet<usize> = HashSet::new();
 
-    let part_idx = choose_column_from_dictionary("part_number", headers, rows, dictionary, &used)
+    if heuristic_only {
+        let part_idx = find_unique_text_column(&sample, rows, &used)
+            .or_else(|| find_text_column(max_columns, rows, &used))?;
+        used.insert(part_idx);
+        let model_idx = find_text_column(max_columns, rows, &used)?;
+
+        return Some(ColumnMapping {
+            part_number: part_idx,
+            model_number: model_idx,
+            manufacturer: None,
+            quantity: None,
+            keep_columns: None,
+            has_headers: true,
+            verbatim_columns: Vec::new(),
+            date_columns: Vec::new(),
+            split_column: None,
+            whitespace_modes: Vec::new(),
+        });
+    }
+
+    let part_idx = choose_column_from_dictionary("part_number", &sample, dictionary, &used)
         .map(|(idx, _)| idx)
         .or_else(|| find_text_column(max_columns, rows, &used))?;
     used.insert(part_idx);
 
-    let model_idx = choose_column_from_dictionary("model_number", headers, rows, dictionary, &used)
+    let model_idx = choose_column_from_dictionary("model_number", &sample, dictionary, &used)
         .map(|(idx, _)| idx)
         .or_else(|| find_text_column(max_columns, rows, &used))?;
     used.insert(model_idx);
 
     let manufacturer_idx =
-        choose_column_from_dictionary("manufacturer", headers, rows, dictionary, &used)
+        choose_column_from_dictionary("manufacturer", &sample, dictionary, &used)
             .map(|(idx, _)| idx);
 
     Some(ColumnMapping {
         part_number: part_idx,
         model_number: model_idx,
         manufacturer: manufacturer_idx,
+        quantity: None,
+        keep_columns: None,
+        has_headers: true,
+        verbatim_columns: Vec::new(),
+        date_columns: Vec::new(),
+        split_column: None,
+        whitespace_modes: Vec::new(),
     })
 }
 
+/// 列推定用に列種別ごとのパターンを正規化済みで保持する辞書
+/// 辞書の保存・取り込み時にのみ作り直し、解析のたびの再正規化を避ける
+#[derive(Debug, Default)]
+pub struct CompiledDictionary {
+    patterns: HashMap<String, Vec<String>>,
+}
+
+impl CompiledDictionary {
+    pub fn new(dictionary: &ColumnDictionary) -> Self {
+        let mut patterns: HashMap<String, Vec<String>> = HashMap::new();
+        for entry in &dictionary.columns {
+            let column_type = entry.column_type.trim().to_lowercase();
+            if patterns.contains_key(&column_type) {
+                // patterns_for と同じく最初の項目を優先する
+                continue;
+            }
+            let normalized = entry
+                .patterns
+                .iter()
+                .map(|p| normalize_token(p))
+                .filter(|p| !p.is_empty())
+                .collect();
+            patterns.insert(column_type, normalized);
+        }
+        Self { patterns }
+    }
+
+    fn patterns(&self, column_type: &str) -> &[String] {
+        self.patterns
+            .get(&column_type.trim().to_lowercase())
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+}
+
+/// 列推定用に正規化済みのヘッダーとサンプル行（列・パターンごとの再正規化を避ける）
+struct NormalizedSample {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    max_columns: usize,
+}
+
+impl NormalizedSample {
+    fn new(headers: &[String], rows: &[Vec<String>]) -> Self {
+        let max_columns = headers
+            .len()
+            .max(rows.iter().map(|row| row.len()).max().unwrap_or(0));
+        Self {
+            headers: headers.iter().map(|h| normalize_token(h)).collect(),
+            rows: rows
+                .iter()
+                .map(|row| row.iter().map(|value| normalize_token(value)).collect())
+                .collect(),
+            max_columns,
+        }
+    }
+}
+
 fn choose_column_from_dictionary(
     column_type: &str,
-    headers: &[String],
-    rows: &[Vec<String>],
-    dictionary: &ColumnDictionary,
+    sample: &NormalizedSample,
+    dictionary: &CompiledDictionary,
     used: &HashSet<usize>,
 ) -> Option<(usize, f32)> {
-    let max_columns = headers
-        .len()
-        .max(rows.iter().map(|row| row.len()).max().unwrap_or(0));
+    let max_columns = sample.max_columns;
 
     if max_columns == 0 {
         return None;
     }
 
-    let patterns: Vec<String> = dictionary
-        .patterns_for(column_type)
-        .into_iter()
-        .map(|p| normalize_token(&p))
-        .filter(|p| !p.is_empty())
-        .collect();
+    let patterns = dictionary.patterns(column_type);
 
     let mut best: Option<(usize, f32)> = None;
 
@@ -446,10 +1064,7 @@ fn choose_column_from_dictionary(
         if used.contains(&idx) {
             continue;
         }
-        let header_norm = headers
-            .get(idx)
-            .map(|h| normalize_token(h))
-            .unwrap_or_default();
+        let header_norm = sample.headers.get(idx).map(String::as_str).unwrap_or("");
 
         let mut score = 0.0f32;
 
@@ -457,17 +1072,13 @@ fn choose_column_from_dictionary(
             let mut header_matches = 0f32;
             let mut value_ratio_total = 0f32;
 
-            for pattern in &patterns {
-                if pattern.is_empty() {
-                    continue;
-                }
-
-                if header_norm.contains(pattern) || pattern.contains(&header_norm) {
+            for pattern in patterns {
+                if header_norm.contains(pattern.as_str()) || pattern.contains(header_norm) {
                     header_matches += 1.0;
                     continue;
                 }
 
-                let (matches, total) = count_pattern_matches(idx, rows, pattern);
+                let (matches, total) = count_pattern_matches(idx, &sample.rows, pattern);
                 if total > 0 {
                     value_ratio_total += matches as f32 / total as f32;
                 }
@@ -482,7 +1093,7 @@ fn choose_column_from_dictionary(
 
         if column_type.eq_ignore_ascii_case("part_number") {
             // Penalize columns with very few unique textual values
-            let uniqueness = compute_uniqueness_ratio(idx, rows);
+            let uniqueness = compute_uniqueness_ratio(idx, &sample.rows);
             if uniqueness > 0.0 {
                 score += uniqueness * 0.3;
             }
@@ -510,12 +1121,12 @@ fn normalize_token(value: &str) -> String {
         .collect::<String>()
 }
 
+/// `rows` は正規化済みのサンプル行
 fn count_pattern_matches(col_idx: usize, rows: &[Vec<String>], pattern: &str) -> (usize, usize) {
     let mut matches = 0usize;
     let mut total = 0usize;
     for row in rows {
-        if let Some(value) = row.get(col_idx) {
-            let normalized = normalize_token(value);
+        if let Some(normalized) = row.get(col_idx) {
             if normalized.is_empty() {
                 continue;
             }
@@ -528,12 +1139,12 @@ fn count_pattern_matches(col_idx: usize, rows: &[Vec<String>], pattern: &str) ->
     (matches, total)
 }
 
+/// `rows` は正規化済みのサンプル行
 fn compute_uniqueness_ratio(col_idx: usize, rows: &[Vec<String>]) -> f32 {
     let mut unique = HashSet::new();
     let mut total = 0usize;
     for row in rows {
-        if let Some(value) = row.get(col_idx) {
-            let normalized = normalize_token(value);
+        if let Some(normalized) = row.get(col_idx) {
             if normalized.is_empty() {
                 continue;
             }
@@ -548,30 +1159,52 @@ fn compute_uniqueness_ratio(col_idx: usize, rows: &[Vec<String>]) -> f32 {
     }
 }
 
-fn find_text_column(
-    max_columns: usize,
-    rows: &[Vec<String>],
-    used: &HashSet<usize>,
+/// 文字を含む値が3割以上ある列を文字列列とみなす
+fn is_text_column(idx: usize, rows: &[Vec<String>]) -> bool {
+    let mut score = 0usize;
+    let mut total = 0usize;
+    for row in rows {
+        if let Some(value) = row.get(idx) {
+            let trimmed = value.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            total += 1;
+            if trimmed.chars().any(|c| c.is_alphabetic()) {
+                score += 1;
+            }
+        }
+    }
+    total > 0 && (score as f32 / total as f32) >= 0.3
+}
+
+/// 文字列列のうち値の一意性が最も高い列（同率の場合は左側）を返す
+fn find_unique_text_column(
+    sample: &NormalizedSample,
+    rows: &[Vec<String>],
+    used: &HashSet<usize>,
 ) -> Option<usize> {
-    for idx in 0..max_columns {
-        if used.contains(&idx) {
+    let mut best: Option<(usize, f32)> = None;
+    for idx in 0..sample.max_columns {
+        if used.contains(&idx) || !is_text_column(idx, rows) {
             continue;
         }
-        let mut score = 0usize;
-        let mut total = 0usize;
-        for row in rows {
-            if let Some(value) = row.get(idx) {
-                let trimmed = value.trim();
-                if trimmed.is_empty() {
-                    continue;
-                }
-                total += 1;
-                if trimmed.chars().any(|c| c.is_alphabetic()) {
-                    score += 1;
-                }
-            }
+        let uniqueness = compute_uniqueness_ratio(idx, &sample.rows);
+        match best {
+            Some((_, best_score)) if uniqueness <= best_score => {}
+            _ => best = Some((idx, uniqueness)),
         }
-        if total > 0 && (score as f32 / total as f32) >= 0.3 {
+    }
+    best.map(|(idx, _)| idx)
+}
+
+fn find_text_column(
+    max_columns: usize,
+    rows: &[Vec<String>],
+    used: &HashSet<usize>,
+) -> Option<usize> {
+    for idx in 0..max_columns {
+        if !used.contains(&idx) && is_text_column(idx, rows) {
             return Some(idx);
         }
     }
@@ -585,66 +1218,214 @@ fn find_text_column(
     None
 }
 
-/// CSVファイルを読み込む
+/// テキスト形式の部品表の区切り文字（.tsv はタブ、.txt は先頭行にタブがあればタブ、それ以外はカンマ）
+fn text_delimiter(file_path: &str, content: &str) -> u8 {
+    let extension = Path::new(file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    match extension.as_str() {
+        "tsv" => b'\t',
+        "txt" => {
+            let first_line = content.lines().find(|line| !line.trim().is_empty());
+            if first_line.is_some_and(|line| line.contains('\t')) {
+                b'\t'
+            } else {
+                b','
+            }
+        }
+        _ => b',',
+    }
+}
+
+/// テキストファイルの文字コードと、先頭から読み飛ばすBOMのバイト数を判定する
+/// BOMがあればそれに従い（UTF-8 / UTF-16 LE / UTF-16 BE）、なければUTF-8として不正なバイトがあるかで
+/// UTF-8とShift-JISを判別する。先頭部分だけを渡した場合に末尾で文字が途切れていてもUTF-8とみなす
+fn detect_text_encoding(head: &[u8]) -> (&'static Encoding, usize) {
+    if head.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        (UTF_8, 3)
+    } else if head.starts_with(&[0xFF, 0xFE]) {
+        (UTF_16LE, 2)
+    } else if head.starts_with(&[0xFE, 0xFF]) {
+        (UTF_16BE, 2)
+    } else {
+        match std::str::from_utf8(head) {
+            Ok(_) => (UTF_8, 0),
+            Err(e) if e.error_len().is_none() => (UTF_8, 0),
+            Err(_) => (SHIFT_JIS, 0),
+        }
+    }
+}
+
+/// ファイル全体を文字コードを判定してUTF-8の文字列にする（BOMは取り除く）
+/// UTF-16のBOMがあるのに変換できないバイトが含まれる場合はエラーにする
+fn decode_text(content: &[u8]) -> Result<std::borrow::Cow<'_, str>, BomProcessorError> {
+    let (encoding, skip) = detect_text_encoding(content);
+    let (decoded, had_errors) = encoding.decode_without_bom_handling(&content[skip..]);
+    if had_errors && (encoding == UTF_16LE || encoding == UTF_16BE) {
+        return Err(BomProcessorError::EncodingError(format!(
+            "{}として読み込めないデータが含まれています",
+            encoding.name()
+        )));
+    }
+    Ok(decoded)
+}
+
+/// 読みながらUTF-8に変換するReader（大きなCSVをファイル全体と変換後の文字列の両方でメモリに載せないため）
+struct DecodingReader<R> {
+    inner: R,
+    decoder: encoding_rs::Decoder,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    position: usize,
+    finished: bool,
+    /// 変換できないバイトを置換文字にして読み進める（Shift-JIS のみ。decode_text と同じ扱い）
+    lenient: bool,
+}
+
+impl<R: Read> DecodingReader<R> {
+    fn new(inner: R, encoding: &'static Encoding, chunk_size: usize) -> Self {
+        Self {
+            inner,
+            lenient: encoding == SHIFT_JIS,
+            decoder: encoding.new_decoder_without_bom_handling(),
+            input: vec![0; chunk_size.max(1)],
+            output: Vec::new(),
+            position: 0,
+            finished: false,
+        }
+    }
+}
+
+impl<R: Read> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.position >= self.output.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            let read = self.inner.read(&mut self.input)?;
+            let last = read == 0;
+            // 入力をすべて変換できる大きさを確保する（文字の途中で切れたバイトはデコーダーが保持する）
+            let capacity = self
+                .decoder
+                .max_utf8_buffer_length(read)
+                .unwrap_or(read * 3 + 16);
+            self.output.resize(capacity, 0);
+            let (_, _, written, had_replacements) =
+                self.decoder
+                    .decode_to_utf8(&self.input[..read], &mut self.output, last);
+            // 先頭部分で判定した文字コードと後続のデータが合わない場合は、途中から文字化けさせずに中止する
+            if had_replacements && !self.lenient {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "{}として読み込めないデータがファイルの途中に含まれています（文字コードは先頭部分で判定しています）",
+                        self.decoder.encoding().name()
+                    ),
+                ));
+            }
+            self.output.truncate(written);
+            self.position = 0;
+            self.finished = last;
+        }
+        let count = buf.len().min(self.output.len() - self.position);
+        buf[..count].copy_from_slice(&self.output[self.position..self.position + count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+/// 大きなCSVを順に読むためのReaderと、区切り文字の判定に使う先頭部分の文字列を返す
+/// 文字コードは先頭部分だけで判定し、後続に判定と合わないデータがあれば読み込み時にエラーとする
+fn open_streaming_csv(file_path: &str) -> Result<(Box<dyn Read>, String), BomProcessorError> {
+    let mut file = fs::File::open(file_path)?;
+    let mut head = Vec::with_capacity(CSV_STREAM_CHUNK_SIZE);
+    (&mut file)
+        .take(CSV_STREAM_CHUNK_SIZE as u64)
+        .read_to_end(&mut head)
+        .map_err(|e| BomProcessorError::FileReadError(e.to_string()))?;
+
+    let (encoding, skip) = detect_text_encoding(&head);
+
+    let head = head.split_off(skip);
+    let head_text = encoding.decode_without_bom_handling(&head).0.into_owned();
+    let reader = DecodingReader::new(
+        Cursor::new(head).chain(file),
+        encoding,
+        CSV_STREAM_CHUNK_SIZE,
+    );
+    Ok((Box::new(reader), head_text))
+}
+
+/// CSVファイルを読み込む（タブ区切りの .tsv / .txt も含む）
+/// CSV_STREAMING_THRESHOLD を超えるファイルは一括で読み込まず、変換しながら順に読む
 async fn load_csv_file(
     file_path: &str,
     column_mapping: &ColumnMapping,
+    file_size: u64,
 ) -> Result<LoadBomResult, BomProcessorError> {
-    let content =
-        fs::read(file_path).map_err(|e| BomProcessorError::FileReadError(e.to_string()))?;
-
-    // エンコーディングを自動検出
-    let (decoded_content, _, _) = if content.starts_with(&[0xEF, 0xBB, 0xBF]) {
-        // UTF-8 BOM
-        (UTF_8.decode(&content[3..]).0, UTF_8, true)
-    } else if content.starts_with(&[0xFF, 0xFE]) {
-        // UTF-16 LE BOM
-        return Err(BomProcessorError::EncodingError(
-            "UTF-16エンコーディングはサポートされていません".to_string(),
-        ));
+    let (text, delimiter): (Box<dyn Read>, u8) = if file_size > CSV_STREAMING_THRESHOLD {
+        let (text, head) = open_streaming_csv(file_path)?;
+        (text, text_delimiter(file_path, &head))
     } else {
-        // まずUTF-8として試行
-        let utf8_result = UTF_8.decode(&content);
-        if utf8_result.2 {
-            (utf8_result.0, UTF_8, false)
-        } else {
-            // UTF-8で失敗した場合はShift-JISとして試行
-            let sjis_result = SHIFT_JIS.decode(&content);
-            (sjis_result.0, SHIFT_JIS, false)
-        }
+        let content = fs::read(file_path)?;
+
+        // エンコーディングを自動検出
+        let decoded_content = decode_text(&content)?;
+        let delimiter = text_delimiter(file_path, &decoded_content);
+        (
+            Box::new(Cursor::new(decoded_content.into_owned().into_bytes())),
+            delimiter,
+        )
     };
 
     let mut reader = ReaderBuilder::new()
-        .has_headers(true)
-        .from_reader(decoded_content.as_bytes());
+        .delimiter(delimiter)
+        .has_headers(column_mapping.has_headers)
+        .flexible(!column_mapping.has_headers)
+        .from_reader(text);
 
     let mut headers = Vec::new();
     let mut raw_rows = Vec::new();
 
-    // ヘッダーを取得
-    if let Some(result) = reader.headers().ok() {
-        headers = result.iter().map(|s| s.to_string()).collect();
+    // ヘッダーを取得（ヘッダーなしの場合は build_bom_from_rows で「列N」を付与する）
+    if column_mapping.has_headers {
+        if let Some(result) = reader.headers().ok() {
+            headers = result.iter().map(|s| s.to_string()).collect();
+        }
     }
 
     // データ行を処理
     for result in reader.records() {
-        let record = result.map_err(|e| BomProcessorError::FileReadError(e.to_string()))?;
+        let record = result.map_err(|e| match e.kind() {
+            csv::ErrorKind::Io(err) if err.kind() == std::io::ErrorKind::InvalidData => {
+                BomProcessorError::EncodingError(err.to_string())
+            }
+            _ => BomProcessorError::FileReadError(e.to_string()),
+        })?;
         raw_rows.push(record.iter().map(|value| value.to_string()).collect());
     }
 
     build_bom_from_rows(headers, raw_rows, column_mapping)
 }
 
-fn build_bom_from_rows(
+pub(crate) fn build_bom_from_rows(
     mut headers: Vec<String>,
     raw_rows: Vec<Vec<String>>,
     column_mapping: &ColumnMapping,
 ) -> Result<LoadBomResult, BomProcessorError> {
-    let mut max_required_index = column_mapping.part_number.max(column_mapping.model_number);
+    let mut max_required_index = match column_mapping.split_column {
+        Some((split_idx, _)) => split_idx,
+        None => column_mapping.part_number.max(column_mapping.model_number),
+    };
 
     if let Some(manufacturer_idx) = column_mapping.manufacturer {
         max_required_index = max_required_index.max(manufacturer_idx);
     }
+    if let Some(quantity_idx) = column_mapping.quantity {
+        max_required_index = max_required_index.max(quantity_idx);
+    }
 
     let mut max_columns = raw_rows
         .iter()
@@ -669,8 +1450,12 @@ fn build_bom_from_rows(
         ));
     }
 
+    let retained = retained_columns(column_mapping, headers.len());
+    let is_verbatim = |idx: usize| column_mapping.verbatim_columns.contains(&idx);
+
     let mut rows = Vec::new();
     let mut corrections = Vec::new();
+    let mut skipped_empty_rows = Vec::new();
 
     for (row_idx, raw_row) in raw_rows.into_iter().enumerate() {
         let data_row_number = row_idx + 1;
@@ -679,8 +1464,14 @@ fn build_bom_from_rows(
 
         for (col_idx, header) in headers.iter().enumerate() {
             let original_value = raw_row.get(col_idx).cloned().unwrap_or_default();
-            let normalized = standardize_string(&original_value);
-            let rule = string_correction_rule(col_idx, column_mapping);
+            let (normalized, rule) = match normalize_cell(col_idx, &original_value, column_mapping)
+            {
+                Some(result) => result,
+                None => {
+                    cells[col_idx] = original_value;
+                    continue;
+                }
+            };
             record_string_correction(
                 &mut pending,
                 data_row_number,
@@ -693,42 +1484,219 @@ fn build_bom_from_rows(
             cells[col_idx] = normalized;
         }
 
-        if column_mapping.part_number >= headers.len()
-            || column_mapping.model_number >= headers.len()
+        let key_columns_out_of_range = match column_mapping.split_column {
+            Some((split_idx, _)) => split_idx >= headers.len(),
+            None => {
+                column_mapping.part_number >= headers.len()
+                    || column_mapping.model_number >= headers.len()
+            }
+        };
+        if key_columns_out_of_range
             || column_mapping
                 .manufacturer
                 .map(|idx| idx >= headers.len())
                 .unwrap_or(false)
+            || column_mapping
+                .quantity
+                .map(|idx| idx >= headers.len())
+                .unwrap_or(false)
         {
             return Err(BomProcessorError::ColumnError(
                 "列番号の指定がヘッダー数を超えています".to_string(),
             ));
         }
 
-        let part_number = cells[column_mapping.part_number].clone();
+        let (part_number, model_number) = match column_mapping.split_column {
+            Some((split_idx, delimiter)) => {
+                let composite = &cells[split_idx];
+                let fields: Vec<&str> = composite.split(delimiter).map(str::trim).collect();
+                if fields.len() != 2 && !composite.trim().is_empty() {
+                    pending.push(AutoCorrection {
+                        row_number: data_row_number,
+                        column_index: split_idx,
+                        column_name: headers[split_idx].clone(),
+                        original_value: composite.clone(),
+                        corrected_value: String::new(),
+                        rule: "split_column_mismatch".to_string(),
+                    });
+                }
+                (
+                    fields.first().copied().unwrap_or_default().to_string(),
+                    fields.get(1).copied().unwrap_or_default().to_string(),
+                )
+            }
+            None => (
+                cells[column_mapping.part_number].clone(),
+                cells[column_mapping.model_number].clone(),
+            ),
+        };
         if part_number.trim().is_empty() {
+            if cells.iter().any(|cell| !cell.trim().is_empty()) {
+                skipped_empty_rows.push(data_row_number);
+            }
             continue;
         }
 
-        let model_number = cells[column_mapping.model_number].clone();
-
-        let mut attributes = HashMap::new();
+        let mut attributes = IndexMap::new();
         for (idx, header) in headers.iter().enumerate() {
+            if !retained.contains(&idx) {
+                continue;
+            }
             attributes.insert(header.clone(), cells.get(idx).cloned().unwrap_or_default());
         }
 
+        let mut quantity = None;
+        if let Some(idx) = column_mapping.quantity {
+            let raw_quantity = cells[idx].trim();
+            if !raw_quantity.is_empty() {
+                quantity = parse_quantity(raw_quantity);
+                if quantity.is_none() {
+                    pending.push(AutoCorrection {
+                        row_number: data_row_number,
+                        column_index: idx,
+                        column_name: headers[idx].clone(),
+                        original_value: raw_quantity.to_string(),
+                        corrected_value: String::new(),
+                        rule: "invalid_quantity".to_string(),
+                    });
+                }
+            }
+        }
+
         rows.push(BomRow {
             part_number,
             model_number,
             attributes,
+            quantity,
         });
 
         corrections.extend(pending.into_iter());
     }
 
+    let position = |idx: usize| retained.iter().position(|&kept| kept == idx).unwrap_or(idx);
+    // 分割指定時は部品番号・型番とも分割元の列を指す
+    let (part_column, model_column) = match column_mapping.split_column {
+        Some((split_idx, _)) => (split_idx, split_idx),
+        None => (column_mapping.part_number, column_mapping.model_number),
+    };
+    let effective_mapping = ColumnMapping {
+        part_number: position(part_column),
+        model_number: position(model_column),
+        manufacturer: column_mapping.manufacturer.map(position),
+        quantity: column_mapping.quantity.map(position),
+        keep_columns: None,
+        has_headers: column_mapping.has_headers,
+        verbatim_columns: column_mapping
+            .verbatim_columns
+            .iter()
+            .filter(|idx| retained.contains(idx))
+            .map(|&idx| position(idx))
+            .collect(),
+        date_columns: column_mapping
+            .date_columns
+            .iter()
+            .filter(|idx| retained.contains(idx))
+            .map(|&idx| position(idx))
+            .collect(),
+        split_column: column_mapping
+            .split_column
+            .map(|(idx, delimiter)| (position(idx), delimiter)),
+        whitespace_modes: column_mapping
+            .whitespace_modes
+            .iter()
+            .filter(|(idx, _)| retained.contains(idx))
+            .map(|&(idx, mode)| (position(idx), mode))
+            .collect(),
+    };
+    let verbatim = VerbatimColumns {
+        part_number: is_verbatim(part_column),
+        model_number: is_verbatim(model_column),
+        attributes: column_mapping
+            .verbatim_columns
+            .iter()
+            .filter(|idx| retained.contains(idx))
+            .filter_map(|&idx| headers.get(idx).cloned())
+            .collect(),
+    };
+    let source_headers = headers.clone();
+    let headers = retained
+        .iter()
+        .filter_map(|&idx| headers.get(idx).cloned())
+        .collect();
+    let part_numbers: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row: &BomRow| vec![row.part_number.clone()])
+        .collect();
+    let mut warnings = part_column_warnings(Some(0), &part_numbers);
+    if !skipped_empty_rows.is_empty() {
+        warnings.push(format!(
+            "部品番号が空の {} 行をスキップしました",
+            skipped_empty_rows.len()
+        ));
+    }
+
     Ok(LoadBomResult {
-        bom: BomData { headers, rows },
+        bom: BomData {
+            headers,
+            rows,
+            verbatim,
+        },
         corrections,
+        column_mapping: effective_mapping,
+        sheet_name: None,
+        warnings,
+        skipped_empty_rows,
+        source_headers,
+    })
+}
+
+/// 部品表に保持する列番号（昇順）。keep_columns 未指定時は全列、指定時はキー列を加えた列
+fn retained_columns(mapping: &ColumnMapping, column_count: usize) -> Vec<usize> {
+    match mapping.keep_columns.as_ref() {
+        None => (0..column_count).collect(),
+        Some(keep) => {
+            let mut columns: Vec<usize> = keep
+                .iter()
+                .copied()
+                .chain([mapping.part_number, mapping.model_number])
+                .chain(mapping.split_column.map(|(idx, _)| idx))
+                .chain(mapping.manufacturer)
+                .chain(mapping.quantity)
+                .filter(|&idx| idx < column_count)
+                .collect();
+            columns.sort_unstable();
+            columns.dedup();
+            columns
+        }
+    }
+}
+
+/// 読み込み時と同じ規則でセル値を正規化し、適用した規則名とともに返す（原文保持列は None）
+fn normalize_cell(
+    column_index: usize,
+    value: &str,
+    mapping: &ColumnMapping,
+) -> Option<(String, &'static str)> {
+    if mapping.verbatim_columns.contains(&column_index) {
+        return None;
+    }
+    let date = mapping
+        .date_columns
+        .contains(&column_index)
+        .then(|| normalize_date(value))
+        .flatten();
+    let whitespace = mapping
+        .whitespace_modes
+        .iter()
+        .find(|(idx, _)| *idx == column_index)
+        .map(|&(_, mode)| mode)
+        .unwrap_or_default();
+    Some(match date {
+        Some(date) => (date, "normalize_date"),
+        None => (
+            standardize_string_with(value, whitespace),
+            string_correction_rule(column_index, mapping),
+        ),
     })
 }
 
@@ -764,9 +1732,67 @@ fn record_string_correction(
     });
 }
 
+/// Excelのシリアル値の起点（1900年うるう年問題を考慮した 1899-12-30）
+const EXCEL_EPOCH: (i32, u32, u32) = (1899, 12, 30);
+/// シリアル値として扱う上限（9999-12-31）
+const EXCEL_MAX_SERIAL: f64 = 2_958_465.0;
+
+/// 日付セルを ISO 形式（YYYY-MM-DD）に変換する。解釈できない場合は None
+/// 対応形式: "2024/1/5"、"2024-01-05"、"2024.1.5"、"2024年1月5日"、時刻付き、Excelのシリアル値
+pub fn normalize_date(input: &str) -> Option<String> {
+    let value = fullwidth_to_halfwidth(input.trim());
+    if value.is_empty() {
+        return None;
+    }
+
+    if let Ok(serial) = value.parse::<f64>() {
+        if !(1.0..=EXCEL_MAX_SERIAL).contains(&serial) {
+            return None;
+        }
+        let (year, month, day) = EXCEL_EPOCH;
+        let date = NaiveDate::from_ymd_opt(year, month, day)?
+            .checked_add_days(Days::new(serial.floor() as u64))?;
+        return Some(date.format("%Y-%m-%d").to_string());
+    }
+
+    let date_part = value
+        .split(|c: char| c.is_whitespace() || c == 'T')
+        .next()
+        .unwrap_or("");
+    let unified = date_part
+        .replace(['年', '月', '/', '／', '.'], "-")
+        .replace('日', "");
+    let parts: Vec<&str> = unified.split('-').filter(|p| !p.is_empty()).collect();
+    if parts.len() != 3 || parts[0].len() != 4 {
+        return None;
+    }
+    let year = parts[0].parse::<i32>().ok()?;
+    let month = parts[1].parse::<u32>().ok()?;
+    let day = parts[2].parse::<u32>().ok()?;
+    NaiveDate::from_ymd_opt(year, month, day).map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// 標準化時の空白の扱い
+/// strip: 空白をすべて削除する（従来の動作）
+/// collapse: 前後の空白を除き、連続する空白を1つにまとめる
+/// keep: 空白をそのまま残す
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WhitespaceMode {
+    #[default]
+    Strip,
+    Collapse,
+    Keep,
+}
+
 /// 文字列を標準化する
 pub fn standardize_string(input: &str) -> String {
-    input
+    standardize_string_with(input, WhitespaceMode::Strip)
+}
+
+/// 空白の扱いを指定して文字列を標準化する
+pub fn standardize_string_with(input: &str, whitespace: WhitespaceMode) -> String {
+    let converted = input
         .chars()
         .map(|c| {
             match c {
@@ -781,144 +1807,883 @@ pub fn standardize_string(input: &str) -> String {
                 _ => c,
             }
         })
-        .collect::<String>()
-        .replace(" ", "") // 空白を削除
-        .to_uppercase() // 大文字に変換
+        .collect::<String>();
+    let spaced = match whitespace {
+        WhitespaceMode::Strip => converted.replace(" ", ""),
+        WhitespaceMode::Collapse => converted.split_whitespace().collect::<Vec<_>>().join(" "),
+        WhitespaceMode::Keep => converted,
+    };
+    spaced.to_uppercase() // 大文字に変換
+}
+
+/// 重複した部品番号を統合する際の値の選び方
+/// first-wins: 先に出現した行の値を優先（空の属性のみ後の行で補完）
+/// last-wins: 後に出現した行の値で上書き
+/// concat: 異なる値を " / " で連結し、数量は合計する
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeStrategy {
+    FirstWins,
+    #[default]
+    LastWins,
+    Concat,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OptimizeResult {
+    pub rows_before: usize,
+    pub rows_after: usize,
+    /// 重複していたため統合した部品番号（部品番号順）
+    pub merged_parts: Vec<String>,
+}
+
+const CONCAT_SEPARATOR: &str = " / ";
+
+fn merge_value(existing: &mut String, incoming: String, strategy: MergeStrategy) {
+    match strategy {
+        MergeStrategy::FirstWins => {
+            if existing.trim().is_empty() {
+                *existing = incoming;
+            }
+        }
+        MergeStrategy::LastWins => *existing = incoming,
+        MergeStrategy::Concat => {
+            if incoming.trim().is_empty()
+                || existing
+                    .split(CONCAT_SEPARATOR)
+                    .any(|part| part == incoming)
+            {
+                return;
+            }
+            if existing.trim().is_empty() {
+                *existing = incoming;
+            } else {
+                existing.push_str(CONCAT_SEPARATOR);
+                existing.push_str(&incoming);
+            }
+        }
+    }
 }
 
-/// 部品表データを並列処理で最適化
-pub fn optimize_bom_data(bom_data: &mut BomData) {
-    let mut part_map: HashMap<String, BomRow> = HashMap::new();
+fn merge_rows(existing: &mut BomRow, incoming: BomRow, strategy: MergeStrategy) {
+    merge_value(&mut existing.model_number, incoming.model_number, strategy);
+    for (key, value) in incoming.attributes {
+        match existing.attributes.get_mut(&key) {
+            Some(current) => merge_value(current, value, strategy),
+            None => {
+                existing.attributes.insert(key, value);
+            }
+        }
+    }
+    existing.quantity = match (strategy, existing.quantity, incoming.quantity) {
+        (MergeStrategy::Concat, Some(a), Some(b)) => Some(a.saturating_add(b)),
+        (MergeStrategy::LastWins, current, next) => next.or(current),
+        (_, current, next) => current.or(next),
+    };
+}
 
-    for mut row in bom_data.rows.drain(..) {
-        part_map
-            .entry(row.part_number.clone())
-            .and_modify(|existing_row| {
-                for (key, value) in row.attributes.drain() {
-                    existing_row.attributes.insert(key, value);
+/// 部品表データを並列処理で最適化（重複した部品番号を統合し、部品番号順に並べ替える）
+pub fn optimize_bom_data(bom_data: &mut BomData, strategy: MergeStrategy) -> OptimizeResult {
+    let rows_before = bom_data.rows.len();
+    let mut part_map: IndexMap<String, BomRow> = IndexMap::new();
+    let mut merged_parts: Vec<String> = Vec::new();
+
+    for row in bom_data.rows.drain(..) {
+        match part_map.get_mut(&row.part_number) {
+            Some(existing_row) => {
+                if !merged_parts.contains(&row.part_number) {
+                    merged_parts.push(row.part_number.clone());
                 }
-            })
-            .or_insert(row);
+                merge_rows(existing_row, row, strategy);
+            }
+            None => {
+                part_map.insert(row.part_number.clone(), row);
+            }
+        }
     }
 
     bom_data.rows = part_map.into_values().collect();
+    merged_parts.sort();
 
     // 並列処理でソート
     bom_data
         .rows
         .par_sort_by(|a, b| a.part_number.cmp(&b.part_number));
+
+    OptimizeResult {
+        rows_before,
+        rows_after: bom_data.rows.len(),
+        merged_parts,
+    }
 }
 
-pub fn preprocess_bom_data(
-    bom_data: &BomData,
-    rules: &PreprocessRules,
-) -> Result<BomData, BomProcessorError> {
-    let mut processed_rows: Vec<BomRow> = Vec::new();
+/// 数字部分を数値として比較する自然順（"C2" < "C10"）
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut left = a.chars().peekable();
+    let mut right = b.chars().peekable();
+
+    loop {
+        match (left.peek().copied(), right.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) if l.is_ascii_digit() && r.is_ascii_digit() => {
+                let take_number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = chars.peek().copied().filter(|c| c.is_ascii_digit()) {
+                        digits.push(c);
+                        chars.next();
+                    }
+                    digits
+                };
+                let l_digits = take_number(&mut left);
+                let r_digits = take_number(&mut right);
+                let l_trimmed = l_digits.trim_start_matches('0');
+                let r_trimmed = r_digits.trim_start_matches('0');
+                let ordering = l_trimmed
+                    .len()
+                    .cmp(&r_trimmed.len())
+                    .then_with(|| l_trimmed.cmp(r_trimmed))
+                    .then_with(|| l_digits.len().cmp(&r_digits.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(l), Some(r)) => {
+                if l != r {
+                    return l.cmp(&r);
+                }
+                left.next();
+                right.next();
+            }
+        }
+    }
+}
 
-    for original in &bom_data.rows {
-        let mut base_row = original.clone();
+/// 指定列（part_number / model_number / 属性列名）の空でない値を重複なく自然順で返す
+pub fn unique_column_values(
+    bom: &BomData,
+    column_name: &str,
+) -> Result<Vec<String>, BomProcessorError> {
+    let values: Vec<&str> = match column_name {
+        "part_number" => bom
+            .rows
+            .iter()
+            .map(|row| row.part_number.as_str())
+            .collect(),
+        "model_number" => bom
+            .rows
+            .iter()
+            .map(|row| row.model_number.as_str())
+            .collect(),
+        _ => {
+            if !bom.headers.iter().any(|header| header == column_name) {
+                return Err(BomProcessorError::ColumnError(format!(
+                    "列「{}」が見つかりません",
+                    column_name
+                )));
+            }
+            bom.rows
+                .iter()
+                .filter_map(|row| row.attributes.get(column_name).map(|v| v.as_str()))
+                .collect()
+        }
+    };
 
-        base_row.part_number = apply_string_rules(&base_row.part_number, rules);
-        base_row.model_number = apply_string_rules(&base_row.model_number, rules);
+    let mut unique: Vec<String> = values
+        .into_iter()
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .map(|value| value.to_string())
+        .collect();
+    unique.sort_by(|a, b| natural_cmp(a, b));
+    Ok(unique)
+}
 
-        for value in base_row.attributes.values_mut() {
-            *value = apply_string_rules(value, rules);
+/// 行の並び順・属性の並び順に依存しない部品表の内容のハッシュ（SHA-256の16進文字列）
+/// 各行を (部品番号, 型番, 数量, 列名順に並べた属性) にまとめて並べ替えてからハッシュする
+pub fn bom_fingerprint(bom: &BomData) -> String {
+    let mut records: Vec<Vec<String>> = bom
+        .rows
+        .iter()
+        .map(|row| {
+            let mut attributes: Vec<(&String, &String)> = row.attributes.iter().collect();
+            attributes.sort();
+            let mut fields = vec![
+                row.part_number.clone(),
+                row.model_number.clone(),
+                row.quantity.map(|q| q.to_string()).unwrap_or_default(),
+            ];
+            for (name, value) in attributes {
+                fields.push(name.clone());
+                fields.push(value.clone());
+            }
+            fields
+        })
+        .collect();
+    records.sort();
+
+    let mut hasher = Sha256::new();
+    for fields in &records {
+        // 区切り文字を含む値でも衝突しないよう、各値の前に長さを入れる
+        hasher.update((fields.len() as u64).to_le_bytes());
+        for field in fields {
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field.as_bytes());
         }
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
 
-        let mut expanded_rows: Vec<BomRow> = Vec::new();
+#[derive(Debug, Clone, Serialize)]
+pub struct BomStats {
+    pub total_rows: usize,
+    pub unique_parts: usize,
+    /// 2行以上に現れる部品番号の数
+    pub duplicate_parts: usize,
+    pub empty_model_rows: usize,
+    /// メーカー列が指定されていない場合は None
+    pub empty_manufacturer_rows: Option<usize>,
+    /// 列名 → 空でない値を持つ行の割合（0.0〜1.0、ヘッダー順）
+    pub column_fill_rates: IndexMap<String, f64>,
+}
 
-        if rules.expand_ranges {
-            if let Some(expanded) = expand_ranges(&base_row.part_number) {
-                let original_part = base_row.part_number.clone();
-                for part in expanded {
-                    let mut cloned = base_row.clone();
-                    cloned.part_number = apply_string_rules(&part, rules);
-                    replace_attribute_value(
-                        &mut cloned.attributes,
-                        &original_part,
-                        &cloned.part_number,
-                    );
-                    expanded_rows.push(cloned);
-                }
+/// 部品表の行数・重複・空欄などの概要を1回の走査で集計する
+pub fn compute_bom_stats(bom: &BomData, manufacturer_header: Option<&str>) -> BomStats {
+    let mut part_counts: HashMap<&str, usize> = HashMap::new();
+    let mut empty_model_rows = 0usize;
+    let mut empty_manufacturer_rows = 0usize;
+    let mut filled: Vec<usize> = vec![0; bom.headers.len()];
+
+    for row in &bom.rows {
+        *part_counts.entry(row.part_number.as_str()).or_insert(0) += 1;
+        if row.model_number.trim().is_empty() {
+            empty_model_rows += 1;
+        }
+        if let Some(header) = manufacturer_header {
+            let empty = row
+                .attributes
+                .get(header)
+                .map(|value| value.trim().is_empty())
+                .unwrap_or(true);
+            if empty {
+                empty_manufacturer_rows += 1;
             }
         }
-
-        if expanded_rows.is_empty() {
-            expanded_rows.push(base_row);
+        for (count, header) in filled.iter_mut().zip(&bom.headers) {
+            if row
+                .attributes
+                .get(header)
+                .is_some_and(|value| !value.trim().is_empty())
+            {
+                *count += 1;
+            }
         }
-
-        processed_rows.extend(expanded_rows);
     }
 
-    let mut result = bom_data.clone();
-    result.rows = processed_rows;
-    Ok(result)
+    let total_rows = bom.rows.len();
+    let column_fill_rates = bom
+        .headers
+        .iter()
+        .zip(filled)
+        .map(|(header, count)| {
+            let rate = if total_rows == 0 {
+                0.0
+            } else {
+                count as f64 / total_rows as f64
+            };
+            (header.clone(), rate)
+        })
+        .collect();
+
+    BomStats {
+        total_rows,
+        unique_parts: part_counts.len(),
+        duplicate_parts: part_counts.values().filter(|&&count| count > 1).count(),
+        empty_model_rows,
+        empty_manufacturer_rows: manufacturer_header.map(|_| empty_manufacturer_rows),
+        column_fill_rates,
+    }
 }
 
-fn apply_string_rules(value: &str, rules: &PreprocessRules) -> String {
-    let mut result = value.to_string();
-    if rules.remove_parentheses {
-        result = remove_parentheses(&result);
+/// ヘッダー名を変更し、各行の属性キーも付け替える
+pub fn rename_header(
+    bom: &mut BomData,
+    old_name: &str,
+    new_name: &str,
+) -> Result<(), BomProcessorError> {
+    if new_name.is_empty() {
+        return Err(BomProcessorError::ColumnError(
+            "新しい列名が空です".to_string(),
+        ));
     }
-    if rules.fullwidth_to_halfwidth {
-        result = fullwidth_to_halfwidth(&result);
+    if old_name == new_name {
+        return Ok(());
     }
-    if rules.lowercase_to_uppercase {
-        result = result.to_uppercase();
+    if bom.headers.iter().any(|h| h == new_name) {
+        return Err(BomProcessorError::ColumnError(format!(
+            "列名「{}」は既に存在します",
+            new_name
+        )));
     }
-    result
-}
 
-fn replace_attribute_value(
-    attributes: &mut HashMap<String, String>,
-    original_value: &str,
-    new_value: &str,
-) {
-    for value in attributes.values_mut() {
-        if value == original_value {
-            *value = new_value.to_string();
+    let header = bom
+        .headers
+        .iter_mut()
+        .find(|h| *h == old_name)
+        .ok_or_else(|| {
+            BomProcessorError::ColumnError(format!("列「{}」が見つかりません", old_name))
+        })?;
+    *header = new_name.to_string();
+    for name in bom.verbatim.attributes.iter_mut() {
+        if name == old_name {
+            *name = new_name.to_string();
         }
     }
-}
 
-fn remove_parentheses(input: &str) -> String {
-    input.replace('(', "").replace(')', "")
+    for row in bom.rows.iter_mut() {
+        if let Some(index) = row.attributes.get_index_of(old_name) {
+            let value = row.attributes.shift_remove_index(index).map(|(_, v)| v);
+            if let Some(value) = value {
+                row.attributes
+                    .shift_insert(index, new_name.to_string(), value);
+            }
+        }
+    }
+    Ok(())
 }
 
-fn expand_ranges(input: &str) -> Option<Vec<String>> {
-    if let Some(dash_pos) = input.find('-') {
-        let prefix = &input[..dash_pos];
-        let suffix = &input[dash_pos + 1..];
-
-        if let (Some(start_num), Some(end_num)) = (extract_number(prefix), extract_number(suffix)) {
-            if start_num < end_num && end_num - start_num <= 100 {
-                let base = prefix
-                    .trim_end_matches(|c: char| c.is_ascii_digit())
-                    .to_string();
-                let mut result = Vec::new();
-                for i in start_num..=end_num {
-                    result.push(format!("{}{}", base, i));
-                }
-                return Some(result);
+/// 削除する列の位置を返す。存在しない列や、列指定でキー列（部品番号・型番・メーカー・数量）になっている列はエラー
+fn removal_indices(
+    bom: &BomData,
+    names: &[&str],
+    mapping: Option<&ColumnMapping>,
+) -> Result<Vec<usize>, BomProcessorError> {
+    let mut indices = Vec::new();
+    for name in names {
+        let idx = bom.headers.iter().position(|h| h == name).ok_or_else(|| {
+            BomProcessorError::ColumnError(format!("列「{}」が見つかりません", name))
+        })?;
+        if let Some(mapping) = mapping {
+            let is_key = [mapping.part_number, mapping.model_number]
+                .into_iter()
+                .chain(mapping.manufacturer)
+                .chain(mapping.quantity)
+                .chain(mapping.split_column.map(|(split_idx, _)| split_idx))
+                .any(|key_idx| key_idx == idx);
+            if is_key {
+                return Err(BomProcessorError::ColumnError(format!(
+                    "列「{}」は列指定に使われているため削除できません",
+                    name
+                )));
             }
         }
+        indices.push(idx);
     }
-    None
+    indices.sort_unstable();
+    indices.dedup();
+    Ok(indices)
 }
 
-fn extract_number(input: &str) -> Option<u32> {
-    let digits: String = input
-        .chars()
-        .rev()
-        .take_while(|c| c.is_ascii_digit())
-        .collect::<Vec<_>>()
-        .into_iter()
-        .rev()
+/// 指定位置の列をヘッダーと各行の属性から取り除き、列番号による列指定を削除後の位置に合わせる
+fn remove_column_indices(
+    bom: &mut BomData,
+    indices: &[usize],
+    mapping: Option<&mut ColumnMapping>,
+) {
+    let names: Vec<String> = indices
+        .iter()
+        .map(|&idx| bom.headers[idx].clone())
+        .collect();
+    let mut position = 0usize;
+    bom.headers.retain(|_| {
+        let keep = !indices.contains(&position);
+        position += 1;
+        keep
+    });
+    for row in bom.rows.iter_mut() {
+        for name in &names {
+            row.attributes.shift_remove(name);
+        }
+    }
+    bom.verbatim.attributes.retain(|name| !names.contains(name));
+
+    if let Some(mapping) = mapping {
+        let shift = |idx: usize| idx - indices.iter().filter(|&&removed| removed < idx).count();
+        mapping.part_number = shift(mapping.part_number);
+        mapping.model_number = shift(mapping.model_number);
+        mapping.manufacturer = mapping.manufacturer.map(shift);
+        mapping.quantity = mapping.quantity.map(shift);
+        mapping.split_column = mapping
+            .split_column
+            .map(|(idx, delimiter)| (shift(idx), delimiter));
+        for columns in [&mut mapping.verbatim_columns, &mut mapping.date_columns] {
+            columns.retain(|idx| !indices.contains(idx));
+            for idx in columns.iter_mut() {
+                *idx = shift(*idx);
+            }
+        }
+        mapping
+            .whitespace_modes
+            .retain(|(idx, _)| !indices.contains(idx));
+        for (idx, _) in mapping.whitespace_modes.iter_mut() {
+            *idx = shift(*idx);
+        }
+    }
+}
+
+fn check_new_column_name(bom: &BomData, name: &str) -> Result<(), BomProcessorError> {
+    if name.is_empty() {
+        return Err(BomProcessorError::ColumnError(
+            "新しい列名が空です".to_string(),
+        ));
+    }
+    if bom.headers.iter().any(|h| h == name) {
+        return Err(BomProcessorError::ColumnError(format!(
+            "列名「{}」は既に存在します",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// 2つの属性列を区切り文字で連結した新しい列を末尾に追加する（空の値は連結しない）
+/// drop_sources が true の場合は連結元の列を削除し、mapping の列番号を合わせる
+pub fn merge_columns(
+    bom: &mut BomData,
+    source_a: &str,
+    source_b: &str,
+    target: &str,
+    separator: &str,
+    drop_sources: bool,
+    mapping: Option<&mut ColumnMapping>,
+) -> Result<(), BomProcessorError> {
+    check_new_column_name(bom, target)?;
+    let indices = removal_indices(
+        bom,
+        &[source_a, source_b],
+        if drop_sources {
+            mapping.as_deref()
+        } else {
+            None
+        },
+    )?;
+
+    for row in bom.rows.iter_mut() {
+        let merged = [source_a, source_b]
+            .iter()
+            .filter_map(|name| row.attributes.get(*name))
+            .filter(|value| !value.trim().is_empty())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(separator);
+        row.attributes.insert(target.to_string(), merged);
+    }
+    bom.headers.push(target.to_string());
+
+    if drop_sources {
+        remove_column_indices(bom, &indices, mapping);
+    }
+    Ok(())
+}
+
+/// 属性列を区切り文字で分割し、target_names の列として末尾に追加する
+/// 分割数が足りない場合は空で補い、多い場合は残りを最後の列にまとめる。分割数が異なる行は警告として返す
+/// remove_source が true の場合は分割元の列を削除し、mapping の列番号を合わせる
+pub fn split_column(
+    bom: &mut BomData,
+    source: &str,
+    separator: &str,
+    target_names: &[String],
+    remove_source: bool,
+    mapping: Option<&mut ColumnMapping>,
+) -> Result<Vec<String>, BomProcessorError> {
+    if separator.is_empty() {
+        return Err(BomProcessorError::ColumnError(
+            "区切り文字が空です".to_string(),
+        ));
+    }
+    if target_names.is_empty() {
+        return Err(BomProcessorError::ColumnError(
+            "分割後の列名が指定されていません".to_string(),
+        ));
+    }
+    for (idx, name) in target_names.iter().enumerate() {
+        check_new_column_name(bom, name)?;
+        if target_names[..idx].contains(name) {
+            return Err(BomProcessorError::ColumnError(format!(
+                "列名「{}」が重複しています",
+                name
+            )));
+        }
+    }
+    let indices = removal_indices(
+        bom,
+        &[source],
+        if remove_source {
+            mapping.as_deref()
+        } else {
+            None
+        },
+    )?;
+
+    let mut warnings = Vec::new();
+    for (row_idx, row) in bom.rows.iter_mut().enumerate() {
+        let value = row.attributes.get(source).cloned().unwrap_or_default();
+        let mut pieces: Vec<String> = if value.trim().is_empty() {
+            Vec::new()
+        } else {
+            value
+                .split(separator)
+                .map(|p| p.trim().to_string())
+                .collect()
+        };
+        if !pieces.is_empty() && pieces.len() != target_names.len() {
+            warnings.push(format!(
+                "{}行目: 列「{}」の値 {} は{}個に分割されました（想定 {}個）",
+                row_idx + 1,
+                source,
+                value,
+                pieces.len(),
+                target_names.len()
+            ));
+        }
+        if pieces.len() > target_names.len() {
+            let rest = pieces.split_off(target_names.len() - 1).join(separator);
+            pieces.push(rest);
+        }
+        pieces.resize(target_names.len(), String::new());
+        for (name, piece) in target_names.iter().zip(pieces) {
+            row.attributes.insert(name.clone(), piece);
+        }
+    }
+    bom.headers.extend(target_names.iter().cloned());
+
+    if remove_source {
+        remove_column_indices(bom, &indices, mapping);
+    }
+    Ok(warnings)
+}
+
+pub fn preprocess_bom_data(
+    bom_data: &BomData,
+    rules: &PreprocessRules,
+) -> Result<PreprocessResult, BomProcessorError> {
+    preprocess_with_row_limit(bom_data, rules, MAX_EXPANDED_ROWS)
+}
+
+fn preprocess_with_row_limit(
+    bom_data: &BomData,
+    rules: &PreprocessRules,
+    max_expanded_rows: usize,
+) -> Result<PreprocessResult, BomProcessorError> {
+    let designator = designator_regex(rules.designator_pattern.as_deref())?;
+    let max_range_span = rules.max_range_span.min(MAX_RANGE_SPAN_LIMIT);
+    let mut added_rows = 0usize;
+    let mut row_limit_reached = false;
+    let mut processed_rows: Vec<BomRow> = Vec::new();
+    let mut has_range_source = false;
+    let mut warnings = Vec::new();
+    for rule in &rules.numeric_columns {
+        if !bom_data.headers.contains(&rule.column) {
+            warnings.push(format!("数値列 {} が見つかりません", rule.column));
+        }
+    }
+
+    for (row_idx, original) in bom_data.rows.iter().enumerate() {
+        let mut base_row = original.clone();
+
+        let verbatim = &bom_data.verbatim;
+        if !verbatim.part_number {
+            base_row.part_number = apply_string_rules(&base_row.part_number, rules);
+        }
+        if !verbatim.model_number {
+            base_row.model_number = apply_string_rules(&base_row.model_number, rules);
+        }
+
+        for (name, value) in base_row.attributes.iter_mut() {
+            if !verbatim.attributes.contains(name) {
+                *value = apply_string_rules(value, rules);
+                let convention = rules
+                    .numeric_columns
+                    .iter()
+                    .find(|rule| &rule.column == name)
+                    .map(|rule| rule.convention);
+                if let Some(normalized) =
+                    convention.and_then(|convention| normalize_number(value, convention))
+                {
+                    *value = normalized;
+                }
+            }
+        }
+
+        let mut expanded_rows: Vec<BomRow> = Vec::new();
+
+        if rules.expand_ranges && !verbatim.part_number {
+            match expand_ranges(
+                &base_row.part_number,
+                &designator,
+                max_range_span,
+                rules.expand_descending,
+            ) {
+                RangeExpansion::Expanded(expanded)
+                    if added_rows + expanded.len() - 1 > max_expanded_rows =>
+                {
+                    if !row_limit_reached {
+                        warnings.push(format!(
+                            "{}行目以降: 範囲展開で追加する行数が上限 {} を超えるため展開しませんでした",
+                            row_idx + 1,
+                            max_expanded_rows
+                        ));
+                        row_limit_reached = true;
+                    }
+                }
+                RangeExpansion::Expanded(expanded) => {
+                    added_rows += expanded.len() - 1;
+                    let original_part = base_row.part_number.clone();
+                    for part in expanded {
+                        let mut cloned = base_row.clone();
+                        cloned.part_number = apply_string_rules(&part, rules);
+                        replace_attribute_value(
+                            &mut cloned.attributes,
+                            &original_part,
+                            &cloned.part_number,
+                        );
+                        if rules.keep_range_source {
+                            cloned
+                                .attributes
+                                .insert(RANGE_SOURCE_ATTRIBUTE.to_string(), original_part.clone());
+                            has_range_source = true;
+                        }
+                        expanded_rows.push(cloned);
+                    }
+                }
+                RangeExpansion::SpanExceeded(span) => {
+                    warnings.push(format!(
+                        "{}行目: 範囲 {} の幅 {} が上限 {} を超えるため展開しませんでした",
+                        row_idx + 1,
+                        base_row.part_number,
+                        span,
+                        max_range_span
+                    ));
+                }
+                RangeExpansion::Descending => {
+                    warnings.push(format!(
+                        "{}行目: 範囲 {} は降順のため展開しませんでした",
+                        row_idx + 1,
+                        base_row.part_number
+                    ));
+                }
+                RangeExpansion::NotRange => {}
+            }
+        }
+
+        if expanded_rows.is_empty() {
+            expanded_rows.push(base_row);
+        }
+
+        processed_rows.extend(expanded_rows);
+    }
+
+    let mut result = bom_data.clone();
+    result.rows = processed_rows;
+    if has_range_source && !result.headers.iter().any(|h| h == RANGE_SOURCE_ATTRIBUTE) {
+        result.headers.push(RANGE_SOURCE_ATTRIBUTE.to_string());
+    }
+    Ok(PreprocessResult {
+        bom: result,
+        warnings,
+    })
+}
+
+fn apply_string_rules(value: &str, rules: &PreprocessRules) -> String {
+    let mut result = value.to_string();
+    if rules.remove_parentheses {
+        result = remove_parentheses(&result);
+    }
+    if rules.fullwidth_to_halfwidth {
+        result = fullwidth_to_halfwidth(&result);
+    }
+    if rules.lowercase_to_uppercase {
+        result = result.to_uppercase();
+    }
+    result
+}
+
+/// 数値を指定の表記で解釈し、桁区切りなし・小数点 `.`・小数部末尾の0なしの形式にする
+/// 数値として解釈できない値（単位付き、桁区切りの位置が不正など）は None を返す
+pub fn normalize_number(value: &str, convention: NumberConvention) -> Option<String> {
+    let (group_sep, decimal_sep) = match convention {
+        NumberConvention::Us => (',', '.'),
+        NumberConvention::European => ('.', ','),
+    };
+    let trimmed = value.trim();
+    let (sign, digits) = match trimmed.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let (integer, fraction) = match digits.split_once(decimal_sep) {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (digits, None),
+    };
+
+    let groups: Vec<&str> = integer.split(group_sep).collect();
+    let grouped_ok = groups.len() == 1
+        || (!groups[0].is_empty()
+            && groups[0].len() <= 3
+            && groups[1..].iter().all(|group| group.len() == 3));
+    let integer: String = groups.concat();
+    if integer.is_empty()
+        || !grouped_ok
+        || !integer.chars().all(|c| c.is_ascii_digit())
+        || fraction.is_some_and(|f| f.is_empty() || !f.chars().all(|c| c.is_ascii_digit()))
+    {
+        return None;
+    }
+
+    let integer = integer.trim_start_matches('0');
+    let integer = if integer.is_empty() { "0" } else { integer };
+    let fraction = fraction.unwrap_or("").trim_end_matches('0');
+    let sign = if integer == "0" && fraction.is_empty() {
+        ""
+    } else {
+        sign
+    };
+    if fraction.is_empty() {
+        Some(format!("{sign}{integer}"))
+    } else {
+        Some(format!("{sign}{integer}.{fraction}"))
+    }
+}
+
+fn replace_attribute_value(
+    attributes: &mut IndexMap<String, String>,
+    original_value: &str,
+    new_value: &str,
+) {
+    for value in attributes.values_mut() {
+        if value == original_value {
+            *value = new_value.to_string();
+        }
+    }
+}
+
+fn remove_parentheses(input: &str) -> String {
+    input.replace('(', "").replace(')', "")
+}
+
+/// 参照記号パターンをコンパイルする（未指定の場合は既定パターン）
+pub fn designator_regex(pattern: Option<&str>) -> Result<Regex, BomProcessorError> {
+    let pattern = pattern
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .unwrap_or(DEFAULT_DESIGNATOR_PATTERN);
+    Regex::new(pattern)
+        .map_err(|e| BomProcessorError::InvalidInput(format!("参照記号パターンが無効です: {}", e)))
+}
+
+#[derive(Debug, PartialEq)]
+enum RangeExpansion {
+    Expanded(Vec<String>),
+    /// 範囲表記だが展開上限を超えている（値は範囲の幅）
+    SpanExceeded(u32),
+    /// 降順の範囲表記で、降順展開が無効
+    Descending,
+    NotRange,
+}
+
+/// "C1-C3" のような範囲表記を展開する。
+/// 前半が参照記号パターンに一致し、ハイフンが1つだけの場合に限り展開する（"ABC-100-200" などの型番は対象外）
+/// 始点と終点が同じ "C5-C5" は "C5" の1件とし、降順の "C10-C2" は `descending` が true の場合のみ降順に展開する
+fn expand_ranges(
+    input: &str,
+    designator: &Regex,
+    max_span: u32,
+    descending: bool,
+) -> RangeExpansion {
+    if input.matches('-').count() != 1 {
+        return RangeExpansion::NotRange;
+    }
+
+    if let Some(dash_pos) = input.find('-') {
+        let prefix = &input[..dash_pos];
+        let suffix = &input[dash_pos + 1..];
+
+        if !designator.is_match(prefix) {
+            return RangeExpansion::NotRange;
+        }
+
+        let base = prefix
+            .trim_end_matches(|c: char| c.is_ascii_digit())
+            .to_string();
+        let suffix_base = suffix.trim_end_matches(|c: char| c.is_ascii_digit());
+        if !suffix_base.is_empty() && suffix_base != base {
+            return RangeExpansion::NotRange;
+        }
+
+        if let (Some(start_num), Some(end_num)) = (extract_number(prefix), extract_number(suffix)) {
+            if start_num > end_num && !descending {
+                return RangeExpansion::Descending;
+            }
+            let span = start_num.abs_diff(end_num);
+            if span > max_span {
+                return RangeExpansion::SpanExceeded(span);
+            }
+            let numbers: Vec<u32> = if start_num <= end_num {
+                (start_num..=end_num).collect()
+            } else {
+                (end_num..=start_num).rev().collect()
+            };
+            return RangeExpansion::Expanded(
+                numbers
+                    .into_iter()
+                    .map(|i| format!("{}{}", base, i))
+                    .collect(),
+            );
+        }
+    }
+    RangeExpansion::NotRange
+}
+
+fn extract_number(input: &str) -> Option<u32> {
+    let digits: String = input
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
         .collect();
     digits.parse().ok()
 }
 
-fn fullwidth_to_halfwidth(input: &str) -> String {
+/// 数量セルを解釈する（全角数字・桁区切り・末尾の単位に対応。例: "1,000" / "10 pcs" / "５個"）
+pub fn parse_quantity(input: &str) -> Option<u32> {
+    let halfwidth = fullwidth_to_halfwidth(input.trim());
+    let number_end = halfwidth
+        .find(|c: char| !(c.is_ascii_digit() || c == ',' || c == '，'))
+        .unwrap_or(halfwidth.len());
+    let (number, unit) = halfwidth.split_at(number_end);
+
+    let unit = unit.trim();
+    if unit.starts_with('.') || unit.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let digits: String = number.chars().filter(|c| c.is_ascii_digit()).collect();
+    if digits.is_empty() {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+pub fn fullwidth_to_halfwidth(input: &str) -> String {
     input
         .chars()
         .map(|c| match c {
@@ -932,47 +2697,214 @@ fn fullwidth_to_halfwidth(input: &str) -> String {
 
 pub async fn load_registered_name_csv(
     file_path: &str,
+    dictionary: &ColumnDictionary,
 ) -> Result<RegisteredNameList, BomProcessorError> {
-    let content =
-        fs::read(file_path).map_err(|e| BomProcessorError::FileReadError(format!("{}", e)))?;
+    let content = fs::read(file_path)?;
 
-    let (decoded_content, _, _) = if content.starts_with(&[0xEF, 0xBB, 0xBF]) {
-        (UTF_8.decode(&content[3..]).0, UTF_8, true)
-    } else {
-        let utf8_result = UTF_8.decode(&content);
-        if utf8_result.2 {
-            (utf8_result.0, UTF_8, false)
-        } else {
-            let sjis_result = SHIFT_JIS.decode(&content);
-            (sjis_result.0, SHIFT_JIS, false)
-        }
-    };
+    let decoded_content = decode_text(&content)?;
+
+    parse_registered_name_csv(&decoded_content, dictionary)
+}
 
+const DEFAULT_PART_MODEL_PATTERNS: &[&str] = &["部品型番", "型番", "part model", "model"];
+const DEFAULT_REGISTERED_NAME_PATTERNS: &[&str] = &["登録名", "registered name"];
+const DEFAULT_REGISTERED_MANUFACTURER_PATTERNS: &[&str] = &["メーカー", "maker", "manufacturer"];
+
+/// 登録名CSVの内容を解析する（ヘッダー名から列位置を判定し、判定できない場合は1列目/2列目を使用）
+fn parse_registered_name_csv(
+    content: &str,
+    dictionary: &ColumnDictionary,
+) -> Result<RegisteredNameList, BomProcessorError> {
     let mut reader = ReaderBuilder::new()
         .has_headers(true)
-        .from_reader(decoded_content.as_bytes());
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| BomProcessorError::FileReadError(format!("{}", e)))?
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+
+    let name_patterns = registered_list_patterns(
+        dictionary,
+        "registered_name",
+        DEFAULT_REGISTERED_NAME_PATTERNS,
+    );
+    let model_patterns =
+        registered_list_patterns(dictionary, "part_model", DEFAULT_PART_MODEL_PATTERNS);
+
+    let manufacturer_patterns = registered_list_patterns(
+        dictionary,
+        "manufacturer",
+        DEFAULT_REGISTERED_MANUFACTURER_PATTERNS,
+    );
+
+    let name_idx = find_header_index(&headers, &name_patterns, &[]);
+    let model_idx = name_idx.and_then(|name| find_header_index(&headers, &model_patterns, &[name]));
+    let (model_idx, name_idx, manufacturer_idx) = match (model_idx, name_idx) {
+        (Some(model), Some(name)) => (
+            model,
+            name,
+            find_header_index(&headers, &manufacturer_patterns, &[model, name]),
+        ),
+        // 位置による既定の列割り当てでは、メーカー列はヘッダー名で示されている場合のみ使う
+        _ => (
+            0,
+            1,
+            find_header_index(&headers, &manufacturer_patterns, &[0, 1]),
+        ),
+    };
 
     let mut entries = Vec::new();
 
     for result in reader.records() {
         let record = result.map_err(|e| BomProcessorError::FileReadError(format!("{}", e)))?;
-        if record.len() < 2 {
-            continue;
-        }
+        let (part_model, registered_name) = match (record.get(model_idx), record.get(name_idx)) {
+            (Some(model), Some(name)) => (model, name),
+            _ => continue,
+        };
+        let manufacturer = manufacturer_idx
+            .and_then(|idx| record.get(idx))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
         entries.push(RegisteredNameEntry {
-            part_model: record[0].to_string(),
-            registered_name: record[1].to_string(),
+            part_model: part_model.to_string(),
+            registered_name: registered_name.to_string(),
+            manufacturer,
         });
     }
 
     Ok(RegisteredNameList { entries })
 }
 
+fn registered_list_patterns(
+    dictionary: &ColumnDictionary,
+    column_type: &str,
+    defaults: &[&str],
+) -> Vec<String> {
+    let patterns = dictionary.patterns_for(column_type);
+    let patterns = if patterns.is_empty() {
+        defaults.iter().map(|p| p.to_string()).collect()
+    } else {
+        patterns
+    };
+    patterns
+        .iter()
+        .map(|p| normalize_token(p))
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// 辞書のメーカー列名から部品表のメーカー列のヘッダー名を探す
+pub fn find_manufacturer_header(bom: &BomData, dictionary: &ColumnDictionary) -> Option<String> {
+    let patterns = registered_list_patterns(
+        dictionary,
+        "manufacturer",
+        DEFAULT_REGISTERED_MANUFACTURER_PATTERNS,
+    );
+    find_header_index(&bom.headers, &patterns, &[]).map(|idx| bom.headers[idx].clone())
+}
+
+/// 辞書のメーカー列名から部品表のメーカー列を探し、メーカーが空の部品番号を返す（最大 limit 件）
+/// メーカー列が見つからない場合はエラーとする
+pub fn find_parts_missing_manufacturer(
+    bom: &BomData,
+    dictionary: &ColumnDictionary,
+    limit: usize,
+) -> Result<Vec<String>, BomProcessorError> {
+    let header = find_manufacturer_header(bom, dictionary)
+        .ok_or_else(|| BomProcessorError::ColumnError("メーカー列が見つかりません".to_string()))?;
+
+    Ok(bom
+        .rows
+        .iter()
+        .filter(|row| {
+            row.attributes
+                .get(&header)
+                .map(|value| value.trim().is_empty())
+                .unwrap_or(true)
+        })
+        .map(|row| row.part_number.clone())
+        .take(limit)
+        .collect())
+}
+
+/// 正規化済みパターンに最も一致するヘッダー位置を返す（完全一致を部分一致より優先）
+fn find_header_index(headers: &[String], patterns: &[String], skip: &[usize]) -> Option<usize> {
+    let mut best: Option<(usize, u8)> = None;
+    for (idx, header) in headers.iter().enumerate() {
+        if skip.contains(&idx) {
+            continue;
+        }
+        let header_norm = normalize_token(header);
+        if header_norm.is_empty() {
+            continue;
+        }
+        let score = patterns
+            .iter()
+            .map(|pattern| {
+                if header_norm == *pattern {
+                    2
+                } else if header_norm.contains(pattern.as_str()) {
+                    1
+                } else {
+                    0
+                }
+            })
+            .max()
+            .unwrap_or(0);
+        if score == 0 {
+            continue;
+        }
+        match best {
+            Some((_, best_score)) if score <= best_score => {}
+            _ => best = Some((idx, score)),
+        }
+    }
+    best.map(|(idx, _)| idx)
+}
+
+/// 同じ部品型番（メーカー指定がある場合は型番+メーカー）の重複エントリを除去する
+/// 先に出現したエントリを採用し、登録名が食い違うものを競合として返す
+pub fn dedupe_registered_names(
+    list: RegisteredNameList,
+) -> (RegisteredNameList, Vec<RegisteredNameConflict>) {
+    let mut kept: HashMap<(String, Option<String>), String> = HashMap::new();
+    let mut entries = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for entry in list.entries.into_iter() {
+        let key = (
+            entry.part_model.clone(),
+            entry.manufacturer.as_deref().map(standardize_string),
+        );
+        match kept.get(&key) {
+            Some(kept_name) => {
+                if *kept_name != entry.registered_name {
+                    conflicts.push(RegisteredNameConflict {
+                        part_model: entry.part_model,
+                        manufacturer: entry.manufacturer,
+                        kept_name: kept_name.clone(),
+                        discarded_name: entry.registered_name,
+                    });
+                }
+            }
+            None => {
+                kept.insert(key, entry.registered_name.clone());
+                entries.push(entry);
+            }
+        }
+    }
+
+    (RegisteredNameList { entries }, conflicts)
+}
+
 pub async fn load_registered_name_json(
     file_path: &str,
 ) -> Result<RegisteredNameList, BomProcessorError> {
-    let content = fs::read_to_string(file_path)
-        .map_err(|e| BomProcessorError::FileReadError(format!("{}", e)))?;
+    let content = fs::read_to_string(file_path)?;
 
     let list: RegisteredNameList = serde_json::from_str(&content)
         .map_err(|e| BomProcessorError::FormatError(format!("JSON解析エラー: {}", e)))?;
@@ -985,18 +2917,27 @@ pub async fn save_registered_name_csv(
     file_path: &str,
 ) -> Result<(), BomProcessorError> {
     let mut csv_data = Vec::new();
-    csv_data.push(vec!["部品型番".to_string(), "登録名".to_string()]);
+    csv_data.push(vec![
+        "部品型番".to_string(),
+        "登録名".to_string(),
+        "メーカー".to_string(),
+    ]);
 
     for entry in &list.entries {
         csv_data.push(vec![
             entry.part_model.clone(),
             entry.registered_name.clone(),
+            entry.manufacturer.clone().unwrap_or_default(),
         ]);
     }
 
-    crate::file_handler::save_csv_file(&csv_data, file_path, "utf-8")
-        .await
-        .map_err(|e| BomProcessorError::FileReadError(format!("{}", e)))?;
+    crate::file_handler::save_csv_file(
+        &csv_data,
+        file_path,
+        crate::file_handler::DEFAULT_CSV_ENCODING,
+    )
+    .await
+    .map_err(|e| BomProcessorError::FileReadError(format!("{}", e)))?;
 
     Ok(())
 }
@@ -1014,40 +2955,247 @@ pub async fn save_registered_name_json(
     Ok(())
 }
 
-pub fn apply_registered_names_to_bom(
-    bom_data: &mut BomData,
+/// 登録名の適用元
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NameSource {
+    Override,
+    Registered,
+    None,
+}
+
+impl NameSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NameSource::Override => "override",
+            NameSource::Registered => "registered",
+            NameSource::None => "none",
+        }
+    }
+}
+
+/// 上書きリストと登録名リストの両方に該当する行でどちらを優先するか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisteredNamePolicy {
+    /// true: 上書きリストを優先（従来の動作）、false: 登録名リストを優先し、上書きは登録名がない行にのみ適用
+    #[serde(default = "default_override_first")]
+    pub override_first: bool,
+}
+
+fn default_override_first() -> bool {
+    true
+}
+
+impl Default for RegisteredNamePolicy {
+    fn default() -> Self {
+        Self {
+            override_first: true,
+        }
+    }
+}
+
+/// 上書きリストと登録名リストから各行の登録名を解決する
+pub struct RegisteredNameResolver {
+    override_map: HashMap<String, String>,
+    model_override_map: HashMap<String, String>,
+    registered_name_map: HashMap<String, String>,
+    registered_vendor_map: HashMap<(String, String), String>,
+    manufacturer_header: Option<String>,
+    policy: RegisteredNamePolicy,
+}
+
+impl RegisteredNameResolver {
+    pub fn new(
+        registered_name_list: &Option<RegisteredNameList>,
+        override_list: &Option<OverrideList>,
+        manufacturer_header: Option<&str>,
+        policy: RegisteredNamePolicy,
+    ) -> Self {
+        let mut override_map: HashMap<String, String> = HashMap::new();
+        let mut model_override_map: HashMap<String, String> = HashMap::new();
+        if let Some(list) = override_list.as_ref() {
+            for entry in &list.entries {
+                let map = match entry.match_field {
+                    OverrideMatchField::PartNumber => &mut override_map,
+                    OverrideMatchField::ModelNumber => &mut model_override_map,
+                };
+                map.insert(entry.part_number.clone(), entry.registered_name.clone());
+            }
+        }
+
+        // メーカー指定のあるエントリは (型番, メーカー) で、ないエントリは型番のみで照合する
+        let mut registered_name_map: HashMap<String, String> = HashMap::new();
+        let mut registered_vendor_map: HashMap<(String, String), String> = HashMap::new();
+        if let Some(list) = registered_name_list.as_ref() {
+            for entry in &list.entries {
+                match entry.manufacturer.as_deref() {
+                    Some(manufacturer) if !manufacturer.trim().is_empty() => {
+                        registered_vendor_map.insert(
+                            (entry.part_model.clone(), standardize_string(manufacturer)),
+                            entry.registered_name.clone(),
+                        );
+                    }
+                    _ => {
+                        registered_name_map
+                            .insert(entry.part_model.clone(), entry.registered_name.clone());
+                    }
+                }
+            }
+        }
+
+        Self {
+            override_map,
+            model_override_map,
+            registered_name_map,
+            registered_vendor_map,
+            manufacturer_header: manufacturer_header.map(|h| h.to_string()),
+            policy,
+        }
+    }
+
+    /// 行の登録名と適用元を返す（上書きと登録名の優先順位は policy に従う）
+    pub fn resolve(&self, row: &BomRow) -> (Option<&str>, NameSource) {
+        let (first, second) = if self.policy.override_first {
+            (self.resolve_override(row), self.resolve_registered(row))
+        } else {
+            (self.resolve_registered(row), self.resolve_override(row))
+        };
+        first.or(second).unwrap_or((None, NameSource::None))
+    }
+
+    /// 上書きリストによる登録名（部品番号での上書きを型番での上書きより優先する）
+    fn resolve_override(&self, row: &BomRow) -> Option<(Option<&str>, NameSource)> {
+        self.override_map
+            .get(&row.part_number)
+            .or_else(|| self.model_override_map.get(&row.model_number))
+            .map(|name| (Some(name.as_str()), NameSource::Override))
+    }
+
+    /// 登録名リストによる登録名（メーカー指定のあるエントリを優先する）
+    fn resolve_registered(&self, row: &BomRow) -> Option<(Option<&str>, NameSource)> {
+        let vendor_name = self
+            .manufacturer_header
+            .as_ref()
+            .and_then(|header| row.attributes.get(header))
+            .filter(|value| !value.trim().is_empty())
+            .and_then(|manufacturer| {
+                self.registered_vendor_map
+                    .get(&(row.model_number.clone(), standardize_string(manufacturer)))
+            });
+        vendor_name
+            .or_else(|| self.registered_name_map.get(&row.model_number))
+            .map(|name| (Some(name.as_str()), NameSource::Registered))
+    }
+}
+
+pub fn apply_registered_names_to_bom(
+    bom_data: &mut BomData,
+    registered_name_list: &Option<RegisteredNameList>,
+    override_list: &Option<OverrideList>,
+    manufacturer_header: Option<&str>,
+    policy: RegisteredNamePolicy,
+) {
+    let resolver = RegisteredNameResolver::new(
+        registered_name_list,
+        override_list,
+        manufacturer_header,
+        policy,
+    );
+
+    for row in &mut bom_data.rows {
+        if let (Some(name), _) = resolver.resolve(row) {
+            row.attributes
+                .insert("登録名".to_string(), name.to_string());
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisteredNamePreviewRow {
+    pub part_number: String,
+    pub model_number: String,
+    pub resolved_name: Option<String>,
+    pub source: NameSource,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisteredNamePreview {
+    pub rows: Vec<RegisteredNamePreviewRow>,
+    pub total_rows: usize,
+    pub summary: HashMap<String, usize>,
+}
+
+/// 部品表を変更せずに、登録名の適用結果を確認する
+pub fn preview_registered_names(
+    bom_data: &BomData,
     registered_name_list: &Option<RegisteredNameList>,
     override_list: &Option<OverrideList>,
-) {
-    let override_map: HashMap<String, String> = override_list
-        .as_ref()
-        .map(|list| {
-            list.entries
-                .iter()
-                .map(|entry| (entry.part_number.clone(), entry.registered_name.clone()))
-                .collect()
-        })
-        .unwrap_or_default();
+    manufacturer_header: Option<&str>,
+    policy: RegisteredNamePolicy,
+    limit: usize,
+) -> RegisteredNamePreview {
+    let resolver = RegisteredNameResolver::new(
+        registered_name_list,
+        override_list,
+        manufacturer_header,
+        policy,
+    );
+
+    let mut summary: HashMap<String, usize> = HashMap::new();
+    for source in [
+        NameSource::Override,
+        NameSource::Registered,
+        NameSource::None,
+    ] {
+        summary.insert(source.as_str().to_string(), 0);
+    }
 
-    let registered_name_map: HashMap<String, String> = registered_name_list
-        .as_ref()
-        .map(|list| {
-            list.entries
-                .iter()
-                .map(|entry| (entry.part_model.clone(), entry.registered_name.clone()))
-                .collect()
-        })
-        .unwrap_or_default();
+    let mut rows = Vec::new();
+    for row in &bom_data.rows {
+        let (name, source) = resolver.resolve(row);
+        *summary.entry(source.as_str().to_string()).or_insert(0) += 1;
+        if rows.len() < limit {
+            rows.push(RegisteredNamePreviewRow {
+                part_number: row.part_number.clone(),
+                model_number: row.model_number.clone(),
+                resolved_name: name.map(|n| n.to_string()),
+                source,
+            });
+        }
+    }
 
-    for row in &mut bom_data.rows {
-        if let Some(override_name) = override_map.get(&row.part_number) {
-            row.attributes
-                .insert("登録名".to_string(), override_name.clone());
-        } else if let Some(registered_name) = registered_name_map.get(&row.model_number) {
-            row.attributes
-                .insert("登録名".to_string(), registered_name.clone());
+    RegisteredNamePreview {
+        rows,
+        total_rows: bom_data.rows.len(),
+        summary,
+    }
+}
+
+/// 登録名リストの各行を検査する（行番号はリスト内の1始まりの位置）
+pub fn validate_registered_name_list(list: &RegisteredNameList) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    for (index, entry) in list.entries.iter().enumerate() {
+        let row_number = index + 1;
+
+        if entry.part_model.trim().is_empty() {
+            errors.push(ValidationError {
+                row_number,
+                field: "型番".to_string(),
+                message: "型番が空です".to_string(),
+            });
+        }
+
+        if entry.registered_name.trim().is_empty() {
+            errors.push(ValidationError {
+                row_number,
+                field: "登録名".to_string(),
+                message: "登録名が空です".to_string(),
+            });
         }
     }
+
+    errors
 }
 
 pub fn validate_bom_data(bom_data: &BomData) -> ValidationResult {
@@ -1117,10 +3265,1072 @@ pub fn validate_bom_data(bom_data: &BomData) -> ValidationResult {
     }
 }
 
+/// テストで非同期の読み込み関数を実行する（ファイル読み込みは同期的に完了するため、待機は発生しない）
+#[cfg(test)]
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+    loop {
+        if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_expand_ranges_requires_designator_prefix() {
+        let designator = designator_regex(None).unwrap();
+
+        assert_eq!(
+            expand_ranges("C1-C3", &designator, 100, false),
+            RangeExpansion::Expanded(vec!["C1".to_string(), "C2".to_string(), "C3".to_string()])
+        );
+        assert_eq!(
+            expand_ranges("R10-12", &designator, 100, false),
+            RangeExpansion::Expanded(vec![
+                "R10".to_string(),
+                "R11".to_string(),
+                "R12".to_string()
+            ])
+        );
+        assert_eq!(
+            expand_ranges("ABC-100-200", &designator, 100, false),
+            RangeExpansion::NotRange
+        );
+        assert_eq!(
+            expand_ranges("AB-C100-200", &designator, 100, false),
+            RangeExpansion::NotRange
+        );
+        assert_eq!(
+            expand_ranges("R1-C3", &designator, 100, false),
+            RangeExpansion::NotRange
+        );
+
+        let custom = designator_regex(Some(r"^(C|R)[0-9]+$")).unwrap();
+        assert_eq!(
+            expand_ranges("U1-U3", &custom, 100, false),
+            RangeExpansion::NotRange
+        );
+        assert!(designator_regex(Some("[")).is_err());
+
+        assert_eq!(
+            expand_ranges("J1-J201", &designator, 100, false),
+            RangeExpansion::SpanExceeded(200)
+        );
+        match expand_ranges("J1-J201", &designator, 300, false) {
+            RangeExpansion::Expanded(parts) => assert_eq!(parts.len(), 201),
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_expand_ranges_equal_and_descending() {
+        let designator = designator_regex(None).unwrap();
+
+        assert_eq!(
+            expand_ranges("C5-C5", &designator, 100, false),
+            RangeExpansion::Expanded(vec!["C5".to_string()])
+        );
+        assert_eq!(
+            expand_ranges("C10-C8", &designator, 100, false),
+            RangeExpansion::Descending
+        );
+        assert_eq!(
+            expand_ranges("C10-C8", &designator, 100, true),
+            RangeExpansion::Expanded(vec!["C10".to_string(), "C9".to_string(), "C8".to_string()])
+        );
+        assert_eq!(
+            expand_ranges("J201-J1", &designator, 100, true),
+            RangeExpansion::SpanExceeded(200)
+        );
+    }
+
+    #[test]
+    fn test_preprocess_keeps_range_source() {
+        let bom = BomData {
+            headers: vec!["部品番号".to_string(), "型番".to_string()],
+            rows: vec![
+                BomRow {
+                    part_number: "C1-C2".to_string(),
+                    model_number: "CAP100".to_string(),
+                    attributes: IndexMap::new(),
+                    quantity: None,
+                },
+                BomRow {
+                    part_number: "R1".to_string(),
+                    model_number: "RES100".to_string(),
+                    attributes: IndexMap::new(),
+                    quantity: None,
+                },
+            ],
+            verbatim: VerbatimColumns::default(),
+        };
+        let rules = PreprocessRules {
+            remove_parentheses: false,
+            expand_ranges: true,
+            fullwidth_to_halfwidth: false,
+            lowercase_to_uppercase: false,
+            designator_pattern: None,
+            keep_range_source: true,
+            max_range_span: DEFAULT_MAX_RANGE_SPAN,
+            expand_descending: false,
+            numeric_columns: Vec::new(),
+        };
+
+        let result = preprocess_bom_data(&bom, &rules).unwrap().bom;
+
+        assert_eq!(result.rows.len(), 3);
+        assert_eq!(
+            result.rows[1].attributes.get(RANGE_SOURCE_ATTRIBUTE),
+            Some(&"C1-C2".to_string())
+        );
+        assert!(!result.rows[2]
+            .attributes
+            .contains_key(RANGE_SOURCE_ATTRIBUTE));
+        assert!(result.headers.contains(&RANGE_SOURCE_ATTRIBUTE.to_string()));
+    }
+
+    #[test]
+    fn test_preprocess_stops_at_expanded_row_limit() {
+        let row = |part: &str| BomRow {
+            part_number: part.to_string(),
+            model_number: "CAP100".to_string(),
+            attributes: IndexMap::new(),
+            quantity: None,
+        };
+        let bom = BomData {
+            headers: vec!["部品番号".to_string(), "型番".to_string()],
+            rows: vec![row("C1-C3"), row("C10-C13"), row("C20-C21")],
+            verbatim: VerbatimColumns::default(),
+        };
+        let rules = PreprocessRules {
+            remove_parentheses: false,
+            expand_ranges: true,
+            fullwidth_to_halfwidth: false,
+            lowercase_to_uppercase: false,
+            designator_pattern: None,
+            keep_range_source: false,
+            max_range_span: DEFAULT_MAX_RANGE_SPAN,
+            expand_descending: false,
+            numeric_columns: Vec::new(),
+        };
+
+        let result = preprocess_with_row_limit(&bom, &rules, 3).unwrap();
+        let parts: Vec<&str> = result
+            .bom
+            .rows
+            .iter()
+            .map(|row| row.part_number.as_str())
+            .collect();
+
+        assert_eq!(parts, ["C1", "C2", "C3", "C10-C13", "C20", "C21"]);
+        assert_eq!(
+            result
+                .warnings
+                .iter()
+                .filter(|warning| warning.contains("上限 3"))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_resolve_mapping_by_name() {
+        let headers = vec![
+            "No".to_string(),
+            "型番".to_string(),
+            "Part Number".to_string(),
+        ];
+        let mapping = ColumnMappingByName {
+            part_number: "part number".to_string(),
+            model_number: "型番".to_string(),
+            manufacturer: None,
+            quantity: None,
+        };
+
+        let resolved = resolve_mapping_by_name(&headers, &mapping).unwrap();
+        assert_eq!(resolved.part_number, 2);
+        assert_eq!(resolved.model_number, 1);
+        assert_eq!(resolved.manufacturer, None);
+
+        let missing = ColumnMappingByName {
+            manufacturer: Some("メーカー".to_string()),
+            ..mapping
+        };
+        assert!(resolve_mapping_by_name(&headers, &missing).is_err());
+    }
+
+    #[test]
+    fn test_build_bom_keeps_selected_columns() {
+        let headers = vec![
+            "No".to_string(),
+            "部品番号".to_string(),
+            "備考".to_string(),
+            "型番".to_string(),
+            "数量".to_string(),
+        ];
+        let raw_rows = vec![vec![
+            "1".to_string(),
+            "C1".to_string(),
+            "memo".to_string(),
+            "CAP100".to_string(),
+            "2".to_string(),
+        ]];
+        let mapping = ColumnMapping {
+            part_number: 1,
+            model_number: 3,
+            manufacturer: None,
+            quantity: None,
+            keep_columns: Some(vec![4]),
+            has_headers: true,
+            verbatim_columns: Vec::new(),
+            date_columns: Vec::new(),
+            split_column: None,
+            whitespace_modes: Vec::new(),
+        };
+
+        let result = build_bom_from_rows(headers, raw_rows, &mapping).unwrap();
+
+        assert_eq!(result.bom.headers, vec!["部品番号", "型番", "数量"]);
+        assert_eq!(
+            result.source_headers,
+            vec!["No", "部品番号", "備考", "型番", "数量"]
+        );
+        assert_eq!(result.bom.rows[0].attributes.len(), 3);
+        assert!(!result.bom.rows[0].attributes.contains_key("備考"));
+        assert_eq!(result.column_mapping.part_number, 0);
+        assert_eq!(result.column_mapping.model_number, 1);
+    }
+
+    #[test]
+    fn test_build_bom_keeps_quantity_column() {
+        let headers = vec![
+            "No".to_string(),
+            "部品番号".to_string(),
+            "備考".to_string(),
+            "型番".to_string(),
+            "数量".to_string(),
+        ];
+        let raw_rows = vec![vec![
+            "1".to_string(),
+            "C1".to_string(),
+            "memo".to_string(),
+            "CAP100".to_string(),
+            "2".to_string(),
+        ]];
+        // 数量列は keep_columns に含めなくても残す
+        let mapping = ColumnMapping {
+            part_number: 1,
+            model_number: 3,
+            manufacturer: None,
+            quantity: Some(4),
+            keep_columns: Some(Vec::new()),
+            has_headers: true,
+            verbatim_columns: Vec::new(),
+            date_columns: Vec::new(),
+            split_column: None,
+            whitespace_modes: Vec::new(),
+        };
+
+        let result = build_bom_from_rows(headers, raw_rows, &mapping).unwrap();
+
+        assert_eq!(result.bom.headers, vec!["部品番号", "型番", "数量"]);
+        assert_eq!(result.column_mapping.quantity, Some(2));
+        assert_eq!(result.bom.rows[0].quantity, Some(2));
+    }
+
+    #[test]
+    fn test_empty_part_number_rows_are_reported() {
+        let headers = vec!["部品番号".to_string(), "型番".to_string()];
+        let raw_rows: Vec<Vec<String>> = [["C1", "CAP100"], ["", "RES100"], ["", ""], [" ", "X"]]
+            .iter()
+            .map(|row| row.iter().map(|v| v.to_string()).collect())
+            .collect();
+        let mapping = ColumnMapping {
+            part_number: 0,
+            model_number: 1,
+            manufacturer: None,
+            quantity: None,
+            keep_columns: None,
+            has_headers: true,
+            verbatim_columns: Vec::new(),
+            date_columns: Vec::new(),
+            split_column: None,
+            whitespace_modes: Vec::new(),
+        };
+
+        let loaded = build_bom_from_rows(headers, raw_rows, &mapping).unwrap();
+
+        assert_eq!(loaded.bom.rows.len(), 1);
+        assert_eq!(loaded.skipped_empty_rows, vec![2, 4]);
+        assert!(loaded
+            .warnings
+            .contains(&"部品番号が空の 2 行をスキップしました".to_string()));
+    }
+
+    #[test]
+    fn test_build_normalized_preview_flags_changed_cells() {
+        let preview = FilePreview {
+            headers: vec!["部品番号".to_string(), "型番".to_string()],
+            rows: vec![vec!["c1".to_string(), " ｃａｐ１００ ".to_string()]],
+        };
+        let mapping = ColumnMapping {
+            part_number: 0,
+            model_number: 1,
+            manufacturer: None,
+            quantity: None,
+            keep_columns: None,
+            has_headers: true,
+            verbatim_columns: vec![0],
+            date_columns: Vec::new(),
+            split_column: None,
+            whitespace_modes: Vec::new(),
+        };
+
+        let result = build_normalized_preview(preview, &mapping, 10);
+
+        let part = &result.rows[0][0];
+        assert_eq!((part.normalized.as_str(), part.changed), ("c1", false));
+        let model = &result.rows[0][1];
+        assert_eq!(model.raw, " ｃａｐ１００ ");
+        assert_eq!(model.normalized, standardize_string(" ｃａｐ１００ "));
+        assert!(model.changed);
+    }
+
+    #[test]
+    fn test_parse_document_properties() {
+        let core = r#"<cp:coreProperties xmlns:cp="x" xmlns:dc="y" xmlns:dcterms="z">
+            <dc:title>基板A &amp; B</dc:title>
+            <cp:lastModifiedBy>yamada</cp:lastModifiedBy>
+            <dcterms:modified xsi:type="dcterms:W3CDTF">2024-05-01T09:00:00Z</dcterms:modified>
+            <dc:subject></dc:subject>
+        </cp:coreProperties>"#;
+        let custom = r#"<Properties>
+            <property fmtid="{D5CDD505}" pid="2" name="文書版数"><vt:lpwstr>Rev.C</vt:lpwstr></property>
+        </Properties>"#;
+
+        let metadata = parse_document_properties(core, custom);
+
+        assert_eq!(metadata.get("title").map(String::as_str), Some("基板A & B"));
+        assert_eq!(
+            metadata.get("last_modified_by").map(String::as_str),
+            Some("yamada")
+        );
+        assert_eq!(
+            metadata.get("modified").map(String::as_str),
+            Some("2024-05-01T09:00:00Z")
+        );
+        assert_eq!(
+            metadata.get("custom:文書版数").map(String::as_str),
+            Some("Rev.C")
+        );
+        assert!(!metadata.contains_key("subject"));
+        assert!(parse_document_properties("", "").is_empty());
+    }
+
+    #[test]
+    fn test_remap_to_source_columns() {
+        let to_vec = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let source = to_vec(&["No", "部品番号", "備考", "型番", "数量"]);
+        let loaded = to_vec(&["部品番号", "型番", "数量"]);
+        let mapping = ColumnMapping {
+            part_number: 0,
+            model_number: 1,
+            manufacturer: None,
+            quantity: Some(2),
+            keep_columns: None,
+            has_headers: true,
+            verbatim_columns: vec![1],
+            date_columns: Vec::new(),
+            split_column: None,
+            whitespace_modes: Vec::new(),
+        };
+
+        let remapped = remap_to_source_columns(&mapping, &loaded, &source).unwrap();
+        assert_eq!(
+            (
+                remapped.part_number,
+                remapped.model_number,
+                remapped.quantity
+            ),
+            (1, 3, Some(4))
+        );
+        assert_eq!(remapped.verbatim_columns, vec![3]);
+
+        let renamed = to_vec(&["部品番号", "型番(新)", "数量"]);
+        assert!(remap_to_source_columns(&mapping, &renamed, &source).is_err());
+    }
+
+    #[test]
+    fn test_split_column_into_part_and_model() {
+        let headers = vec!["No".to_string(), "部品|型番".to_string()];
+        let raw_rows: Vec<Vec<String>> = [["1", "C1|CAP100"], ["2", "R1"], ["3", "R2 | RES200"]]
+            .iter()
+            .map(|row| row.iter().map(|v| v.to_string()).collect())
+            .collect();
+        let mapping = ColumnMapping {
+            part_number: 0,
+            model_number: 0,
+            manufacturer: None,
+            quantity: None,
+            keep_columns: Some(Vec::new()),
+            has_headers: true,
+            verbatim_columns: Vec::new(),
+            date_columns: Vec::new(),
+            split_column: Some((1, '|')),
+            whitespace_modes: Vec::new(),
+        };
+
+        let loaded = build_bom_from_rows(headers, raw_rows, &mapping).unwrap();
+        assert!(loaded.skipped_empty_rows.is_empty());
+        let keys: Vec<(&str, &str)> = loaded
+            .bom
+            .rows
+            .iter()
+            .map(|row| (row.part_number.as_str(), row.model_number.as_str()))
+            .collect();
+        assert_eq!(keys, vec![("C1", "CAP100"), ("R1", ""), ("R2", "RES200")]);
+        assert!(loaded.bom.headers.contains(&"部品|型番".to_string()));
+        assert_eq!(loaded.column_mapping.split_column, Some((1, '|')));
+
+        let mismatches: Vec<usize> = loaded
+            .corrections
+            .iter()
+            .filter(|c| c.rule == "split_column_mismatch")
+            .map(|c| c.row_number)
+            .collect();
+        assert_eq!(mismatches, vec![2]);
+    }
+
+    #[test]
+    fn test_verbatim_columns_bypass_normalization() {
+        let headers = vec!["部品番号".to_string(), "型番".to_string()];
+        let raw_rows = vec![vec!["C1-C3".to_string(), " ab 12 ".to_string()]];
+        let mapping = ColumnMapping {
+            part_number: 0,
+            model_number: 1,
+            manufacturer: None,
+            quantity: None,
+            keep_columns: None,
+            has_headers: true,
+            verbatim_columns: vec![0, 1],
+            date_columns: Vec::new(),
+            split_column: None,
+            whitespace_modes: Vec::new(),
+        };
+
+        let loaded = build_bom_from_rows(headers, raw_rows, &mapping).unwrap();
+        assert!(loaded.corrections.is_empty());
+        assert_eq!(loaded.bom.rows[0].model_number, " ab 12 ");
+
+        let rules = PreprocessRules {
+            remove_parentheses: true,
+            expand_ranges: true,
+            fullwidth_to_halfwidth: true,
+            lowercase_to_uppercase: true,
+            designator_pattern: None,
+            keep_range_source: false,
+            max_range_span: DEFAULT_MAX_RANGE_SPAN,
+            expand_descending: false,
+            numeric_columns: Vec::new(),
+        };
+        let processed = preprocess_bom_data(&loaded.bom, &rules).unwrap().bom;
+
+        assert_eq!(processed.rows.len(), 1);
+        assert_eq!(processed.rows[0].part_number, "C1-C3");
+        assert_eq!(processed.rows[0].attributes["型番"], " ab 12 ");
+    }
+
+    #[test]
+    fn test_normalize_number_conventions() {
+        let pairs = [
+            ("1.000", "1000"),
+            ("10,5", "10.5"),
+            ("1.234.567,80", "1,234,567.80"),
+            ("-0,25", "-0.25"),
+            ("2,50", "2.5"),
+        ];
+        for (european, us) in pairs {
+            let a = normalize_number(european, NumberConvention::European);
+            let b = normalize_number(us, NumberConvention::Us);
+            assert!(a.is_some(), "{european}");
+            assert_eq!(a, b, "{european} / {us}");
+        }
+        assert_eq!(
+            normalize_number("1.234.567,80", NumberConvention::European).as_deref(),
+            Some("1234567.8")
+        );
+        assert_eq!(
+            normalize_number("1000", NumberConvention::Us).as_deref(),
+            Some("1000")
+        );
+        assert_eq!(normalize_number("10uF", NumberConvention::Us), None);
+        assert_eq!(normalize_number("1,00", NumberConvention::Us), None);
+        assert_eq!(normalize_number("", NumberConvention::European), None);
+    }
+
+    #[test]
+    fn test_preprocess_normalizes_numeric_columns() {
+        let mut attributes = IndexMap::new();
+        attributes.insert("定格".to_string(), "1.000,5".to_string());
+        attributes.insert("備考".to_string(), "1.000".to_string());
+        attributes.insert("容量".to_string(), "10uF".to_string());
+        let bom = BomData {
+            headers: vec![
+                "部品番号".to_string(),
+                "定格".to_string(),
+                "備考".to_string(),
+                "容量".to_string(),
+            ],
+            rows: vec![BomRow {
+                part_number: "C1".to_string(),
+                model_number: "CAP100".to_string(),
+                attributes,
+                quantity: None,
+            }],
+            verbatim: VerbatimColumns::default(),
+        };
+        let numeric = |column: &str| crate::NumericColumnRule {
+            column: column.to_string(),
+            convention: NumberConvention::European,
+        };
+        let rules = PreprocessRules {
+            remove_parentheses: false,
+            expand_ranges: false,
+            fullwidth_to_halfwidth: false,
+            lowercase_to_uppercase: false,
+            designator_pattern: None,
+            keep_range_source: false,
+            max_range_span: DEFAULT_MAX_RANGE_SPAN,
+            expand_descending: false,
+            numeric_columns: vec![numeric("定格"), numeric("容量"), numeric("数量")],
+        };
+
+        let processed = preprocess_bom_data(&bom, &rules).unwrap();
+        let row = &processed.bom.rows[0];
+
+        assert_eq!(row.attributes["定格"], "1000.5");
+        // 数値列に指定していない列と、数値でない値はそのまま
+        assert_eq!(row.attributes["備考"], "1.000");
+        assert_eq!(row.attributes["容量"], "10uF");
+        assert_eq!(
+            processed.warnings,
+            vec!["数値列 数量 が見つかりません".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_normalize_date_formats() {
+        for input in [
+            "2024/1/5",
+            "2024-01-05",
+            "2024.1.5",
+            "2024年1月5日",
+            "２０２４／０１／０５",
+            "2024-01-05 10:30:00",
+            "45296",
+            "45296.5",
+        ] {
+            assert_eq!(
+                normalize_date(input).as_deref(),
+                Some("2024-01-05"),
+                "{input}"
+            );
+        }
+        assert_eq!(normalize_date("2024/2/30"), None);
+        assert_eq!(normalize_date("1/5"), None);
+        assert_eq!(normalize_date("未定"), None);
+        assert_eq!(normalize_date(""), None);
+    }
+
+    #[test]
+    fn test_detect_column_mapping_by_dictionary() {
+        let headers: Vec<String> = ["No", "備考", "型番", "部品番号", "メーカー"]
+            .iter()
+            .map(|h| h.to_string())
+            .collect();
+        let rows: Vec<Vec<String>> = [
+            ["1", "", "CAP100", "C1", "TDK"],
+            ["2", "", "RES100", "R1", "KOA"],
+        ]
+        .iter()
+        .map(|row| row.iter().map(|v| v.to_string()).collect())
+        .collect();
+
+        let entry = |column_type: &str, pattern: &str| crate::ColumnDictionaryEntry {
+            column_type: column_type.to_string(),
+            display_name: None,
+            patterns: vec![pattern.to_string()],
+        };
+        let dictionary = ColumnDictionary {
+            columns: vec![
+                entry("part_number", "部品番号"),
+                entry("model_number", "型番"),
+                entry("manufacturer", "メーカー"),
+            ],
+        };
+
+        let compiled = CompiledDictionary::new(&dictionary);
+        let mapping = detect_column_mapping(&headers, &rows, &compiled, false).unwrap();
+        assert_eq!(
+            (
+                mapping.part_number,
+                mapping.model_number,
+                mapping.manufacturer
+            ),
+            (3, 2, Some(4))
+        );
+    }
+
+    #[test]
+    fn test_compiled_dictionary_normalizes_patterns() {
+        let entry = |column_type: &str, patterns: &[&str]| crate::ColumnDictionaryEntry {
+            column_type: column_type.to_string(),
+            display_name: None,
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+        };
+        let dictionary = ColumnDictionary {
+            columns: vec![
+                entry(" Part_Number ", &[" Part No ", "  ", "部品 番号"]),
+                entry("part_number", &["ignored"]),
+            ],
+        };
+
+        let compiled = CompiledDictionary::new(&dictionary);
+
+        assert_eq!(compiled.patterns("PART_NUMBER"), ["partno", "部品番号"]);
+        assert!(compiled.patterns("model_number").is_empty());
+    }
+
+    #[test]
+    fn test_detect_column_mapping_heuristic_only() {
+        let headers: Vec<String> = ["No", "Value", "Ref", "Maker"]
+            .iter()
+            .map(|h| h.to_string())
+            .collect();
+        let rows: Vec<Vec<String>> = [
+            ["1", "CAP100", "C1", "TDK"],
+            ["2", "CAP100", "C2", "TDK"],
+            ["3", "RES100", "R1", "KOA"],
+        ]
+        .iter()
+        .map(|row| row.iter().map(|v| v.to_string()).collect())
+        .collect();
+
+        let mapping =
+            detect_column_mapping(&headers, &rows, &CompiledDictionary::default(), true).unwrap();
+        assert_eq!(
+            (
+                mapping.part_number,
+                mapping.model_number,
+                mapping.manufacturer
+            ),
+            (2, 1, None)
+        );
+    }
+
+    #[test]
+    fn test_quantity_like_part_column_warns() {
+        let rows: Vec<Vec<String>> = ["1", "2", "1", "10", "2", "1", "C1", "1", "2", "1"]
+            .iter()
+            .map(|value| vec![value.to_string(), format!("R{}", value)])
+            .collect();
+
+        assert_eq!(part_column_warnings(Some(0), &rows).len(), 1);
+        assert!(part_column_warnings(Some(1), &rows).is_empty());
+        assert!(part_column_warnings(None, &rows).is_empty());
+
+        let serial: Vec<Vec<String>> = (1..=10).map(|n| vec![n.to_string()]).collect();
+        assert!(part_column_warnings(Some(0), &serial).is_empty());
+    }
+
+    #[test]
+    fn test_collect_column_samples() {
+        let rows: Vec<Vec<String>> = [
+            vec!["C1", "CAP100", ""],
+            vec!["C2", "CAP100", ""],
+            vec!["C3", "RES100"],
+            vec!["C4", "CAP100", "TDK"],
+            vec!["C5", "X", ""],
+            vec!["C6", "Y", ""],
+        ]
+        .iter()
+        .map(|row| row.iter().map(|v| v.to_string()).collect())
+        .collect();
+
+        let samples = collect_column_samples(4, &rows);
+        assert_eq!(samples.len(), 4);
+        assert_eq!(samples[0], vec!["C1", "C2", "C3", "C4", "C5"]);
+        assert_eq!(samples[1], vec!["CAP100", "RES100", "X", "Y"]);
+        assert_eq!(samples[2], vec!["TDK"]);
+        assert!(samples[3].is_empty());
+    }
+
+    #[test]
+    fn test_headers_look_like_data() {
+        let dictionary = CompiledDictionary::default();
+        let data_row = vec!["C1".to_string(), "CAP100".to_string(), "2".to_string()];
+        let header_row = vec![
+            "部品番号".to_string(),
+            "型番".to_string(),
+            "数量".to_string(),
+        ];
+
+        assert!(headers_look_like_data(&data_row, &dictionary));
+        assert!(!headers_look_like_data(&header_row, &dictionary));
+        assert_eq!(synthesized_headers(2), vec!["列1", "列2"]);
+    }
+
+    #[test]
+    fn test_optimize_bom_data_strategies() {
+        let row = |part: &str, model: &str, quantity: u32| BomRow {
+            part_number: part.to_string(),
+            model_number: model.to_string(),
+            attributes: IndexMap::new(),
+            quantity: Some(quantity),
+        };
+        let bom = BomData {
+            headers: vec!["部品番号".to_string(), "型番".to_string()],
+            rows: vec![
+                row("R2", "RES1", 1),
+                row("R1", "RES1", 1),
+                row("R2", "RES2", 2),
+            ],
+            verbatim: VerbatimColumns::default(),
+        };
+
+        let mut first = bom.clone();
+        let result = optimize_bom_data(&mut first, MergeStrategy::FirstWins);
+        assert_eq!((result.rows_before, result.rows_after), (3, 2));
+        assert_eq!(result.merged_parts, vec!["R2"]);
+        assert_eq!(first.rows[1].model_number, "RES1");
+
+        let mut last = bom.clone();
+        optimize_bom_data(&mut last, MergeStrategy::LastWins);
+        assert_eq!(last.rows[1].model_number, "RES2");
+
+        let mut concat = bom;
+        optimize_bom_data(&mut concat, MergeStrategy::Concat);
+        assert_eq!(concat.rows[1].model_number, "RES1 / RES2");
+        assert_eq!(concat.rows[1].quantity, Some(3));
+    }
+
+    #[test]
+    fn test_natural_cmp_and_unique_column_values() {
+        assert_eq!(natural_cmp("C2", "C10"), Ordering::Less);
+        assert_eq!(natural_cmp("R10", "C2"), Ordering::Greater);
+        assert_eq!(natural_cmp("C02", "C2"), Ordering::Greater);
+
+        let row = |part: &str, maker: &str| {
+            let mut attributes = IndexMap::new();
+            attributes.insert("メーカー".to_string(), maker.to_string());
+            BomRow {
+                part_number: part.to_string(),
+                model_number: "M".to_string(),
+                attributes,
+                quantity: None,
+            }
+        };
+        let bom = BomData {
+            headers: vec!["部品番号".to_string(), "メーカー".to_string()],
+            rows: vec![
+                row("C10", "TDK"),
+                row("C2", "KOA"),
+                row("C1", "TDK"),
+                row("C3", ""),
+            ],
+            verbatim: VerbatimColumns::default(),
+        };
+
+        assert_eq!(
+            unique_column_values(&bom, "part_number").unwrap(),
+            vec!["C1", "C2", "C3", "C10"]
+        );
+        assert_eq!(
+            unique_column_values(&bom, "メーカー").unwrap(),
+            vec!["KOA", "TDK"]
+        );
+        assert!(unique_column_values(&bom, "色").is_err());
+    }
+
+    #[test]
+    fn test_compute_bom_stats() {
+        let row = |part: &str, model: &str, maker: &str| {
+            let mut attributes = IndexMap::new();
+            attributes.insert("部品番号".to_string(), part.to_string());
+            attributes.insert("型番".to_string(), model.to_string());
+            attributes.insert("メーカー".to_string(), maker.to_string());
+            BomRow {
+                part_number: part.to_string(),
+                model_number: model.to_string(),
+                attributes,
+                quantity: None,
+            }
+        };
+        let bom = BomData {
+            headers: vec![
+                "部品番号".to_string(),
+                "型番".to_string(),
+                "メーカー".to_string(),
+            ],
+            rows: vec![
+                row("C1", "CAP100", "TDK"),
+                row("C1", "CAP100", ""),
+                row("R1", "", "KOA"),
+                row("R2", "RES200", ""),
+            ],
+            verbatim: VerbatimColumns::default(),
+        };
+
+        let stats = compute_bom_stats(&bom, Some("メーカー"));
+        assert_eq!(stats.total_rows, 4);
+        assert_eq!(stats.unique_parts, 3);
+        assert_eq!(stats.duplicate_parts, 1);
+        assert_eq!(stats.empty_model_rows, 1);
+        assert_eq!(stats.empty_manufacturer_rows, Some(2));
+        assert_eq!(stats.column_fill_rates["部品番号"], 1.0);
+        assert_eq!(stats.column_fill_rates["型番"], 0.75);
+        assert_eq!(stats.column_fill_rates["メーカー"], 0.5);
+
+        assert_eq!(compute_bom_stats(&bom, None).empty_manufacturer_rows, None);
+    }
+
+    #[test]
+    fn test_bom_fingerprint_ignores_row_and_attribute_order() {
+        let row = |part: &str, model: &str, attributes: &[(&str, &str)]| BomRow {
+            part_number: part.to_string(),
+            model_number: model.to_string(),
+            attributes: attributes
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            quantity: Some(1),
+        };
+        let bom = |rows: Vec<BomRow>| BomData {
+            headers: vec!["メーカー".to_string(), "備考".to_string()],
+            rows,
+            verbatim: VerbatimColumns::default(),
+        };
+
+        let original = bom(vec![
+            row("C1", "CAP100", &[("メーカー", "TDK"), ("備考", "")]),
+            row("R1", "RES100", &[("メーカー", "KOA"), ("備考", "x")]),
+        ]);
+        let reordered = bom(vec![
+            row("R1", "RES100", &[("備考", "x"), ("メーカー", "KOA")]),
+            row("C1", "CAP100", &[("備考", ""), ("メーカー", "TDK")]),
+        ]);
+        let changed = bom(vec![
+            row("C1", "CAP100", &[("メーカー", "TDK"), ("備考", "")]),
+            row("R1", "RES101", &[("メーカー", "KOA"), ("備考", "x")]),
+        ]);
+
+        let fingerprint = bom_fingerprint(&original);
+        assert_eq!(fingerprint.len(), 64);
+        assert_eq!(fingerprint, bom_fingerprint(&reordered));
+        assert_ne!(fingerprint, bom_fingerprint(&changed));
+    }
+
+    #[test]
+    fn test_merge_columns() {
+        let row = |part: &str, value: &str, unit: &str| {
+            let mut attributes = IndexMap::new();
+            attributes.insert("部品番号".to_string(), part.to_string());
+            attributes.insert("定数".to_string(), value.to_string());
+            attributes.insert("単位".to_string(), unit.to_string());
+            attributes.insert("メーカー".to_string(), "TDK".to_string());
+            BomRow {
+                part_number: part.to_string(),
+                model_number: String::new(),
+                attributes,
+                quantity: None,
+            }
+        };
+        let bom = BomData {
+            headers: ["部品番号", "定数", "単位", "メーカー"]
+                .iter()
+                .map(|h| h.to_string())
+                .collect(),
+            rows: vec![row("C1", "10", "uF"), row("R1", "100", "")],
+            verbatim: VerbatimColumns::default(),
+        };
+        let mapping = ColumnMapping {
+            part_number: 0,
+            model_number: 0,
+            manufacturer: Some(3),
+            quantity: None,
+            keep_columns: None,
+            has_headers: true,
+            verbatim_columns: vec![2, 3],
+            date_columns: Vec::new(),
+            split_column: None,
+            whitespace_modes: Vec::new(),
+        };
+
+        let mut kept = bom.clone();
+        merge_columns(&mut kept, "定数", "単位", "値", " ", false, None).unwrap();
+        assert_eq!(kept.headers.len(), 5);
+        assert_eq!(kept.rows[0].attributes["値"], "10 uF");
+        assert_eq!(kept.rows[1].attributes["値"], "100");
+
+        let mut dropped = bom.clone();
+        let mut shifted = mapping.clone();
+        merge_columns(
+            &mut dropped,
+            "定数",
+            "単位",
+            "値",
+            "",
+            true,
+            Some(&mut shifted),
+        )
+        .unwrap();
+        assert_eq!(dropped.headers, vec!["部品番号", "メーカー", "値"]);
+        assert!(!dropped.rows[0].attributes.contains_key("定数"));
+        assert_eq!(shifted.manufacturer, Some(1));
+        assert_eq!(shifted.verbatim_columns, vec![1]);
+
+        let mut unchanged = bom.clone();
+        let mut key_mapping = mapping.clone();
+        assert!(merge_columns(
+            &mut unchanged,
+            "定数",
+            "メーカー",
+            "値",
+            "",
+            true,
+            Some(&mut key_mapping)
+        )
+        .is_err());
+        assert_eq!(unchanged.headers, bom.headers);
+        assert!(merge_columns(&mut unchanged, "定数", "単位", "単位", "", false, None).is_err());
+    }
+
+    #[test]
+    fn test_split_column() {
+        let row = |part: &str, value: &str| {
+            let mut attributes = IndexMap::new();
+            attributes.insert("部品番号".to_string(), part.to_string());
+            attributes.insert("値".to_string(), value.to_string());
+            BomRow {
+                part_number: part.to_string(),
+                model_number: String::new(),
+                attributes,
+                quantity: None,
+            }
+        };
+        let mut bom = BomData {
+            headers: vec!["部品番号".to_string(), "値".to_string()],
+            rows: vec![
+                row("C1", "10uF/25V"),
+                row("C2", "1uF"),
+                row("C3", "4.7uF/50V/X7R"),
+                row("C4", ""),
+            ],
+            verbatim: VerbatimColumns::default(),
+        };
+        let targets = vec!["容量".to_string(), "耐圧".to_string()];
+
+        let warnings = split_column(&mut bom, "値", "/", &targets, true, None).unwrap();
+
+        assert_eq!(bom.headers, vec!["部品番号", "容量", "耐圧"]);
+        let values: Vec<(&str, &str)> = bom
+            .rows
+            .iter()
+            .map(|row| {
+                (
+                    row.attributes["容量"].as_str(),
+                    row.attributes["耐圧"].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![("10uF", "25V"), ("1uF", ""), ("4.7uF", "50V/X7R"), ("", "")]
+        );
+        assert!(!bom.rows[0].attributes.contains_key("値"));
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("2行目"));
+
+        assert!(split_column(&mut bom, "容量", "/", &targets, false, None).is_err());
+    }
+
+    #[test]
+    fn test_rename_header_rekeys_attributes() {
+        let mut attributes = IndexMap::new();
+        attributes.insert("COL7".to_string(), "10uF".to_string());
+        attributes.insert("型番".to_string(), "CAP100".to_string());
+        let mut bom = BomData {
+            headers: vec!["COL7".to_string(), "型番".to_string()],
+            rows: vec![BomRow {
+                part_number: "C1".to_string(),
+                model_number: "CAP100".to_string(),
+                attributes,
+                quantity: None,
+            }],
+            verbatim: VerbatimColumns::default(),
+        };
+
+        rename_header(&mut bom, "COL7", "容量").unwrap();
+
+        assert_eq!(bom.headers, vec!["容量", "型番"]);
+        assert_eq!(
+            bom.rows[0].attributes.get("容量"),
+            Some(&"10uF".to_string())
+        );
+        assert!(!bom.rows[0].attributes.contains_key("COL7"));
+        let keys: Vec<&String> = bom.rows[0].attributes.keys().collect();
+        assert_eq!(keys, vec!["容量", "型番"]);
+
+        // セッション保存と同じJSON経由でも属性の並び順が保たれる
+        let json = serde_json::to_string(&bom).unwrap();
+        let restored: BomData = serde_json::from_str(&json).unwrap();
+        let restored_keys: Vec<&String> = restored.rows[0].attributes.keys().collect();
+        assert_eq!(restored_keys, keys);
+        assert!(rename_header(&mut bom, "容量", "型番").is_err());
+        assert!(rename_header(&mut bom, "COL7", "値").is_err());
+    }
+
+    #[test]
+    fn test_rename_header_keeps_verbatim_column() {
+        let mut attributes = IndexMap::new();
+        attributes.insert("備考".to_string(), "ｶﾀｶﾅ".to_string());
+        let mut bom = BomData {
+            headers: vec!["備考".to_string()],
+            rows: vec![BomRow {
+                part_number: "C1".to_string(),
+                model_number: "CAP100".to_string(),
+                attributes,
+                quantity: None,
+            }],
+            verbatim: VerbatimColumns {
+                attributes: vec!["備考".to_string()],
+                ..VerbatimColumns::default()
+            },
+        };
+
+        rename_header(&mut bom, "備考", "メモ").unwrap();
+
+        assert_eq!(bom.verbatim.attributes, vec!["メモ"]);
+    }
+
+    #[test]
+    fn test_parse_quantity() {
+        assert_eq!(parse_quantity("12"), Some(12));
+        assert_eq!(parse_quantity("1,000"), Some(1000));
+        assert_eq!(parse_quantity("10 pcs"), Some(10));
+        assert_eq!(parse_quantity("５"), Some(5));
+        assert_eq!(parse_quantity("１，２００個"), Some(1200));
+        assert_eq!(parse_quantity(""), None);
+        assert_eq!(parse_quantity("pcs"), None);
+        assert_eq!(parse_quantity("1.5"), None);
+        assert_eq!(parse_quantity("1-2"), None);
+    }
+
     #[test]
     fn test_standardize_string() {
         assert_eq!(standardize_string("ABC123"), "ABC123");
@@ -1128,4 +4338,550 @@ mod tests {
         assert_eq!(standardize_string("abc\n123"), "ABC123");
         assert_eq!(standardize_string("A B C"), "ABC");
     }
+
+    #[test]
+    fn test_standardize_string_whitespace_modes() {
+        let input = "  10k  ohm\n1/4 w ";
+        assert_eq!(
+            standardize_string_with(input, WhitespaceMode::Strip),
+            "10KOHM1/4W"
+        );
+        assert_eq!(
+            standardize_string_with(input, WhitespaceMode::Collapse),
+            "10K OHM 1/4 W"
+        );
+        assert_eq!(
+            standardize_string_with(input, WhitespaceMode::Keep),
+            "  10K  OHM 1/4 W "
+        );
+
+        let mut mapping = ColumnMapping {
+            part_number: 0,
+            model_number: 1,
+            manufacturer: None,
+            quantity: None,
+            keep_columns: None,
+            has_headers: true,
+            verbatim_columns: Vec::new(),
+            date_columns: Vec::new(),
+            split_column: None,
+            whitespace_modes: vec![(2, WhitespaceMode::Collapse)],
+        };
+        let headers = vec![
+            "部品番号".to_string(),
+            "型番".to_string(),
+            "品名".to_string(),
+        ];
+        let raw_rows = vec![vec![
+            "C 1".to_string(),
+            "CAP 100".to_string(),
+            "chip  cap".to_string(),
+        ]];
+        let loaded = build_bom_from_rows(headers, raw_rows, &mapping).unwrap();
+        let row = &loaded.bom.rows[0];
+        assert_eq!(
+            (row.part_number.as_str(), row.model_number.as_str()),
+            ("C1", "CAP100")
+        );
+        assert_eq!(row.attributes["品名"], "CHIP CAP");
+
+        let json = r#"{"part_number":0,"model_number":1,"whitespace_modes":[[1,"keep"]]}"#;
+        mapping = serde_json::from_str(json).unwrap();
+        assert_eq!(mapping.whitespace_modes, vec![(1, WhitespaceMode::Keep)]);
+    }
+
+    #[test]
+    fn test_decoding_reader_across_chunks() {
+        let text = "部品番号,型番\nC1,コンデンサ\n";
+        let (sjis, _, _) = SHIFT_JIS.encode(text);
+        for (bytes, encoding) in [
+            (sjis.to_vec(), SHIFT_JIS),
+            (text.as_bytes().to_vec(), UTF_8),
+        ] {
+            for chunk_size in [1, 3, 1024] {
+                let mut decoded = String::new();
+                DecodingReader::new(Cursor::new(bytes.clone()), encoding, chunk_size)
+                    .read_to_string(&mut decoded)
+                    .unwrap();
+                assert_eq!(decoded, text);
+            }
+        }
+
+        // 先頭はUTF-8として正しくても、途中に変換できないバイトがあれば読み込みを中止する
+        let mut broken = text.as_bytes().to_vec();
+        broken.extend_from_slice(&[0x83, 0x4A, b'\n']);
+        let err = DecodingReader::new(Cursor::new(broken.clone()), UTF_8, 8)
+            .read_to_string(&mut String::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        let mut decoded = String::new();
+        DecodingReader::new(Cursor::new(broken), SHIFT_JIS, 8)
+            .read_to_string(&mut decoded)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_decode_text_utf16() {
+        let text = "部品番号,型番\nC1,CAP100\n";
+        let mut le = vec![0xFF, 0xFE];
+        let mut be = vec![0xFE, 0xFF];
+        for unit in text.encode_utf16() {
+            le.extend_from_slice(&unit.to_le_bytes());
+            be.extend_from_slice(&unit.to_be_bytes());
+        }
+        assert_eq!(detect_text_encoding(&le), (UTF_16LE, 2));
+        assert_eq!(detect_text_encoding(&be), (UTF_16BE, 2));
+        assert!(decode_text(&le[..le.len() - 1]).is_err());
+
+        for bytes in [&le, &be] {
+            let decoded = decode_text(bytes).unwrap();
+            let mut reader = ReaderBuilder::new().from_reader(decoded.as_bytes());
+            let headers: Vec<String> = reader.headers().unwrap().iter().map(String::from).collect();
+            assert_eq!(headers, vec!["部品番号", "型番"]);
+        }
+
+        // 大きなファイル用の逐次読み込みも同じ判定を使う
+        let path = std::env::temp_dir().join(format!("bom_utf16_{}.csv", std::process::id()));
+        fs::write(&path, &le).unwrap();
+        let (mut stream, head) = open_streaming_csv(&path.to_string_lossy()).unwrap();
+        let mut streamed = String::new();
+        stream.read_to_string(&mut streamed).unwrap();
+        assert_eq!(streamed, text);
+        assert!(head.starts_with("部品番号"));
+        let _ = fs::remove_file(&path);
+
+        // BOMなしのUTF-8はShift-JISとして読まない
+        assert_eq!(decode_text(text.as_bytes()).unwrap(), text);
+        let (sjis, _, _) = SHIFT_JIS.encode(text);
+        assert_eq!(decode_text(&sjis).unwrap(), text);
+    }
+
+    #[test]
+    fn test_find_sheet_index() {
+        let sheets = vec![
+            "表紙".to_string(),
+            "部品表".to_string(),
+            "Notes".to_string(),
+        ];
+
+        let by_name = SheetSelector::Name("部品表".to_string());
+        assert_eq!(find_sheet_index(&sheets, &by_name).unwrap(), 1);
+        let loose = SheetSelector::Name(" notes ".to_string());
+        assert_eq!(find_sheet_index(&sheets, &loose).unwrap(), 2);
+        assert_eq!(
+            find_sheet_index(&sheets, &SheetSelector::Index(0)).unwrap(),
+            0
+        );
+
+        let err = find_sheet_index(&sheets, &SheetSelector::Name("BOM".to_string())).unwrap_err();
+        assert!(matches!(err, BomProcessorError::InvalidInput(_)));
+        assert!(err.to_string().contains("「BOM」"));
+        assert!(err.to_string().contains("表紙, 部品表, Notes"));
+        assert!(find_sheet_index(&sheets, &SheetSelector::Index(3)).is_err());
+
+        let selector: SheetSelector = serde_json::from_str(r#""部品表""#).unwrap();
+        assert_eq!(selector, by_name);
+        let selector: SheetSelector = serde_json::from_str("2").unwrap();
+        assert_eq!(selector, SheetSelector::Index(2));
+    }
+
+    #[test]
+    fn test_check_file_size() {
+        let path = std::env::temp_dir().join(format!("bom_size_{}.csv", std::process::id()));
+        fs::write(&path, vec![b'a'; 2048]).unwrap();
+        let path_str = path.to_string_lossy();
+
+        assert_eq!(check_file_size(&path_str, 4096).unwrap(), 2048);
+        let err = check_file_size(&path_str, 1024).unwrap_err();
+        assert!(err.to_string().contains("ファイルが大きすぎます"));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_load_headerless_csv() {
+        let path = std::env::temp_dir().join(format!("bom_headerless_{}.csv", std::process::id()));
+        fs::write(&path, "C1,CAP100,10uF\nR1,RES100,1k\n").unwrap();
+        let mapping = ColumnMapping {
+            part_number: 0,
+            model_number: 1,
+            manufacturer: None,
+            quantity: None,
+            keep_columns: None,
+            has_headers: false,
+            verbatim_columns: Vec::new(),
+            date_columns: Vec::new(),
+            split_column: None,
+            whitespace_modes: Vec::new(),
+        };
+
+        let result = block_on(load_bom_file(
+            &path.to_string_lossy(),
+            &mapping,
+            u64::MAX,
+            None,
+        ))
+        .unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(result.bom.headers, vec!["列1", "列2", "列3"]);
+        assert_eq!(result.bom.rows.len(), 2);
+        assert_eq!(result.bom.rows[0].part_number, "C1");
+        assert_eq!(result.bom.rows[0].model_number, "CAP100");
+        assert_eq!(result.bom.rows[0].attributes["列3"], "10UF");
+    }
+
+    #[test]
+    fn test_text_delimiter_by_extension() {
+        let tab = "部品番号\t型番\nC1\tCAP100\n";
+        let comma = "部品番号,型番\nC1,CAP100\n";
+        assert_eq!(text_delimiter("bom.tsv", comma), b'\t');
+        assert_eq!(text_delimiter("BOM.TXT", tab), b'\t');
+        assert_eq!(text_delimiter("bom.txt", comma), b',');
+        assert_eq!(text_delimiter("bom.csv", tab), b',');
+        assert!(SUPPORTED_BOM_EXTENSIONS.contains(&"tsv"));
+    }
+
+    #[test]
+    fn test_parse_registered_name_csv_by_header() {
+        let content = "No,区分,登録名,部品型番\n1,IC,CPU-MAIN,IC001\n2,R,R-10K,RES001\n";
+        let list = parse_registered_name_csv(content, &ColumnDictionary::default()).unwrap();
+
+        assert_eq!(list.entries.len(), 2);
+        assert_eq!(list.entries[0].part_model, "IC001");
+        assert_eq!(list.entries[0].registered_name, "CPU-MAIN");
+    }
+
+    #[test]
+    fn test_parse_registered_name_csv_with_manufacturer() {
+        let content = "No,メーカー,登録名,部品型番\n1,Intel,CPU-MAIN,IC001\n2,,R-10K,RES001\n";
+        let list = parse_registered_name_csv(content, &ColumnDictionary::default()).unwrap();
+
+        assert_eq!(list.entries.len(), 2);
+        assert_eq!(list.entries[0].manufacturer.as_deref(), Some("Intel"));
+        assert_eq!(list.entries[1].manufacturer, None);
+
+        // 位置による割り当てでも、3列目はメーカー列と分かる場合のみメーカーとして読む
+        let content = "A,B,備考\nIC001,CPU-MAIN,要確認\n";
+        let list = parse_registered_name_csv(content, &ColumnDictionary::default()).unwrap();
+        assert_eq!(list.entries[0].registered_name, "CPU-MAIN");
+        assert_eq!(list.entries[0].manufacturer, None);
+
+        let content = "A,B,メーカー\nIC001,CPU-MAIN,Intel\n";
+        let list = parse_registered_name_csv(content, &ColumnDictionary::default()).unwrap();
+        assert_eq!(list.entries[0].manufacturer.as_deref(), Some("Intel"));
+    }
+
+    #[test]
+    fn test_apply_registered_names_with_manufacturer() {
+        let mut murata = IndexMap::new();
+        murata.insert("メーカー".to_string(), "MURATA".to_string());
+        let mut tdk = IndexMap::new();
+        tdk.insert("メーカー".to_string(), "TDK".to_string());
+        let mut bom = BomData {
+            headers: vec!["メーカー".to_string()],
+            rows: vec![
+                BomRow {
+                    part_number: "C1".to_string(),
+                    model_number: "CAP100".to_string(),
+                    attributes: murata,
+                    quantity: None,
+                },
+                BomRow {
+                    part_number: "C2".to_string(),
+                    model_number: "CAP100".to_string(),
+                    attributes: tdk,
+                    quantity: None,
+                },
+            ],
+            verbatim: VerbatimColumns::default(),
+        };
+        let list = RegisteredNameList {
+            entries: vec![
+                RegisteredNameEntry {
+                    part_model: "CAP100".to_string(),
+                    registered_name: "CAP-MURATA".to_string(),
+                    manufacturer: Some("Murata".to_string()),
+                },
+                RegisteredNameEntry {
+                    part_model: "CAP100".to_string(),
+                    registered_name: "CAP-GENERIC".to_string(),
+                    manufacturer: None,
+                },
+            ],
+        };
+
+        apply_registered_names_to_bom(
+            &mut bom,
+            &Some(list),
+            &None,
+            Some("メーカー"),
+            RegisteredNamePolicy::default(),
+        );
+
+        assert_eq!(bom.rows[0].attributes.get("登録名").unwrap(), "CAP-MURATA");
+        assert_eq!(bom.rows[1].attributes.get("登録名").unwrap(), "CAP-GENERIC");
+    }
+
+    #[test]
+    fn test_parse_registered_name_csv_positional_fallback() {
+        let content = "A,B\nIC001,CPU-MAIN\n";
+        let list = parse_registered_name_csv(content, &ColumnDictionary::default()).unwrap();
+
+        assert_eq!(list.entries.len(), 1);
+        assert_eq!(list.entries[0].part_model, "IC001");
+        assert_eq!(list.entries[0].registered_name, "CPU-MAIN");
+    }
+
+    #[test]
+    fn test_validate_registered_name_list_flags_blank_cells() {
+        let content = "型番,登録名\nABC,名前A\n,名前B\nDEF, \n";
+        let list = parse_registered_name_csv(content, &ColumnDictionary::default()).unwrap();
+        let errors = validate_registered_name_list(&list);
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            (errors[0].row_number, errors[0].field.as_str()),
+            (2, "型番")
+        );
+        assert_eq!(
+            (errors[1].row_number, errors[1].field.as_str()),
+            (3, "登録名")
+        );
+    }
+
+    #[test]
+    fn test_find_parts_missing_manufacturer() {
+        let row = |part: &str, maker: &str| {
+            let mut attributes = IndexMap::new();
+            attributes.insert("メーカー".to_string(), maker.to_string());
+            BomRow {
+                part_number: part.to_string(),
+                model_number: "M".to_string(),
+                attributes,
+                quantity: None,
+            }
+        };
+        let bom = BomData {
+            headers: vec!["部品番号".to_string(), "メーカー".to_string()],
+            rows: vec![row("C1", "ACME"), row("C2", " "), row("C3", "")],
+            verbatim: VerbatimColumns::default(),
+        };
+        let dictionary = ColumnDictionary::default();
+
+        assert_eq!(
+            find_parts_missing_manufacturer(&bom, &dictionary, 1).unwrap(),
+            vec!["C2"]
+        );
+        assert_eq!(
+            find_parts_missing_manufacturer(&bom, &dictionary, 10)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_dedupe_registered_names_reports_conflicts() {
+        let entry = |model: &str, name: &str| RegisteredNameEntry {
+            part_model: model.to_string(),
+            registered_name: name.to_string(),
+            manufacturer: None,
+        };
+        let list = RegisteredNameList {
+            entries: vec![
+                entry("IC001", "CPU-MAIN"),
+                entry("RES001", "R-10K"),
+                entry("IC001", "CPU-SUB"),
+                entry("RES001", "R-10K"),
+            ],
+        };
+
+        let (deduped, conflicts) = dedupe_registered_names(list);
+
+        assert_eq!(deduped.entries.len(), 2);
+        assert_eq!(deduped.entries[0].registered_name, "CPU-MAIN");
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].part_model, "IC001");
+        assert_eq!(conflicts[0].kept_name, "CPU-MAIN");
+        assert_eq!(conflicts[0].discarded_name, "CPU-SUB");
+    }
+
+    #[test]
+    fn test_override_precedence_part_then_model_then_registered() {
+        let row = |part: &str, model: &str| BomRow {
+            part_number: part.to_string(),
+            model_number: model.to_string(),
+            attributes: IndexMap::new(),
+            quantity: None,
+        };
+        let mut bom = BomData {
+            headers: vec![],
+            rows: vec![
+                row("R1", "RES001"),
+                row("R2", "RES001"),
+                row("R3", "RES002"),
+            ],
+            verbatim: VerbatimColumns::default(),
+        };
+        let list = RegisteredNameList {
+            entries: vec![
+                RegisteredNameEntry {
+                    part_model: "RES001".to_string(),
+                    registered_name: "R-REGISTERED".to_string(),
+                    manufacturer: None,
+                },
+                RegisteredNameEntry {
+                    part_model: "RES002".to_string(),
+                    registered_name: "R-OTHER".to_string(),
+                    manufacturer: None,
+                },
+            ],
+        };
+        let entry = |key: &str, name: &str, match_field| crate::OverrideEntry {
+            part_number: key.to_string(),
+            registered_name: name.to_string(),
+            match_field,
+        };
+        let overrides = OverrideList {
+            entries: vec![
+                entry("RES001", "R-BY-MODEL", OverrideMatchField::ModelNumber),
+                entry("R2", "R-BY-PART", OverrideMatchField::PartNumber),
+            ],
+        };
+
+        apply_registered_names_to_bom(
+            &mut bom,
+            &Some(list),
+            &Some(overrides),
+            None,
+            RegisteredNamePolicy::default(),
+        );
+
+        let names: Vec<&str> = bom
+            .rows
+            .iter()
+            .map(|r| r.attributes["登録名"].as_str())
+            .collect();
+        assert_eq!(names, vec!["R-BY-MODEL", "R-BY-PART", "R-OTHER"]);
+
+        let legacy: crate::OverrideEntry =
+            serde_json::from_str(r#"{"part_number":"R1","registered_name":"X"}"#).unwrap();
+        assert_eq!(legacy.match_field, OverrideMatchField::PartNumber);
+    }
+
+    #[test]
+    fn test_registered_name_policy_orders_sources() {
+        let row = |part: &str, model: &str| BomRow {
+            part_number: part.to_string(),
+            model_number: model.to_string(),
+            attributes: IndexMap::new(),
+            quantity: None,
+        };
+        let bom = BomData {
+            headers: vec![],
+            rows: vec![row("R1", "RES001"), row("U1", "IC001"), row("R2", "RES002")],
+            verbatim: VerbatimColumns::default(),
+        };
+        let list = Some(RegisteredNameList {
+            entries: ["RES001", "RES002"]
+                .iter()
+                .map(|model| RegisteredNameEntry {
+                    part_model: model.to_string(),
+                    registered_name: format!("REG-{model}"),
+                    manufacturer: None,
+                })
+                .collect(),
+        });
+        let overrides = Some(OverrideList {
+            entries: ["R1", "U1"]
+                .iter()
+                .map(|part| crate::OverrideEntry {
+                    part_number: part.to_string(),
+                    registered_name: format!("OVR-{part}"),
+                    match_field: OverrideMatchField::PartNumber,
+                })
+                .collect(),
+        });
+        let resolve = |override_first: bool| {
+            let policy = RegisteredNamePolicy { override_first };
+            let preview = preview_registered_names(&bom, &list, &overrides, None, policy, 10);
+            preview
+                .rows
+                .into_iter()
+                .map(|r| (r.resolved_name.unwrap(), r.source))
+                .collect::<Vec<_>>()
+        };
+
+        // 上書き優先（既定）: 両方に該当する R1 は上書きリストの値
+        assert!(RegisteredNamePolicy::default().override_first);
+        assert_eq!(
+            resolve(true),
+            vec![
+                ("OVR-R1".to_string(), NameSource::Override),
+                ("OVR-U1".to_string(), NameSource::Override),
+                ("REG-RES002".to_string(), NameSource::Registered),
+            ]
+        );
+        // 登録名優先: R1 は登録名リストの値、登録名のない U1 には上書きを適用する
+        assert_eq!(
+            resolve(false),
+            vec![
+                ("REG-RES001".to_string(), NameSource::Registered),
+                ("OVR-U1".to_string(), NameSource::Override),
+                ("REG-RES002".to_string(), NameSource::Registered),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_preview_registered_names_reports_sources() {
+        let row = |part: &str, model: &str| BomRow {
+            part_number: part.to_string(),
+            model_number: model.to_string(),
+            attributes: IndexMap::new(),
+            quantity: None,
+        };
+        let bom = BomData {
+            headers: vec![],
+            rows: vec![row("R1", "RES001"), row("R2", "RES001"), row("U1", "IC999")],
+            verbatim: VerbatimColumns::default(),
+        };
+        let list = RegisteredNameList {
+            entries: vec![RegisteredNameEntry {
+                part_model: "RES001".to_string(),
+                registered_name: "R-10K".to_string(),
+                manufacturer: None,
+            }],
+        };
+        let overrides = OverrideList {
+            entries: vec![crate::OverrideEntry {
+                part_number: "R2".to_string(),
+                registered_name: "R-10K-SPECIAL".to_string(),
+                match_field: OverrideMatchField::PartNumber,
+            }],
+        };
+
+        let preview = preview_registered_names(
+            &bom,
+            &Some(list),
+            &Some(overrides),
+            None,
+            RegisteredNamePolicy::default(),
+            2,
+        );
+
+        assert_eq!(preview.total_rows, 3);
+        assert_eq!(preview.rows.len(), 2);
+        assert_eq!(preview.rows[0].source, NameSource::Registered);
+        assert_eq!(preview.rows[1].source, NameSource::Override);
+        assert_eq!(
+            preview.rows[1].resolved_name.as_deref(),
+            Some("R-10K-SPECIAL")
+        );
+        assert_eq!(preview.summary.get("none"), Some(&1));
+        assert!(bom
+            .rows
+            .iter()
+            .all(|r| !r.attributes.contains_key("登録名")));
+    }
 }
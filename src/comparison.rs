@@ -1,64 +1,645 @@
-use crate::{BomData, ComparisonResult, ComparisonRow};
+use crate::bom_processor::{fullwidth_to_halfwidth, standardize_string};
+use crate::error::AppError;
+use crate::file_handler;
+use crate::{BomData, ComparisonResult, ComparisonRow, DuplicatePart};
+use indexmap::IndexMap;
 use rayon::prelude::*;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
-/// 部品表AとBを比較する
-pub fn perform_comparison(bom_a: &BomData, bom_b: &BomData) -> ComparisonResult {
-    let map_a: HashMap<String, &crate::BomRow> = bom_a
-        .rows
-        .iter()
-        .map(|row| (row.part_number.clone(), row))
-        .collect();
-    let map_b: HashMap<String, &crate::BomRow> = bom_b
-        .rows
+const MAX_LISTED_DUPLICATES: usize = 20;
+/// save_comparison_result が対応する出力形式
+pub const COMPARISON_OUTPUT_FORMATS: &[&str] = &["csv", "txt", "json", "diff", "html"];
+// 正規化の不一致を疑う条件（そのままの一致率が低く、正規化後に大きく改善する場合）
+const NORMALIZATION_HINT_MAX_RAW_RATE: f64 = 0.5;
+const NORMALIZATION_HINT_MIN_NORMALIZED_RATE: f64 = 0.8;
+
+// 比較オプション
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CompareOptions {
+    /// 比較対象から除外する部品番号（部分一致、または `*`/`?` を含む場合はワイルドカード）
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    /// 型番に加えて変更判定に使用する属性列（空の場合は型番のみで判定）
+    #[serde(default)]
+    pub compare_attributes: Vec<String>,
+    /// 型番の変更判定にのみ適用する正規化（部品番号の照合と出力する型番は変えない）
+    #[serde(default)]
+    pub model_normalize: ModelNormalize,
+    /// Aのみ・Bのみの部品のうち、型番・数量・属性がすべて一致する組を部品番号変更として扱う（推定）
+    #[serde(default)]
+    pub detect_renames: bool,
+    /// 型番を比較しない（型番列のない部品表との比較用。共通部品は属性のみで変更を判定する）
+    /// 指定がなくても、いずれかの部品表の型番がすべて空の場合は比較時に有効になる
+    #[serde(default)]
+    pub ignore_models: bool,
+    /// 型番・比較属性が定数表記（"10k"、"0.1uF" など）の場合は数値として比較する
+    /// "10.0k" と "10k"、"0.1uF" と "100nF" を同じ値として扱う
+    #[serde(default)]
+    pub value_equivalence: bool,
+}
+
+/// 定数表記を数値として比較するときの許容誤差（相対値）
+const VALUE_EQUIVALENCE_TOLERANCE: f64 = 1e-9;
+
+/// 型番比較時の正規化（手入力による大文字・小文字、全角・半角、前後の空白の揺れを無視する）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ModelNormalize {
+    #[serde(default)]
+    pub case_insensitive: bool,
+    #[serde(default)]
+    pub fullwidth: bool,
+    #[serde(default)]
+    pub trim: bool,
+}
+
+impl ModelNormalize {
+    fn apply(&self, model: &str) -> String {
+        let mut value = if self.trim {
+            model.trim().to_string()
+        } else {
+            model.to_string()
+        };
+        if self.fullwidth {
+            value = fullwidth_to_halfwidth(&value);
+        }
+        if self.case_insensitive {
+            value = value.to_uppercase();
+        }
+        value
+    }
+}
+
+impl CompareOptions {
+    /// 正規化後の型番が異なるか（型番を比較しない場合は常に false）
+    fn models_differ(&self, model_a: &str, model_b: &str) -> bool {
+        !self.ignore_models
+            && model_a != model_b
+            && self.model_normalize.apply(model_a) != self.model_normalize.apply(model_b)
+            && !(self.value_equivalence && values_equivalent(model_a, model_b))
+    }
+
+    fn attributes_differ(&self, value_a: Option<&String>, value_b: Option<&String>) -> bool {
+        match (value_a, value_b) {
+            (Some(a), Some(b)) => a != b && !(self.value_equivalence && values_equivalent(a, b)),
+            (a, b) => a != b,
+        }
+    }
+
+    /// 比較する部品表に合わせたオプション（型番が1件もない部品表がある場合は型番を比較しない）
+    fn for_boms(&self, bom_a: &BomData, bom_b: &BomData) -> Cow<'_, CompareOptions> {
+        if self.ignore_models || (self.has_models(bom_a) && self.has_models(bom_b)) {
+            return Cow::Borrowed(self);
+        }
+        Cow::Owned(CompareOptions {
+            ignore_models: true,
+            ..self.clone()
+        })
+    }
+
+    /// 除外対象以外の行に空でない型番が1件でもあるか
+    fn has_models(&self, bom: &BomData) -> bool {
+        bom.rows
+            .iter()
+            .any(|row| !row.model_number.trim().is_empty() && !self.is_excluded(&row.part_number))
+    }
+
+    fn is_excluded(&self, part_number: &str) -> bool {
+        let target = part_number.to_uppercase();
+        self.exclude_patterns.iter().any(|pattern| {
+            let pattern = pattern.trim().to_uppercase();
+            if pattern.is_empty() {
+                false
+            } else if pattern.contains('*') || pattern.contains('?') {
+                glob_match(&pattern, &target)
+            } else {
+                target.contains(&pattern)
+            }
+        })
+    }
+}
+
+/// 比較対象属性のうち値が異なる列名を返す
+fn changed_attributes(
+    row_a: &crate::BomRow,
+    row_b: &crate::BomRow,
+    options: &CompareOptions,
+) -> Vec<String> {
+    options
+        .compare_attributes
         .iter()
-        .map(|row| (row.part_number.clone(), row))
-        .collect();
+        .filter(|name| {
+            options.attributes_differ(row_a.attributes.get(*name), row_b.attributes.get(*name))
+        })
+        .cloned()
+        .collect()
+}
 
-    let (common_parts, a_only_parts) = rayon::join(
-        || find_common_parts(&map_a, &map_b),
-        || find_a_only_parts(&map_a, &map_b),
-    );
-    let (b_only_parts, modified_parts) = rayon::join(
-        || find_b_only_parts(&map_a, &map_b),
-        || find_modified_parts(&map_a, &map_b),
+/// 定数表記を（値, 単位）に変換する。"4k7" のように接頭辞を小数点の位置に置く表記にも対応する
+/// 抵抗の単位（Ω / ohm / R）は単位なしと同じに扱う。定数表記でない文字列は None を返す
+fn parse_si_value(value: &str) -> Option<(f64, String)> {
+    let value = value.trim();
+    let number_end = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+        .unwrap_or(value.len());
+    let (number, rest) = value.split_at(number_end);
+    let rest = rest.trim_start();
+
+    let (multiplier, after_prefix) = split_si_prefix(rest)?;
+    // "4k7" → 4.7k
+    let fraction_len = after_prefix
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(after_prefix.len());
+    let (fraction, unit) = after_prefix.split_at(fraction_len);
+    let number = if fraction.is_empty() {
+        number.to_string()
+    } else if multiplier != 1.0 && !number.contains('.') {
+        format!("{number}.{fraction}")
+    } else {
+        return None;
+    };
+    if number.is_empty() || !number.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let magnitude: f64 = number.parse().ok()?;
+
+    // 抵抗値は単位なしで書かれることが多いため、Ωは単位なしと同じに扱う
+    let unit = match si_unit(unit)? {
+        "ohm" => "",
+        unit => unit,
+    };
+    Some((magnitude * multiplier, unit.to_string()))
+}
+
+/// 対応する単位を小文字の表記にする（単位なしは ""、未対応の単位は None）
+fn si_unit(unit: &str) -> Option<&'static str> {
+    match unit.trim().to_lowercase().as_str() {
+        "" => Some(""),
+        "ω" | "ohm" | "ohms" | "r" => Some("ohm"),
+        "f" => Some("f"),
+        "h" => Some("h"),
+        "v" => Some("v"),
+        "a" => Some("a"),
+        "w" => Some("w"),
+        "hz" => Some("hz"),
+        _ => None,
+    }
+}
+
+/// 接頭辞の倍率と残りの文字列を返す（接頭辞がない場合は倍率1）。判別できない場合は None
+/// 読み込み時に値は大文字に揃えられる（"100nF" → "100NF"）ため、大文字の P / N / U は
+/// 単位が続く場合に限りピコ・ナノ・マイクロとみなす。M はミリとメガを区別できないため、
+/// F / H / V / A / W が続く場合はミリ、Ω・Hz が続く場合と "MEG" はメガとし、単位なしの M は比較しない
+fn split_si_prefix(rest: &str) -> Option<(f64, &str)> {
+    if let Some(prefix) = rest.get(..3) {
+        if prefix.eq_ignore_ascii_case("meg") {
+            return Some((1e6, &rest[3..]));
+        }
+    }
+    let mut chars = rest.chars();
+    let prefix = match chars.next() {
+        Some(prefix) => prefix,
+        None => return Some((1.0, rest)),
+    };
+    let after = chars.as_str();
+    // "4K7" のように接頭辞の後に小数部が続く場合は、その後の単位を見る
+    let unit_after = si_unit(after.trim_start_matches(|c: char| c.is_ascii_digit()));
+    let has_unit = matches!(unit_after, Some(unit) if !unit.is_empty());
+
+    let multiplier = match prefix {
+        'p' => 1e-12,
+        'n' => 1e-9,
+        // 'Μ' は µ を大文字にしたもの
+        'u' | 'µ' | 'μ' | 'Μ' => 1e-6,
+        'm' => 1e-3,
+        'k' | 'K' => 1e3,
+        'G' => 1e9,
+        'P' if has_unit => 1e-12,
+        'N' if has_unit => 1e-9,
+        'U' if has_unit => 1e-6,
+        'M' => match unit_after {
+            Some("f" | "h" | "v" | "a" | "w") => 1e-3,
+            Some("ohm" | "hz") => 1e6,
+            _ => return None,
+        },
+        _ => return Some((1.0, rest)),
+    };
+    Some((multiplier, after))
+}
+
+/// 2つの定数表記が許容誤差内で同じ値か（単位が異なる場合や、定数表記でない場合は false）
+fn values_equivalent(value_a: &str, value_b: &str) -> bool {
+    match (parse_si_value(value_a), parse_si_value(value_b)) {
+        (Some((a, unit_a)), Some((b, unit_b))) => {
+            unit_a == unit_b && (a - b).abs() <= VALUE_EQUIVALENCE_TOLERANCE * a.abs().max(b.abs())
+        }
+        _ => false,
+    }
+}
+
+/// `*`（任意の文字列）と `?`（任意の1文字）のみをサポートする簡易ワイルドカード照合
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0usize, 0usize);
+    let mut star: Option<usize> = None;
+    let mut star_text = 0usize;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some(p);
+            star_text = t;
+            p += 1;
+        } else if let Some(star_pos) = star {
+            p = star_pos + 1;
+            star_text += 1;
+            t = star_text;
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn build_part_map<'a>(
+    bom: &'a BomData,
+    options: &CompareOptions,
+) -> (HashMap<String, &'a crate::BomRow>, usize) {
+    let mut excluded = 0usize;
+    let mut map = HashMap::new();
+    for row in &bom.rows {
+        if options.is_excluded(&row.part_number) {
+            excluded += 1;
+            continue;
+        }
+        map.insert(row.part_number.clone(), row);
+    }
+    (map, excluded)
+}
+
+/// 部品表AとBを比較する
+pub fn perform_comparison(
+    bom_a: &BomData,
+    bom_b: &BomData,
+    options: &CompareOptions,
+) -> ComparisonResult {
+    let options = &*options.for_boms(bom_a, bom_b);
+    let (map_a, excluded_a) = build_part_map(bom_a, options);
+    let (map_b, excluded_b) = build_part_map(bom_b, options);
+
+    let ((common_parts, modified_parts), (a_only_parts, b_only_parts)) = rayon::join(
+        || find_shared_parts(&map_a, &map_b, options),
+        || {
+            rayon::join(
+                || find_a_only_parts(&map_a, &map_b),
+                || find_b_only_parts(&map_a, &map_b),
+            )
+        },
     );
 
-    ComparisonResult {
+    // HashMapへの変換で同一部品番号の行は最後の1行に集約されるため、件数を記録しておく
+    let mut duplicate_parts = find_duplicate_parts(bom_a, "a", options);
+    duplicate_parts.extend(find_duplicate_parts(bom_b, "b", options));
+
+    let mut result = ComparisonResult {
         common_parts,
         a_only_parts,
         b_only_parts,
         modified_parts,
+        renamed_parts: Vec::new(),
+        duplicate_parts,
+        excluded_a,
+        excluded_b,
+    };
+    if options.detect_renames {
+        detect_renamed_parts(&mut result, &map_a, &map_b, options);
+    }
+    result
+}
+
+/// 比較結果を1行ずつ返すイテレータ（結果全体を保持せずに処理したいバッチ処理向け）
+/// Aの部品を出現順に共通（"common" / "modified"）または "a_only" として返し、続けて "b_only" を返す。
+/// 各部品は1回だけ返す。
+/// 同一部品番号の行は perform_comparison と同じく最後の1行を使う。部品番号変更の検出は行わない
+pub fn compare_rows<'a>(
+    bom_a: &'a BomData,
+    bom_b: &'a BomData,
+    options: &'a CompareOptions,
+) -> impl Iterator<Item = ComparisonRow> + 'a {
+    let (order_a, map_a) = ordered_part_map(bom_a, options);
+    let (order_b, map_b) = ordered_part_map(bom_b, options);
+    let options = options.for_boms(bom_a, bom_b);
+
+    // 比較行は都度生成し、ここでは行への参照のみを保持する
+    let b_only: Vec<(&str, &crate::BomRow)> = order_b
+        .into_iter()
+        .filter(|part_number| !map_a.contains_key(part_number))
+        .map(|part_number| (part_number, map_b[part_number]))
+        .collect();
+    let a_rows = order_a
+        .into_iter()
+        .map(move |part_number| match map_b.get(part_number) {
+            Some(row_b) => common_row(part_number, map_a[part_number], row_b, &options),
+            None => a_only_row(part_number, map_a[part_number]),
+        });
+    let b_rows = b_only
+        .into_iter()
+        .map(|(part_number, row_b)| b_only_row(part_number, row_b));
+    a_rows.chain(b_rows)
+}
+
+/// compare_rows の結果を1行ずつCSVに書き出す（列は save_comparison_result のCSVと同じ）
+/// 書き出した比較行の数を返す
+pub fn write_comparison_rows_csv<W: std::io::Write>(
+    rows: impl Iterator<Item = ComparisonRow>,
+    writer: W,
+) -> Result<usize, AppError> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer
+        .write_record(["部品番号", "型番A", "型番B", "ステータス", "差分種別"])
+        .map_err(|e| AppError::write_failed("CSV書き込みエラー", e))?;
+    let mut count = 0usize;
+    for row in rows {
+        writer
+            .write_record([
+                display_part_number(&row),
+                row.model_a,
+                row.model_b,
+                get_status_text(&row.status),
+                get_change_type_text(&row.change_type),
+            ])
+            .map_err(|e| AppError::write_failed("CSV書き込みエラー", e))?;
+        count += 1;
+    }
+    writer
+        .flush()
+        .map_err(|e| AppError::write_failed("CSV書き込みエラー", e))?;
+    Ok(count)
+}
+
+/// 除外対象を除いた部品番号の出現順と、部品番号 → 最後の行の対応
+fn ordered_part_map<'a>(
+    bom: &'a BomData,
+    options: &CompareOptions,
+) -> (Vec<&'a str>, HashMap<&'a str, &'a crate::BomRow>) {
+    let mut order = Vec::new();
+    let mut map = HashMap::new();
+    for row in &bom.rows {
+        if options.is_excluded(&row.part_number) {
+            continue;
+        }
+        if map.insert(row.part_number.as_str(), row).is_none() {
+            order.push(row.part_number.as_str());
+        }
     }
+    (order, map)
 }
 
-fn find_common_parts(
+/// 部品番号変更の判定に使う行の内容（正規化後の型番・数量・属性）
+/// 自身の部品番号と同じ値の属性（部品番号列など）は比較しない
+fn rename_signature(
+    row: &crate::BomRow,
+    options: &CompareOptions,
+) -> (String, Option<u32>, Vec<(String, String)>) {
+    let mut attributes: Vec<(String, String)> = row
+        .attributes
+        .iter()
+        .filter(|(_, value)| **value != row.part_number)
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    attributes.sort();
+    (
+        options.model_normalize.apply(&row.model_number),
+        row.quantity,
+        attributes,
+    )
+}
+
+/// Aのみ・Bのみの部品から内容が一致する組を部品番号順に1対1で取り出し、renamed_parts に移す
+fn detect_renamed_parts(
+    result: &mut ComparisonResult,
     map_a: &HashMap<String, &crate::BomRow>,
     map_b: &HashMap<String, &crate::BomRow>,
-) -> Vec<ComparisonRow> {
+    options: &CompareOptions,
+) {
+    let mut b_only: Vec<&String> = result
+        .b_only_parts
+        .iter()
+        .map(|row| &row.part_number)
+        .collect();
+    b_only.sort();
+    let mut candidates: HashMap<_, VecDeque<&String>> = HashMap::new();
+    for part_number in b_only {
+        if let Some(row_b) = map_b.get(part_number) {
+            candidates
+                .entry(rename_signature(row_b, options))
+                .or_default()
+                .push_back(part_number);
+        }
+    }
+
+    let mut a_only: Vec<&String> = result
+        .a_only_parts
+        .iter()
+        .map(|row| &row.part_number)
+        .collect();
+    a_only.sort();
+    let mut renamed = Vec::new();
+    for part_a in a_only {
+        let row_a = match map_a.get(part_a) {
+            Some(row) => row,
+            None => continue,
+        };
+        let part_b = match candidates
+            .get_mut(&rename_signature(row_a, options))
+            .and_then(|queue| queue.pop_front())
+        {
+            Some(part_b) => part_b,
+            None => continue,
+        };
+        let row_b = map_b[part_b];
+        renamed.push(ComparisonRow {
+            part_number: part_b.clone(),
+            model_a: row_a.model_number.clone(),
+            model_b: row_b.model_number.clone(),
+            status: "renamed".to_string(),
+            change_type: "RENAMED".to_string(),
+            changed_attributes: Vec::new(),
+            changed_values_a: IndexMap::new(),
+            changed_values_b: IndexMap::new(),
+            quantity_a: row_a.quantity,
+            quantity_b: row_b.quantity,
+            renamed_from: Some(part_a.clone()),
+        });
+    }
+
+    let from_a: HashSet<&str> = renamed
+        .iter()
+        .filter_map(|row| row.renamed_from.as_deref())
+        .collect();
+    let to_b: HashSet<&str> = renamed.iter().map(|row| row.part_number.as_str()).collect();
+    result
+        .a_only_parts
+        .retain(|row| !from_a.contains(row.part_number.as_str()));
+    result
+        .b_only_parts
+        .retain(|row| !to_b.contains(row.part_number.as_str()));
+    result.renamed_parts = renamed;
+}
+
+/// 同一部品番号が複数回出現する部品を検出する
+fn find_duplicate_parts(bom: &BomData, side: &str, options: &CompareOptions) -> Vec<DuplicatePart> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for row in &bom.rows {
+        if options.is_excluded(&row.part_number) {
+            continue;
+        }
+        *counts.entry(row.part_number.as_str()).or_insert(0) += 1;
+    }
+
+    let mut duplicates: Vec<DuplicatePart> = counts
+        .into_iter()
+        .filter(|(_, occurrences)| *occurrences > 1)
+        .map(|(part_number, occurrences)| DuplicatePart {
+            side: side.to_string(),
+            part_number: part_number.to_string(),
+            occurrences,
+        })
+        .collect();
+    duplicates.sort_by(|a, b| a.part_number.cmp(&b.part_number));
+    duplicates
+}
+
+/// 比較結果から利用者へ提示する警告メッセージを生成する
+pub fn comparison_warnings(result: &ComparisonResult) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    for side in ["a", "b"] {
+        let duplicates: Vec<&DuplicatePart> = result
+            .duplicate_parts
+            .iter()
+            .filter(|dup| dup.side == side)
+            .collect();
+        if duplicates.is_empty() {
+            continue;
+        }
+
+        let mut listed = duplicates
+            .iter()
+            .take(MAX_LISTED_DUPLICATES)
+            .map(|dup| format!("{}({}件)", dup.part_number, dup.occurrences))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if duplicates.len() > MAX_LISTED_DUPLICATES {
+            listed.push_str(&format!(
+                " ほか{}件",
+                duplicates.len() - MAX_LISTED_DUPLICATES
+            ));
+        }
+
+        warnings.push(format!(
+            "部品表{}で部品番号が重複している{}件を1行に集約して比較しました: {}",
+            side.to_uppercase(),
+            duplicates.len(),
+            listed
+        ));
+    }
+
+    warnings
+}
+
+/// 両方に存在する部品を変更なし（common_parts）と変更あり（modified_parts）に分ける
+/// 1部品はどちらか一方にのみ入る
+fn find_shared_parts(
+    map_a: &HashMap<String, &crate::BomRow>,
+    map_b: &HashMap<String, &crate::BomRow>,
+    options: &CompareOptions,
+) -> (Vec<ComparisonRow>, Vec<ComparisonRow>) {
     map_a
         .par_iter()
         .filter(|(part_number, _)| map_b.contains_key(*part_number))
-        .map(|(part_number, row_a)| {
-            let row_b = map_b.get(part_number).unwrap();
-            let is_modified = row_a.model_number != row_b.model_number;
-            ComparisonRow {
-                part_number: part_number.clone(),
-                model_a: row_a.model_number.clone(),
-                model_b: row_b.model_number.clone(),
-                status: if is_modified {
-                    "modified".to_string()
-                } else {
-                    "common".to_string()
-                },
-                change_type: if is_modified {
-                    "MODIFIED".to_string()
-                } else {
-                    "UNCHANGED".to_string()
-                },
-            }
-        })
-        .collect()
+        .map(|(part_number, row_a)| common_row(part_number, row_a, map_b[part_number], options))
+        .partition(|row| row.change_type == "UNCHANGED")
+}
+
+/// 両方に存在する部品の比較行（型番・比較対象属性が異なれば "modified"）
+fn common_row(
+    part_number: &str,
+    row_a: &crate::BomRow,
+    row_b: &crate::BomRow,
+    options: &CompareOptions,
+) -> ComparisonRow {
+    let changed = changed_attributes(row_a, row_b, options);
+    let values = |row: &crate::BomRow| -> IndexMap<String, String> {
+        changed
+            .iter()
+            .map(|name| {
+                let value = row.attributes.get(name).cloned().unwrap_or_default();
+                (name.clone(), value)
+            })
+            .collect()
+    };
+    let (changed_values_a, changed_values_b) = (values(row_a), values(row_b));
+    let is_modified =
+        options.models_differ(&row_a.model_number, &row_b.model_number) || !changed.is_empty();
+    ComparisonRow {
+        part_number: part_number.to_string(),
+        model_a: row_a.model_number.clone(),
+        model_b: row_b.model_number.clone(),
+        status: if is_modified {
+            "modified".to_string()
+        } else {
+            "common".to_string()
+        },
+        change_type: if is_modified {
+            "MODIFIED".to_string()
+        } else {
+            "UNCHANGED".to_string()
+        },
+        changed_attributes: changed,
+        changed_values_a,
+        changed_values_b,
+        quantity_a: row_a.quantity,
+        quantity_b: row_b.quantity,
+        renamed_from: None,
+    }
+}
+
+fn a_only_row(part_number: &str, row_a: &crate::BomRow) -> ComparisonRow {
+    ComparisonRow {
+        part_number: part_number.to_string(),
+        model_a: row_a.model_number.clone(),
+        model_b: String::new(),
+        status: "a_only".to_string(),
+        change_type: "REMOVED".to_string(),
+        changed_attributes: Vec::new(),
+        changed_values_a: IndexMap::new(),
+        changed_values_b: IndexMap::new(),
+        quantity_a: row_a.quantity,
+        quantity_b: None,
+        renamed_from: None,
+    }
+}
+
+fn b_only_row(part_number: &str, row_b: &crate::BomRow) -> ComparisonRow {
+    ComparisonRow {
+        part_number: part_number.to_string(),
+        model_a: String::new(),
+        model_b: row_b.model_number.clone(),
+        status: "b_only".to_string(),
+        change_type: "ADDED".to_string(),
+        changed_attributes: Vec::new(),
+        changed_values_a: IndexMap::new(),
+        changed_values_b: IndexMap::new(),
+        quantity_a: None,
+        quantity_b: row_b.quantity,
+        renamed_from: None,
+    }
 }
 
 fn find_a_only_parts(
@@ -68,13 +649,7 @@ fn find_a_only_parts(
     map_a
         .par_iter()
         .filter(|(part_number, _)| !map_b.contains_key(*part_number))
-        .map(|(part_number, row_a)| ComparisonRow {
-            part_number: part_number.clone(),
-            model_a: row_a.model_number.clone(),
-            model_b: String::new(),
-            status: "a_only".to_string(),
-            change_type: "REMOVED".to_string(),
-        })
+        .map(|(part_number, row_a)| a_only_row(part_number, row_a))
         .collect()
 }
 
@@ -85,43 +660,91 @@ fn find_b_only_parts(
     map_b
         .par_iter()
         .filter(|(part_number, _)| !map_a.contains_key(*part_number))
-        .map(|(part_number, row_b)| ComparisonRow {
-            part_number: part_number.clone(),
-            model_a: String::new(),
-            model_b: row_b.model_number.clone(),
-            status: "b_only".to_string(),
-            change_type: "ADDED".to_string(),
-        })
+        .map(|(part_number, row_b)| b_only_row(part_number, row_b))
         .collect()
 }
 
-fn find_modified_parts(
-    map_a: &HashMap<String, &crate::BomRow>,
-    map_b: &HashMap<String, &crate::BomRow>,
-) -> Vec<ComparisonRow> {
-    map_a
-        .par_iter()
-        .filter_map(|(part_number, row_a)| {
-            map_b
-                .get(part_number.as_str())
-                .map(|row_b| (part_number, *row_a, *row_b))
-        })
-        .filter(|(_, row_a, row_b)| row_a.model_number != row_b.model_number)
-        .map(|(part_number, row_a, row_b)| ComparisonRow {
-            part_number: part_number.clone(),
-            model_a: row_a.model_number.clone(),
-            model_b: row_b.model_number.clone(),
-            status: "modified".to_string(),
-            change_type: "MODIFIED".to_string(),
-        })
+/// 指定部品のみの部品マップ（比較時と同じく、同一部品番号の行は最後の1行を使用する）
+fn single_part_map<'a>(
+    bom: &'a BomData,
+    part_number: &str,
+    options: &CompareOptions,
+) -> HashMap<String, &'a crate::BomRow> {
+    if options.is_excluded(part_number) {
+        return HashMap::new();
+    }
+    bom.rows
+        .iter()
+        .rev()
+        .find(|row| row.part_number == part_number)
+        .map(|row| (part_number.to_string(), row))
+        .into_iter()
         .collect()
 }
 
+/// 1部品の比較行だけを再計算して比較結果を更新する（部品番号が変わらない編集向け）
+pub fn patch_comparison_part(
+    result: &mut ComparisonResult,
+    bom_a: &BomData,
+    bom_b: &BomData,
+    part_number: &str,
+    options: &CompareOptions,
+) {
+    let options = &*options.for_boms(bom_a, bom_b);
+    // 部品番号変更の組に含まれる場合は組を解消し、相手側の部品も通常の分類で再計算する
+    let mut parts = vec![part_number.to_string()];
+    result.renamed_parts.retain(|row| {
+        let from = row.renamed_from.as_deref().unwrap_or_default();
+        if row.part_number != part_number && from != part_number {
+            return true;
+        }
+        parts.push(row.part_number.clone());
+        parts.push(from.to_string());
+        false
+    });
+    parts.sort();
+    parts.dedup();
+
+    for part in &parts {
+        for rows in [
+            &mut result.common_parts,
+            &mut result.a_only_parts,
+            &mut result.b_only_parts,
+            &mut result.modified_parts,
+        ] {
+            rows.retain(|row| row.part_number != *part);
+        }
+
+        let map_a = single_part_map(bom_a, part, options);
+        let map_b = single_part_map(bom_b, part, options);
+
+        let (common, modified) = find_shared_parts(&map_a, &map_b, options);
+        result.common_parts.extend(common);
+        result.modified_parts.extend(modified);
+        result
+            .a_only_parts
+            .extend(find_a_only_parts(&map_a, &map_b));
+        result
+            .b_only_parts
+            .extend(find_b_only_parts(&map_a, &map_b));
+    }
+}
+
 pub async fn save_comparison_result(
     result: &ComparisonResult,
     file_path: &str,
     format: &str,
-) -> Result<String, String> {
+    append: bool,
+    encoding: Option<&str>,
+) -> Result<String, AppError> {
+    if append && format != "csv" {
+        return Err(AppError::InvalidInput(
+            "追記はCSV形式のみ対応しています".to_string(),
+        ));
+    }
+    check_output_encoding(format, append, encoding)?;
+    let text_encoding = encoding.unwrap_or(file_handler::DEFAULT_TEXT_ENCODING);
+
     let mut csv_data = Vec::new();
 
     csv_data.push(vec![
@@ -132,15 +755,17 @@ pub async fn save_comparison_result(
         "差分種別".to_string(),
     ]);
 
+    // 各部品はいずれか1つのカテゴリにのみ含まれる（共通部品は変更のないもののみ）
     for row in result
         .common_parts
         .iter()
         .chain(result.a_only_parts.iter())
         .chain(result.b_only_parts.iter())
         .chain(result.modified_parts.iter())
+        .chain(result.renamed_parts.iter())
     {
         csv_data.push(vec![
-            row.part_number.clone(),
+            display_part_number(row),
             row.model_a.clone(),
             row.model_b.clone(),
             get_status_text(&row.status),
@@ -149,10 +774,17 @@ pub async fn save_comparison_result(
     }
 
     match format {
+        "csv" if append => {
+            let label = crate::file_handler::run_label();
+            crate::file_handler::append_csv_file(&csv_data, file_path, &label)
+                .await
+                .map_err(|e| AppError::write_failed("CSV追記エラー", e))?;
+        }
         "csv" => {
-            crate::file_handler::save_csv_file(&csv_data, file_path, "utf-8")
+            let encoding = encoding.unwrap_or(file_handler::DEFAULT_CSV_ENCODING);
+            file_handler::save_csv_file(&csv_data, file_path, encoding)
                 .await
-                .map_err(|e| format!("CSV保存エラー: {e}"))?;
+                .map_err(|e| AppError::write_failed("CSV保存エラー", e))?;
         }
         "txt" => {
             let mut content = String::new();
@@ -160,7 +792,11 @@ pub async fn save_comparison_result(
             content.push_str(&format!("共通部品: {}件\n", result.common_parts.len()));
             content.push_str(&format!("Aのみ部品: {}件\n", result.a_only_parts.len()));
             content.push_str(&format!("Bのみ部品: {}件\n", result.b_only_parts.len()));
-            content.push_str(&format!("変更部品: {}件\n\n", result.modified_parts.len()));
+            content.push_str(&format!("変更部品: {}件\n", result.modified_parts.len()));
+            content.push_str(&format!(
+                "部品番号変更: {}件\n\n",
+                result.renamed_parts.len()
+            ));
 
             content.push_str("=== 部品一覧 ===\n");
             for row in result
@@ -169,28 +805,403 @@ pub async fn save_comparison_result(
                 .chain(result.modified_parts.iter())
                 .chain(result.a_only_parts.iter())
                 .chain(result.b_only_parts.iter())
+                .chain(result.renamed_parts.iter())
             {
                 content.push_str(&format!(
                     "{} | {} | {} | {}\n",
-                    row.part_number,
+                    display_part_number(row),
                     row.model_a,
                     row.model_b,
                     get_status_text(&row.status)
                 ));
             }
 
-            crate::file_handler::save_txt_file(&content, file_path, "utf-8")
+            file_handler::save_txt_file(&content, file_path, text_encoding)
+                .await
+                .map_err(|e| AppError::write_failed("TXT保存エラー", e))?;
+        }
+        "json" => {
+            let content = serde_json::to_string_pretty(result)
+                .map_err(|e| AppError::Internal(format!("JSON生成エラー: {e}")))?;
+            file_handler::save_txt_file(&content, file_path, file_handler::DEFAULT_TEXT_ENCODING)
                 .await
-                .map_err(|e| format!("TXT保存エラー: {e}"))?;
+                .map_err(|e| AppError::write_failed("JSON保存エラー", e))?;
+        }
+        "diff" => {
+            let content = render_unified_diff(result);
+            file_handler::save_txt_file(&content, file_path, text_encoding)
+                .await
+                .map_err(|e| AppError::write_failed("差分保存エラー", e))?;
+        }
+        "html" => {
+            let content = render_html_report(result);
+            file_handler::save_txt_file(&content, file_path, file_handler::DEFAULT_TEXT_ENCODING)
+                .await
+                .map_err(|e| AppError::write_failed("HTML保存エラー", e))?;
+        }
+        _ => {
+            return Err(AppError::UnsupportedFormat(
+                "サポートされていないフォーマットです".to_string(),
+            ))
         }
-        _ => return Err("サポートされていないフォーマットです".to_string()),
     }
 
     Ok("比較結果を保存しました".to_string())
 }
 
+/// 出力の文字コード指定を検証する（JSON・HTMLはUTF-8固定、追記は既存ファイルに合わせてUTF-8のみ）
+pub fn check_output_encoding(
+    format: &str,
+    append: bool,
+    encoding: Option<&str>,
+) -> Result<(), AppError> {
+    let encoding = match encoding {
+        Some(encoding) => encoding,
+        None => return Ok(()),
+    };
+    if (append || format == "json" || format == "html") && !file_handler::is_utf8_encoding(encoding)
+    {
+        return Err(AppError::Encoding(if append {
+            "追記はUTF-8のみ対応しています".to_string()
+        } else {
+            format!("{}はUTF-8のみ対応しています", format.to_uppercase())
+        }));
+    }
+    file_handler::encode_text("", encoding)
+        .map(|_| ())
+        .map_err(AppError::Encoding)
+}
+
+/// メーカーが不明な部品をまとめるグループ名
+pub const UNKNOWN_MANUFACTURER_GROUP: &str = "unknown";
+
+/// 比較結果をメーカーごとに分割する（キーはメーカー名。部品番号 → メーカーの対応がない・空の部品は "unknown"）
+pub fn group_by_manufacturer(
+    result: &ComparisonResult,
+    manufacturers: &HashMap<String, String>,
+) -> BTreeMap<String, ComparisonResult> {
+    let mut groups: BTreeMap<String, ComparisonResult> = BTreeMap::new();
+    let group_of = |row: &ComparisonRow| {
+        manufacturers
+            .get(&row.part_number)
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .unwrap_or(UNKNOWN_MANUFACTURER_GROUP)
+            .to_string()
+    };
+    let empty = empty_comparison_result;
+
+    for row in &result.common_parts {
+        groups
+            .entry(group_of(row))
+            .or_insert_with(empty)
+            .common_parts
+            .push(row.clone());
+    }
+    for row in &result.a_only_parts {
+        groups
+            .entry(group_of(row))
+            .or_insert_with(empty)
+            .a_only_parts
+            .push(row.clone());
+    }
+    for row in &result.b_only_parts {
+        groups
+            .entry(group_of(row))
+            .or_insert_with(empty)
+            .b_only_parts
+            .push(row.clone());
+    }
+    for row in &result.modified_parts {
+        groups
+            .entry(group_of(row))
+            .or_insert_with(empty)
+            .modified_parts
+            .push(row.clone());
+    }
+    for row in &result.renamed_parts {
+        groups
+            .entry(group_of(row))
+            .or_insert_with(empty)
+            .renamed_parts
+            .push(row.clone());
+    }
+
+    groups
+}
+
+fn empty_comparison_result() -> ComparisonResult {
+    ComparisonResult {
+        common_parts: Vec::new(),
+        a_only_parts: Vec::new(),
+        b_only_parts: Vec::new(),
+        modified_parts: Vec::new(),
+        renamed_parts: Vec::new(),
+        duplicate_parts: Vec::new(),
+        excluded_a: 0,
+        excluded_b: 0,
+    }
+}
+
+/// 比較結果をカテゴリごとに分割する（ファイル名に使うカテゴリ名と、そのカテゴリの行だけを持つ比較結果）
+/// 行のないカテゴリは含めない
+pub fn split_by_category(result: &ComparisonResult) -> Vec<(&'static str, ComparisonResult)> {
+    let categories = [
+        ("common", &result.common_parts),
+        ("a_only", &result.a_only_parts),
+        ("b_only", &result.b_only_parts),
+        ("modified", &result.modified_parts),
+        ("renamed", &result.renamed_parts),
+    ];
+
+    categories
+        .into_iter()
+        .filter(|(_, rows)| !rows.is_empty())
+        .map(|(name, rows)| {
+            let mut part = empty_comparison_result();
+            let target = match name {
+                "common" => &mut part.common_parts,
+                "a_only" => &mut part.a_only_parts,
+                "b_only" => &mut part.b_only_parts,
+                "modified" => &mut part.modified_parts,
+                _ => &mut part.renamed_parts,
+            };
+            target.clone_from(rows);
+            (name, part)
+        })
+        .collect()
+}
+
+/// カテゴリ別に保存する際に添える集計（summary.txt）の内容
+pub fn render_comparison_summary(result: &ComparisonResult) -> String {
+    let stats = get_comparison_stats(result);
+    let get = |key: &str| stats.get(key).copied().unwrap_or(0);
+    let mut content = String::new();
+    content.push_str("=== 部品表比較サマリー ===\n\n");
+    for (label, key) in [
+        ("共通部品", "common"),
+        ("Aのみ部品", "a_only"),
+        ("Bのみ部品", "b_only"),
+        ("変更部品", "modified"),
+        ("部品番号変更", "renamed"),
+    ] {
+        content.push_str(&format!("{label}: {}件\n", get(key)));
+    }
+    content.push('\n');
+    content.push_str(&format!("部品表A: {}件\n", get("total_a")));
+    content.push_str(&format!("部品表B: {}件\n", get("total_b")));
+    if get("excluded_a") > 0 || get("excluded_b") > 0 {
+        content.push_str(&format!(
+            "除外: A {}件 / B {}件\n",
+            get("excluded_a"),
+            get("excluded_b")
+        ));
+    }
+    content
+}
+
+/// 変更のない共通部品を除いた比較結果を返す（追加・削除・変更のみ）
+pub fn without_unchanged(result: &ComparisonResult) -> ComparisonResult {
+    let mut filtered = result.clone();
+    filtered
+        .common_parts
+        .retain(|row| row.change_type != "UNCHANGED");
+    filtered
+}
+
+/// 比較結果で追加・変更とされた部品の行を bom から抜き出した部品表を返す（行の順序・属性は元のまま）
+/// 追加された部品はB側にしか行がないため、A側の部品表を渡した場合は変更された部品のみとなる
+pub fn extract_changed_bom(bom: &BomData, result: &ComparisonResult) -> BomData {
+    let changed: HashSet<&str> = result
+        .modified_parts
+        .iter()
+        .chain(&result.b_only_parts)
+        .map(|row| row.part_number.as_str())
+        .collect();
+
+    BomData {
+        headers: bom.headers.clone(),
+        rows: bom
+            .rows
+            .iter()
+            .filter(|row| changed.contains(row.part_number.as_str()))
+            .cloned()
+            .collect(),
+        verbatim: bom.verbatim.clone(),
+    }
+}
+
+/// HTMLの特殊文字をエスケープする（部品の説明などに含まれるタグをそのまま出力しない）
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const HTML_REPORT_STYLE: &str = "body{font-family:sans-serif;margin:24px;color:#222}\
+h1{font-size:20px}table{border-collapse:collapse;margin:8px 0 16px}\
+th,td{border:1px solid #bbb;padding:4px 8px;text-align:left;font-size:13px}\
+th{background:#eee}summary{cursor:pointer;font-weight:bold;margin:8px 0}\
+tr.modified td{background:#fff6d5}tr.a_only td{background:#fde2e2}\
+tr.b_only td{background:#e2f5e2}tr.renamed td{background:#e2ecfd}\
+@media print{summary{list-style:none}}";
+
+/// 比較結果を単体で開けるHTMLレポートにする（外部ファイルを参照せず、CSSも埋め込む）
+/// 集計表と、カテゴリごとに折りたためる表を出力する。共通部品の表は初期状態で閉じておく
+pub fn render_html_report(result: &ComparisonResult) -> String {
+    let stats = get_comparison_stats(result);
+    let get = |key: &str| stats.get(key).copied().unwrap_or(0);
+    let sections = [
+        ("変更部品", "modified", &result.modified_parts),
+        ("部品番号変更", "renamed", &result.renamed_parts),
+        ("Bのみ部品（追加）", "b_only", &result.b_only_parts),
+        ("Aのみ部品（削除）", "a_only", &result.a_only_parts),
+        ("共通部品", "common", &result.common_parts),
+    ];
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>部品表比較結果</title>\n");
+    html.push_str(&format!(
+        "<style>{HTML_REPORT_STYLE}</style>\n</head>\n<body>\n"
+    ));
+    html.push_str("<h1>部品表比較結果</h1>\n");
+    html.push_str(&format!(
+        "<p>作成日時: {}</p>\n",
+        escape_html(&file_handler::run_label())
+    ));
+
+    html.push_str("<table class=\"summary\">\n");
+    for (label, key, _) in &sections {
+        html.push_str(&format!(
+            "<tr><th>{label}</th><td>{}件</td></tr>\n",
+            get(key)
+        ));
+    }
+    html.push_str(&format!(
+        "<tr><th>部品表A / 部品表B</th><td>{}件 / {}件</td></tr>\n",
+        get("total_a"),
+        get("total_b")
+    ));
+    html.push_str("</table>\n");
+
+    for (label, key, rows) in sections {
+        let open = if key == "common" { "" } else { " open" };
+        html.push_str(&format!(
+            "<details{open}>\n<summary>{label}（{}件）</summary>\n",
+            rows.len()
+        ));
+        if rows.is_empty() {
+            html.push_str("<p>該当なし</p>\n</details>\n");
+            continue;
+        }
+        html.push_str("<table>\n<tr><th>部品番号</th><th>型番A</th><th>型番B</th><th>差分種別</th><th>変更された属性</th></tr>\n");
+        for row in rows {
+            html.push_str(&format!(
+                "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&row.status),
+                escape_html(&display_part_number(row)),
+                escape_html(&row.model_a),
+                escape_html(&row.model_b),
+                escape_html(&get_change_type_text(&row.change_type)),
+                escape_html(&row.changed_attributes.join(", "))
+            ));
+        }
+        html.push_str("</table>\n</details>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// 比較結果を unified diff 形式で出力する（削除は "-"、追加は "+"、変更は "-旧/+新" の組）
+pub fn render_unified_diff(result: &ComparisonResult) -> String {
+    let mut changes: Vec<&ComparisonRow> = result
+        .a_only_parts
+        .iter()
+        .chain(result.b_only_parts.iter())
+        .chain(result.modified_parts.iter())
+        .chain(result.renamed_parts.iter())
+        .collect();
+    changes.sort_by(|a, b| a.part_number.cmp(&b.part_number));
+
+    let mut body = String::new();
+    let mut removed = 0usize;
+    let mut added = 0usize;
+    for row in changes {
+        match row.status.as_str() {
+            "a_only" => {
+                body.push_str(&format!("-{}\t{}\n", row.part_number, row.model_a));
+                removed += 1;
+            }
+            "b_only" => {
+                body.push_str(&format!("+{}\t{}\n", row.part_number, row.model_b));
+                added += 1;
+            }
+            "renamed" => {
+                let from = row.renamed_from.as_deref().unwrap_or_default();
+                body.push_str(&format!("-{}\t{}\n", from, row.model_a));
+                body.push_str(&format!("+{}\t{}\n", row.part_number, row.model_b));
+                removed += 1;
+                added += 1;
+            }
+            _ => {
+                body.push_str(&format!(
+                    "-{}\t{}{}\n",
+                    row.part_number,
+                    row.model_a,
+                    diff_attributes(&row.changed_values_a)
+                ));
+                body.push_str(&format!(
+                    "+{}\t{}{}\n",
+                    row.part_number,
+                    row.model_b,
+                    diff_attributes(&row.changed_values_b)
+                ));
+                removed += 1;
+                added += 1;
+            }
+        }
+    }
+
+    let mut content = String::new();
+    content.push_str("--- 部品表A\n");
+    content.push_str("+++ 部品表B\n");
+    if removed + added > 0 {
+        content.push_str(&format!("@@ -1,{} +1,{} @@\n", removed, added));
+        content.push_str(&body);
+    }
+    content
+}
+
+/// 差分行の末尾に付ける、値が異なった属性の「列名=値」（タブ区切り）
+fn diff_attributes(values: &IndexMap<String, String>) -> String {
+    values
+        .iter()
+        .map(|(name, value)| format!("\t{}={}", name, value))
+        .collect()
+}
+
+/// 出力用の部品番号（部品番号変更の場合は「旧 → 新」）
+fn display_part_number(row: &ComparisonRow) -> String {
+    match row.renamed_from.as_deref() {
+        Some(from) => format!("{} → {}", from, row.part_number),
+        None => row.part_number.clone(),
+    }
+}
+
 fn get_status_text(status: &str) -> String {
     match status {
+        "renamed" => "部品番号変更".to_string(),
         "common" => "共通部品".to_string(),
         "a_only" => "Aのみ".to_string(),
         "b_only" => "Bのみ".to_string(),
@@ -205,6 +1216,7 @@ fn get_change_type_text(change_type: &str) -> String {
         "REMOVED" => "削除".to_string(),
         "MODIFIED" => "変更".to_string(),
         "UNCHANGED" => "変更なし".to_string(),
+        "RENAMED" => "部品番号変更".to_string(),
         other => other.to_string(),
     }
 }
@@ -215,22 +1227,179 @@ pub fn get_comparison_stats(result: &ComparisonResult) -> HashMap<String, usize>
     stats.insert("a_only".to_string(), result.a_only_parts.len());
     stats.insert("b_only".to_string(), result.b_only_parts.len());
     stats.insert("modified".to_string(), result.modified_parts.len());
+    stats.insert("renamed".to_string(), result.renamed_parts.len());
+    let shared = result.common_parts.len() + result.modified_parts.len();
     stats.insert(
         "total_a".to_string(),
-        result.common_parts.len() + result.a_only_parts.len(),
+        shared + result.a_only_parts.len() + result.renamed_parts.len(),
     );
     stats.insert(
         "total_b".to_string(),
-        result.common_parts.len() + result.b_only_parts.len(),
+        shared + result.b_only_parts.len() + result.renamed_parts.len(),
     );
+    stats.insert("excluded_a".to_string(), result.excluded_a);
+    stats.insert("excluded_b".to_string(), result.excluded_b);
+    insert_quantity_stats(&mut stats, result);
     stats
 }
 
+/// 数量で重み付けした集計を追加する（数量が未設定の部品は0として数え、件数を quantity_missing に記録）
+fn insert_quantity_stats(stats: &mut HashMap<String, usize>, result: &ComparisonResult) {
+    let mut missing = 0usize;
+    let mut quantity = |value: Option<u32>| match value {
+        Some(q) => q as usize,
+        None => {
+            missing += 1;
+            0
+        }
+    };
+
+    let added: usize = result
+        .b_only_parts
+        .iter()
+        .map(|row| quantity(row.quantity_b))
+        .sum();
+    let removed: usize = result
+        .a_only_parts
+        .iter()
+        .map(|row| quantity(row.quantity_a))
+        .sum();
+
+    let mut increase = 0usize;
+    let mut decrease = 0usize;
+    for row in &result.modified_parts {
+        let before = quantity(row.quantity_a);
+        let after = quantity(row.quantity_b);
+        if after > before {
+            increase += after - before;
+        } else {
+            decrease += before - after;
+        }
+    }
+
+    stats.insert("quantity_added".to_string(), added);
+    stats.insert("quantity_removed".to_string(), removed);
+    // 差分は負になり得るため、増加分と減少分を分けて返す
+    stats.insert("quantity_modified_increase".to_string(), increase);
+    stats.insert("quantity_modified_decrease".to_string(), decrease);
+    stats.insert("quantity_missing".to_string(), missing);
+}
+
+/// 統計に読み込み行数を追加し、比較件数と部品表の一意部品数が一致するか検証する
+pub fn reconcile_stats(
+    stats: &mut HashMap<String, usize>,
+    bom_a: &BomData,
+    bom_b: &BomData,
+    options: &CompareOptions,
+) -> bool {
+    // 比較処理の部品マップとは独立に数え直す
+    let unique_a = count_unique_parts(bom_a, options);
+    let unique_b = count_unique_parts(bom_b, options);
+
+    stats.insert("loaded_a".to_string(), bom_a.rows.len());
+    stats.insert("loaded_b".to_string(), bom_b.rows.len());
+    stats.insert("unique_a".to_string(), unique_a);
+    stats.insert("unique_b".to_string(), unique_b);
+
+    let total_a = stats.get("total_a").copied().unwrap_or(0);
+    let total_b = stats.get("total_b").copied().unwrap_or(0);
+    let reconciled = total_a == unique_a && total_b == unique_b;
+    // 統計マップは件数のみを扱うため、真偽値は 1/0 で格納する
+    stats.insert("reconciled".to_string(), usize::from(reconciled));
+    reconciled
+}
+
+fn count_unique_parts(bom: &BomData, options: &CompareOptions) -> usize {
+    bom.rows
+        .iter()
+        .filter(|row| !options.is_excluded(&row.part_number))
+        .map(|row| row.part_number.as_str())
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// 並び順以外に差分がない（部品番号と型番の組が完全に一致する）かを判定する
+pub fn is_reorder_only(
+    stats: &HashMap<String, usize>,
+    bom_a: &BomData,
+    bom_b: &BomData,
+    options: &CompareOptions,
+) -> bool {
+    let get = |key: &str| stats.get(key).copied().unwrap_or(0);
+    if get("a_only") > 0 || get("b_only") > 0 || get("modified") > 0 || get("renamed") > 0 {
+        return false;
+    }
+
+    sorted_pairs(bom_a, options) == sorted_pairs(bom_b, options)
+}
+
+fn sorted_pairs<'a>(bom: &'a BomData, options: &CompareOptions) -> Vec<(&'a str, String)> {
+    let mut pairs: Vec<(&str, String)> = bom
+        .rows
+        .iter()
+        .filter(|row| !options.is_excluded(&row.part_number))
+        .map(|row| {
+            (
+                row.part_number.as_str(),
+                options.model_normalize.apply(&row.model_number),
+            )
+        })
+        .collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// 部品番号を正規化すると一致率が大きく上がる場合、正規化設定の不一致を疑う提案を返す
+pub fn normalization_suggestion(
+    bom_a: &BomData,
+    bom_b: &BomData,
+    options: &CompareOptions,
+) -> Option<String> {
+    let (map_a, _) = build_part_map(bom_a, options);
+    let (map_b, _) = build_part_map(bom_b, options);
+    let denominator = map_a.len().min(map_b.len());
+    if denominator == 0 {
+        return None;
+    }
+
+    let raw_matched = map_a.keys().filter(|key| map_b.contains_key(*key)).count();
+    let raw_rate = raw_matched as f64 / denominator as f64;
+    if raw_rate > NORMALIZATION_HINT_MAX_RAW_RATE {
+        return None;
+    }
+
+    let normalized_b: HashSet<String> = map_b.keys().map(|key| standardize_string(key)).collect();
+    let normalized_a: HashSet<String> = map_a.keys().map(|key| standardize_string(key)).collect();
+    let normalized_matched = normalized_a.intersection(&normalized_b).count();
+    let normalized_rate = normalized_matched as f64 / denominator as f64;
+    if normalized_rate < NORMALIZATION_HINT_MIN_NORMALIZED_RATE {
+        return None;
+    }
+
+    Some(format!(
+        "正規化設定が一致していない可能性があります（一致率 {:.0}% → 正規化後 {:.0}%）",
+        raw_rate * 100.0,
+        normalized_rate * 100.0
+    ))
+}
+
+/// 突き合わせに失敗した場合の警告メッセージ
+pub fn reconciliation_warning(stats: &HashMap<String, usize>) -> String {
+    let get = |key: &str| stats.get(key).copied().unwrap_or(0);
+    format!(
+        "比較件数が部品表の部品数と一致しません (A: 比較{}件/一意{}件, B: 比較{}件/一意{}件)",
+        get("total_a"),
+        get("unique_a"),
+        get("total_b"),
+        get("unique_b")
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{BomData, BomRow};
-    use std::collections::HashMap;
+    use crate::{BomData, BomRow, VerbatimColumns};
+    use indexmap::IndexMap;
 
     fn create_test_bom_a() -> BomData {
         BomData {
@@ -239,14 +1408,17 @@ mod tests {
                 BomRow {
                     part_number: "PART001".to_string(),
                     model_number: "MODEL001".to_string(),
-                    attributes: HashMap::new(),
+                    attributes: IndexMap::new(),
+                    quantity: None,
                 },
                 BomRow {
                     part_number: "PART002".to_string(),
                     model_number: "MODEL002".to_string(),
-                    attributes: HashMap::new(),
+                    attributes: IndexMap::new(),
+                    quantity: None,
                 },
             ],
+            verbatim: VerbatimColumns::default(),
         }
     }
 
@@ -257,14 +1429,17 @@ mod tests {
                 BomRow {
                     part_number: "PART001".to_string(),
                     model_number: "MODEL001".to_string(),
-                    attributes: HashMap::new(),
+                    attributes: IndexMap::new(),
+                    quantity: None,
                 },
                 BomRow {
                     part_number: "PART003".to_string(),
                     model_number: "MODEL003".to_string(),
-                    attributes: HashMap::new(),
+                    attributes: IndexMap::new(),
+                    quantity: None,
                 },
             ],
+            verbatim: VerbatimColumns::default(),
         }
     }
 
@@ -273,7 +1448,7 @@ mod tests {
         let bom_a = create_test_bom_a();
         let bom_b = create_test_bom_b();
 
-        let result = perform_comparison(&bom_a, &bom_b);
+        let result = perform_comparison(&bom_a, &bom_b, &CompareOptions::default());
 
         assert_eq!(result.common_parts.len(), 1);
         assert_eq!(result.a_only_parts.len(), 1);
@@ -282,5 +1457,646 @@ mod tests {
         assert_eq!(result.common_parts[0].part_number, "PART001");
         assert_eq!(result.a_only_parts[0].part_number, "PART002");
         assert_eq!(result.b_only_parts[0].part_number, "PART003");
+        assert!(result.duplicate_parts.is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_parts_are_reported() {
+        let mut bom_a = create_test_bom_a();
+        bom_a.rows.push(BomRow {
+            part_number: "PART001".to_string(),
+            model_number: "MODEL001-ALT".to_string(),
+            attributes: IndexMap::new(),
+            quantity: None,
+        });
+        let bom_b = create_test_bom_b();
+
+        let result = perform_comparison(&bom_a, &bom_b, &CompareOptions::default());
+
+        assert_eq!(result.duplicate_parts.len(), 1);
+        assert_eq!(result.duplicate_parts[0].side, "a");
+        assert_eq!(result.duplicate_parts[0].part_number, "PART001");
+        assert_eq!(result.duplicate_parts[0].occurrences, 2);
+
+        let warnings = comparison_warnings(&result);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("PART001(2件)"));
+    }
+
+    #[test]
+    fn test_exclude_patterns() {
+        let mut bom_a = create_test_bom_a();
+        bom_a.rows.push(BomRow {
+            part_number: "TP1".to_string(),
+            model_number: "TESTPOINT".to_string(),
+            attributes: IndexMap::new(),
+            quantity: None,
+        });
+        let mut bom_b = create_test_bom_b();
+        bom_b.rows.push(BomRow {
+            part_number: "FID2".to_string(),
+            model_number: "FIDUCIAL".to_string(),
+            attributes: IndexMap::new(),
+            quantity: None,
+        });
+
+        let options = CompareOptions {
+            exclude_patterns: vec!["TP*".to_string(), "fid".to_string()],
+            ..CompareOptions::default()
+        };
+        let result = perform_comparison(&bom_a, &bom_b, &options);
+
+        assert_eq!(result.excluded_a, 1);
+        assert_eq!(result.excluded_b, 1);
+        assert_eq!(result.a_only_parts.len(), 1);
+        assert_eq!(result.b_only_parts.len(), 1);
+        assert!(result
+            .a_only_parts
+            .iter()
+            .chain(result.b_only_parts.iter())
+            .all(|row| row.part_number != "TP1" && row.part_number != "FID2"));
+    }
+
+    #[test]
+    fn test_is_reorder_only() {
+        let bom_a = create_test_bom_a();
+        let mut reordered = create_test_bom_a();
+        reordered.rows.reverse();
+        let options = CompareOptions::default();
+
+        let result = perform_comparison(&bom_a, &reordered, &options);
+        let stats = get_comparison_stats(&result);
+        assert!(is_reorder_only(&stats, &bom_a, &reordered, &options));
+
+        let bom_b = create_test_bom_b();
+        let result = perform_comparison(&bom_a, &bom_b, &options);
+        let stats = get_comparison_stats(&result);
+        assert!(!is_reorder_only(&stats, &bom_a, &bom_b, &options));
+    }
+
+    #[test]
+    fn test_normalization_suggestion() {
+        let bom_a = create_test_bom_a();
+        let mut bom_b = create_test_bom_a();
+        for row in bom_b.rows.iter_mut() {
+            row.part_number = format!(" {}", row.part_number.to_lowercase());
+        }
+        let options = CompareOptions::default();
+
+        assert!(normalization_suggestion(&bom_a, &bom_b, &options).is_some());
+        assert!(normalization_suggestion(&bom_a, &create_test_bom_b(), &options).is_none());
+    }
+
+    #[test]
+    fn test_patch_comparison_part_matches_full_comparison() {
+        let bom_a = create_test_bom_a();
+        let mut bom_b = create_test_bom_b();
+        let options = CompareOptions::default();
+        let mut result = perform_comparison(&bom_a, &bom_b, &options);
+
+        bom_b.rows[0].model_number = "MODEL-EDITED".to_string();
+        let part_number = bom_b.rows[0].part_number.clone();
+        patch_comparison_part(&mut result, &bom_a, &bom_b, &part_number, &options);
+
+        let expected = get_comparison_stats(&perform_comparison(&bom_a, &bom_b, &options));
+        assert_eq!(get_comparison_stats(&result), expected);
+        let patched: Vec<&ComparisonRow> = result
+            .common_parts
+            .iter()
+            .chain(result.modified_parts.iter())
+            .chain(result.b_only_parts.iter())
+            .filter(|row| row.part_number == part_number)
+            .collect();
+        assert!(patched.iter().all(|row| row.model_b == "MODEL-EDITED"));
+    }
+
+    #[test]
+    fn test_render_unified_diff() {
+        let mut bom_b = create_test_bom_b();
+        bom_b.rows[0].model_number = "MODEL001-REV".to_string();
+        let result = perform_comparison(&create_test_bom_a(), &bom_b, &CompareOptions::default());
+
+        let diff = render_unified_diff(&result);
+        let lines: Vec<&str> = diff.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "--- 部品表A",
+                "+++ 部品表B",
+                "@@ -1,2 +1,2 @@",
+                "-PART001\tMODEL001",
+                "+PART001\tMODEL001-REV",
+                "-PART002\tMODEL002",
+                "+PART003\tMODEL003",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_attributes_detects_modification() {
+        let mut bom_a = create_test_bom_a();
+        let mut bom_b = create_test_bom_a();
+        bom_a.rows[0]
+            .attributes
+            .insert("定数".to_string(), "10k".to_string());
+        bom_b.rows[0]
+            .attributes
+            .insert("定数".to_string(), "22k".to_string());
+        bom_a.rows[0]
+            .attributes
+            .insert("備考".to_string(), "旧".to_string());
+
+        let model_only = perform_comparison(&bom_a, &bom_b, &CompareOptions::default());
+        assert!(model_only.modified_parts.is_empty());
+
+        let options = CompareOptions {
+            compare_attributes: vec!["定数".to_string()],
+            ..CompareOptions::default()
+        };
+        let result = perform_comparison(&bom_a, &bom_b, &options);
+        assert_eq!(result.modified_parts.len(), 1);
+        assert_eq!(result.modified_parts[0].part_number, "PART001");
+        assert_eq!(result.modified_parts[0].changed_attributes, vec!["定数"]);
+    }
+
+    #[test]
+    fn test_render_unified_diff_shows_changed_attributes() {
+        let mut bom_a = create_test_bom_a();
+        let mut bom_b = create_test_bom_a();
+        bom_a.rows[0]
+            .attributes
+            .insert("定数".to_string(), "10k".to_string());
+        bom_b.rows[0]
+            .attributes
+            .insert("定数".to_string(), "22k".to_string());
+        let options = CompareOptions {
+            compare_attributes: vec!["定数".to_string()],
+            ..CompareOptions::default()
+        };
+        let result = perform_comparison(&bom_a, &bom_b, &options);
+
+        let diff = render_unified_diff(&result);
+        let lines: Vec<&str> = diff.lines().collect();
+
+        assert_eq!(
+            lines,
+            [
+                "--- 部品表A",
+                "+++ 部品表B",
+                "@@ -1,1 +1,1 @@",
+                "-PART001\tMODEL001\t定数=10k",
+                "+PART001\tMODEL001\t定数=22k",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_model_normalize_applies_to_models_only() {
+        let bom_a = create_test_bom_a();
+        let mut bom_b = create_test_bom_a();
+        bom_b.rows[0].model_number = "ＭＯＤＥＬ００１".to_string();
+        bom_b.rows[1].model_number = " model002 ".to_string();
+        bom_b.rows[1].part_number = "part002".to_string();
+
+        let strict = perform_comparison(&bom_a, &bom_b, &CompareOptions::default());
+        assert_eq!(strict.modified_parts.len(), 1);
+
+        let options = CompareOptions {
+            model_normalize: ModelNormalize {
+                case_insensitive: true,
+                fullwidth: true,
+                trim: true,
+            },
+            ..CompareOptions::default()
+        };
+        let result = perform_comparison(&bom_a, &bom_b, &options);
+        assert!(result.modified_parts.is_empty());
+        assert_eq!(result.common_parts[0].model_b, "ＭＯＤＥＬ００１");
+        assert_eq!(result.common_parts[0].change_type, "UNCHANGED");
+        // 部品番号は正規化しない
+        assert_eq!(result.a_only_parts[0].part_number, "PART002");
+        assert_eq!(result.b_only_parts[0].part_number, "part002");
+
+        let fullwidth_only = CompareOptions {
+            model_normalize: ModelNormalize {
+                fullwidth: true,
+                ..ModelNormalize::default()
+            },
+            ..CompareOptions::default()
+        };
+        assert!(!fullwidth_only.models_differ("ＡＢ1", "AB1"));
+        assert!(fullwidth_only.models_differ("ab1", "AB1"));
+    }
+
+    #[test]
+    fn test_value_equivalence() {
+        for (a, b) in [
+            ("10.0k", "10k"),
+            ("0.1uF", "100nF"),
+            ("4k7", "4.7kΩ"),
+            ("1MΩ", "1000kΩ"),
+            ("1MA", "1000UA"),
+            ("2.2µF", "2200nF"),
+            ("100 ohm", "100R"),
+        ] {
+            assert!(values_equivalent(a, b), "{a} / {b}");
+        }
+        assert!(!values_equivalent("10k", "1k"));
+        assert!(!values_equivalent("1uF", "1uH"));
+        assert!(!values_equivalent("GRM188", "GRM188"));
+        // 単位のない M はミリかメガか判別できないため比較しない
+        assert!(!values_equivalent("1M", "1000K"));
+    }
+
+    #[test]
+    fn test_value_equivalence_on_loaded_rows() {
+        let load = |rows: &[(&str, &str)]| {
+            let mapping = crate::ColumnMapping {
+                part_number: 0,
+                model_number: 1,
+                manufacturer: None,
+                quantity: None,
+                keep_columns: None,
+                has_headers: true,
+                verbatim_columns: Vec::new(),
+                date_columns: Vec::new(),
+                split_column: None,
+                whitespace_modes: Vec::new(),
+            };
+            let raw_rows = rows
+                .iter()
+                .map(|(part, model)| vec![part.to_string(), model.to_string()])
+                .collect();
+            let headers = vec!["部品番号".to_string(), "定数".to_string()];
+            crate::bom_processor::build_bom_from_rows(headers, raw_rows, &mapping)
+                .unwrap()
+                .bom
+        };
+        let bom_a = load(&[
+            ("C1", "0.1uF"),
+            ("R1", "10.0k"),
+            ("L1", "4.7uH"),
+            ("R2", "1m"),
+            ("C2", "10uF"),
+        ]);
+        let bom_b = load(&[
+            ("C1", "100nF"),
+            ("R1", "10k"),
+            ("L1", "4700nH"),
+            ("R2", "1000k"),
+            ("C2", "1uF"),
+        ]);
+        // 読み込み時に大文字に揃えられる
+        assert_eq!(bom_a.rows[0].model_number, "0.1UF");
+        assert_eq!(bom_b.rows[0].model_number, "100NF");
+
+        let strict = perform_comparison(&bom_a, &bom_b, &CompareOptions::default());
+        assert_eq!(strict.modified_parts.len(), 5);
+
+        let options = CompareOptions {
+            value_equivalence: true,
+            ..CompareOptions::default()
+        };
+        let result = perform_comparison(&bom_a, &bom_b, &options);
+        let mut modified: Vec<&str> = result
+            .modified_parts
+            .iter()
+            .map(|row| row.part_number.as_str())
+            .collect();
+        modified.sort();
+        assert_eq!(modified, vec!["C2", "R2"]);
+        assert_eq!(result.common_parts.len(), 3);
+    }
+
+    #[test]
+    fn test_quantity_weighted_stats() {
+        let mut bom_a = create_test_bom_a();
+        let mut bom_b = create_test_bom_b();
+        bom_a.rows[0].quantity = Some(4);
+        bom_a.rows[1].quantity = Some(10);
+        bom_b.rows[0].model_number = "MODEL001-REV".to_string();
+        bom_b.rows[0].quantity = Some(1);
+
+        let result = perform_comparison(&bom_a, &bom_b, &CompareOptions::default());
+        let stats = get_comparison_stats(&result);
+
+        assert_eq!(stats.get("quantity_removed"), Some(&10));
+        assert_eq!(stats.get("quantity_added"), Some(&0));
+        assert_eq!(stats.get("quantity_modified_decrease"), Some(&3));
+        assert_eq!(stats.get("quantity_modified_increase"), Some(&0));
+        assert_eq!(stats.get("quantity_missing"), Some(&1));
+    }
+
+    #[test]
+    fn test_without_unchanged() {
+        let mut bom_b = create_test_bom_a();
+        bom_b.rows[1].model_number = "MODEL002-REV".to_string();
+        let result = perform_comparison(&create_test_bom_a(), &bom_b, &CompareOptions::default());
+
+        let filtered = without_unchanged(&result);
+
+        assert_eq!(result.common_parts.len(), 1);
+        assert!(filtered.common_parts.is_empty());
+        assert_eq!(filtered.modified_parts.len(), 1);
+        assert_eq!(filtered.modified_parts[0].part_number, "PART002");
+    }
+
+    #[test]
+    fn test_compare_without_model_column() {
+        let bom_a = create_test_bom_a();
+        let mut bom_b = create_test_bom_b();
+        for row in &mut bom_b.rows {
+            row.model_number.clear();
+        }
+
+        let result = perform_comparison(&bom_a, &bom_b, &CompareOptions::default());
+
+        assert!(result.modified_parts.is_empty());
+        assert_eq!(result.common_parts.len(), 1);
+        assert_eq!(result.common_parts[0].part_number, "PART001");
+        assert_eq!(result.a_only_parts[0].part_number, "PART002");
+        assert_eq!(result.b_only_parts[0].part_number, "PART003");
+
+        // 型番のある部品表同士では従来どおり型番の違いを変更とする
+        let mut bom_c = create_test_bom_b();
+        bom_c.rows[0].model_number = "MODEL001-REV".to_string();
+        let result = perform_comparison(&bom_a, &bom_c, &CompareOptions::default());
+        assert_eq!(result.modified_parts.len(), 1);
+    }
+
+    #[test]
+    fn test_modified_part_listed_once() {
+        let mut bom_b = create_test_bom_a();
+        bom_b.rows[1].model_number = "MODEL002-REV".to_string();
+        let result = perform_comparison(&create_test_bom_a(), &bom_b, &CompareOptions::default());
+
+        let occurrences = result
+            .common_parts
+            .iter()
+            .chain(&result.a_only_parts)
+            .chain(&result.b_only_parts)
+            .chain(&result.modified_parts)
+            .chain(&result.renamed_parts)
+            .filter(|row| row.part_number == "PART002")
+            .count();
+        assert_eq!(occurrences, 1);
+        assert!(result
+            .common_parts
+            .iter()
+            .all(|row| row.change_type == "UNCHANGED"));
+
+        let stats = get_comparison_stats(&result);
+        assert_eq!(stats["total_a"], 2);
+        assert_eq!(stats["total_b"], 2);
+    }
+
+    #[test]
+    fn test_group_by_manufacturer() {
+        let result = perform_comparison(
+            &create_test_bom_a(),
+            &create_test_bom_b(),
+            &CompareOptions::default(),
+        );
+        let manufacturers: HashMap<String, String> = [
+            ("PART001".to_string(), "TDK".to_string()),
+            ("PART002".to_string(), "TDK".to_string()),
+            ("PART003".to_string(), " ".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        let groups = group_by_manufacturer(&result, &manufacturers);
+
+        assert_eq!(
+            groups.keys().map(String::as_str).collect::<Vec<_>>(),
+            vec!["TDK", UNKNOWN_MANUFACTURER_GROUP]
+        );
+        assert_eq!(groups["TDK"].common_parts.len(), 1);
+        assert_eq!(groups["TDK"].a_only_parts[0].part_number, "PART002");
+        assert_eq!(
+            groups[UNKNOWN_MANUFACTURER_GROUP].b_only_parts[0].part_number,
+            "PART003"
+        );
+    }
+
+    #[test]
+    fn test_render_html_report_escapes_values() {
+        let mut bom_b = create_test_bom_b();
+        bom_b.rows[1].model_number = "<script>alert('x')</script>".to_string();
+        let result = perform_comparison(&create_test_bom_a(), &bom_b, &CompareOptions::default());
+
+        let html = render_html_report(&result);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;"));
+        assert!(html.contains("<summary>Bのみ部品（追加）（1件）</summary>"));
+        assert!(html.contains("<details>\n<summary>共通部品（1件）</summary>"));
+        assert!(html.contains("<tr class=\"b_only\">"));
+    }
+
+    #[test]
+    fn test_split_by_category() {
+        let result = perform_comparison(
+            &create_test_bom_a(),
+            &create_test_bom_b(),
+            &CompareOptions::default(),
+        );
+
+        let parts = split_by_category(&result);
+
+        assert_eq!(
+            parts.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            vec!["common", "a_only", "b_only"]
+        );
+        let (_, b_only) = &parts[2];
+        assert_eq!(b_only.b_only_parts[0].part_number, "PART003");
+        assert!(b_only.common_parts.is_empty() && b_only.a_only_parts.is_empty());
+
+        let summary = render_comparison_summary(&result);
+        assert!(summary.contains("共通部品: 1件"));
+        assert!(summary.contains("Bのみ部品: 1件"));
+        assert!(!summary.contains("除外"));
+    }
+
+    #[test]
+    fn test_extract_changed_bom() {
+        let bom_a = create_test_bom_a();
+        let mut bom_b = create_test_bom_b();
+        bom_b.rows[0].model_number = "MODEL001-R".to_string();
+        bom_b.rows[1]
+            .attributes
+            .insert("備考".to_string(), "new".to_string());
+        let result = perform_comparison(&bom_a, &bom_b, &CompareOptions::default());
+
+        let changed_b = extract_changed_bom(&bom_b, &result);
+        assert_eq!(
+            changed_b
+                .rows
+                .iter()
+                .map(|row| row.part_number.as_str())
+                .collect::<Vec<_>>(),
+            vec!["PART001", "PART003"]
+        );
+        assert_eq!(changed_b.rows[1].attributes["備考"], "new");
+
+        let changed_a = extract_changed_bom(&bom_a, &result);
+        assert_eq!(changed_a.rows.len(), 1);
+        assert_eq!(changed_a.rows[0].model_number, "MODEL001");
+    }
+
+    #[test]
+    fn test_reconcile_stats() {
+        let mut bom_a = create_test_bom_a();
+        bom_a.rows.push(bom_a.rows[0].clone());
+        let bom_b = create_test_bom_b();
+        let options = CompareOptions::default();
+
+        let result = perform_comparison(&bom_a, &bom_b, &options);
+        let mut stats = get_comparison_stats(&result);
+
+        assert!(reconcile_stats(&mut stats, &bom_a, &bom_b, &options));
+        assert_eq!(stats.get("loaded_a"), Some(&3));
+        assert_eq!(stats.get("unique_a"), Some(&2));
+        assert_eq!(stats.get("total_a"), Some(&2));
+        assert_eq!(stats.get("reconciled"), Some(&1));
+
+        // 比較結果の件数が読み込んだ部品数と合わない場合は検出する
+        let mut result = perform_comparison(&bom_a, &bom_b, &options);
+        result.common_parts.pop();
+        let mut stats = get_comparison_stats(&result);
+        assert!(!reconcile_stats(&mut stats, &bom_a, &bom_b, &options));
+        assert_eq!(stats.get("reconciled"), Some(&0));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("TP*", "TP12"));
+        assert!(glob_match("MH?", "MH1"));
+        assert!(!glob_match("MH?", "MH10"));
+        assert!(!glob_match("TP*", "STP1"));
+    }
+
+    #[test]
+    fn test_detect_renamed_parts() {
+        let row = |part: &str, model: &str, note: &str| {
+            let mut attributes = IndexMap::new();
+            attributes.insert("部品番号".to_string(), part.to_string());
+            attributes.insert("備考".to_string(), note.to_string());
+            BomRow {
+                part_number: part.to_string(),
+                model_number: model.to_string(),
+                attributes,
+                quantity: Some(1),
+            }
+        };
+        let bom = |rows: Vec<BomRow>| BomData {
+            headers: vec!["部品番号".to_string(), "備考".to_string()],
+            rows,
+            verbatim: VerbatimColumns::default(),
+        };
+        let bom_a = bom(vec![
+            row("C1", "CAP100", ""),
+            row("C2", "CAP200", "x"),
+            row("R1", "RES100", ""),
+        ]);
+        let bom_b = bom(vec![
+            row("C1", "CAP100", ""),
+            row("C12", "CAP200", "x"),
+            row("R9", "RES100", "changed"),
+        ]);
+
+        let plain = perform_comparison(&bom_a, &bom_b, &CompareOptions::default());
+        assert!(plain.renamed_parts.is_empty());
+        assert_eq!(plain.a_only_parts.len(), 2);
+
+        let options = CompareOptions {
+            detect_renames: true,
+            ..CompareOptions::default()
+        };
+        let mut result = perform_comparison(&bom_a, &bom_b, &options);
+        assert_eq!(result.renamed_parts.len(), 1);
+        let renamed = &result.renamed_parts[0];
+        assert_eq!(renamed.part_number, "C12");
+        assert_eq!(renamed.renamed_from.as_deref(), Some("C2"));
+        assert_eq!(renamed.change_type, "RENAMED");
+        // 属性が異なる組は部品番号変更とみなさない
+        assert_eq!(result.a_only_parts[0].part_number, "R1");
+        assert_eq!(result.b_only_parts[0].part_number, "R9");
+
+        let mut stats = get_comparison_stats(&result);
+        assert_eq!(stats["renamed"], 1);
+        assert!(reconcile_stats(&mut stats, &bom_a, &bom_b, &options));
+        assert!(render_unified_diff(&result).contains("-C2\tCAP200\n+C12\tCAP200\n"));
+
+        // 組の一方を再計算すると組を解消し、相手側も通常の分類に戻す
+        patch_comparison_part(&mut result, &bom_a, &bom_b, "C2", &options);
+        assert!(result.renamed_parts.is_empty());
+        assert!(result.a_only_parts.iter().any(|r| r.part_number == "C2"));
+        assert!(result.b_only_parts.iter().any(|r| r.part_number == "C12"));
+    }
+
+    #[test]
+    fn test_write_comparison_rows_csv() {
+        let bom_a = create_test_bom_a();
+        let bom_b = create_test_bom_b();
+        let options = CompareOptions::default();
+        let mut buffer = Vec::new();
+
+        let count =
+            write_comparison_rows_csv(compare_rows(&bom_a, &bom_b, &options), &mut buffer).unwrap();
+
+        let text = String::from_utf8(buffer).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(text.lines().count(), 4);
+        assert!(text.starts_with("部品番号,型番A,型番B,ステータス,差分種別\n"));
+        assert!(text.contains("PART003"));
+    }
+
+    #[test]
+    fn test_compare_rows_matches_perform_comparison() {
+        let bom_a = create_test_bom_a();
+        let mut bom_b = create_test_bom_b();
+        bom_b.rows.push(BomRow {
+            part_number: "PART001".to_string(),
+            model_number: "MODEL001-REV".to_string(),
+            attributes: IndexMap::new(),
+            quantity: None,
+        });
+        let options = CompareOptions::default();
+
+        let streamed: Vec<ComparisonRow> = compare_rows(&bom_a, &bom_b, &options).collect();
+        let result = perform_comparison(&bom_a, &bom_b, &options);
+
+        let keys = |rows: &mut dyn Iterator<Item = &ComparisonRow>| {
+            let mut keys: Vec<(String, String, String, String)> = rows
+                .map(|row| {
+                    (
+                        row.part_number.clone(),
+                        row.model_a.clone(),
+                        row.model_b.clone(),
+                        row.status.clone(),
+                    )
+                })
+                .collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(
+            keys(&mut streamed.iter()),
+            keys(
+                &mut result
+                    .common_parts
+                    .iter()
+                    .chain(result.modified_parts.iter())
+                    .chain(result.a_only_parts.iter())
+                    .chain(result.b_only_parts.iter())
+            )
+        );
+        // A側の出現順に返し、Bのみの部品は最後に返す
+        assert_eq!(streamed[0].part_number, "PART001");
+        assert_eq!(streamed[0].status, "modified");
+        assert_eq!(streamed.last().unwrap().status, "b_only");
     }
 }
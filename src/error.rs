@@ -0,0 +1,170 @@
+use crate::bom_processor::BomProcessorError;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+
+/// フロントエンドへ返すエラー。`code` で種別を判定し、`message` を表示に使用する
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("{0}")]
+    FileNotFound(String),
+    #[error("{0}")]
+    FileRead(String),
+    #[error("{0}")]
+    FileWrite(String),
+    #[error("{0}")]
+    UnsupportedFormat(String),
+    #[error("{0}")]
+    Encoding(String),
+    #[error("{0}")]
+    Column(String),
+    #[error("{0}")]
+    NoData(String),
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl AppError {
+    /// フロントエンド向けの安定したエラーコード
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::FileNotFound(_) => "file_not_found",
+            AppError::FileRead(_) => "file_read",
+            AppError::FileWrite(_) => "file_write",
+            AppError::UnsupportedFormat(_) => "unsupported_format",
+            AppError::Encoding(_) => "encoding",
+            AppError::Column(_) => "column",
+            AppError::NoData(_) => "no_data",
+            AppError::InvalidInput(_) => "invalid_input",
+            AppError::Internal(_) => "internal",
+        }
+    }
+
+    /// エラーの種類を保ったまま、メッセージの先頭に発生箇所を付ける
+    pub fn with_context(self, context: &str) -> Self {
+        let message = format!("{context}: {}", self.message());
+        match self {
+            AppError::FileNotFound(_) => AppError::FileNotFound(message),
+            AppError::FileRead(_) => AppError::FileRead(message),
+            AppError::FileWrite(_) => AppError::FileWrite(message),
+            AppError::UnsupportedFormat(_) => AppError::UnsupportedFormat(message),
+            AppError::Encoding(_) => AppError::Encoding(message),
+            AppError::Column(_) => AppError::Column(message),
+            AppError::NoData(_) => AppError::NoData(message),
+            AppError::InvalidInput(_) => AppError::InvalidInput(message),
+            AppError::Internal(_) => AppError::Internal(message),
+        }
+    }
+
+    /// 読み込み時の入出力エラー（ファイルが存在しない場合は FileNotFound）
+    pub fn read_failed(context: &str, err: std::io::Error) -> Self {
+        let message = format!("{context}: {err}");
+        match err.kind() {
+            std::io::ErrorKind::NotFound => AppError::FileNotFound(message),
+            _ => AppError::FileRead(message),
+        }
+    }
+
+    /// 書き込み時の入出力エラー
+    pub fn write_failed(context: &str, err: impl std::fmt::Display) -> Self {
+        AppError::FileWrite(format!("{context}: {err}"))
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::FileNotFound(message)
+            | AppError::FileRead(message)
+            | AppError::FileWrite(message)
+            | AppError::UnsupportedFormat(message)
+            | AppError::Encoding(message)
+            | AppError::Column(message)
+            | AppError::NoData(message)
+            | AppError::InvalidInput(message)
+            | AppError::Internal(message) => message,
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", self.message())?;
+        state.end()
+    }
+}
+
+impl From<BomProcessorError> for AppError {
+    fn from(err: BomProcessorError) -> Self {
+        let message = err.to_string();
+        match err {
+            BomProcessorError::FileReadError(_) => AppError::FileRead(message),
+            BomProcessorError::FormatError(_) => AppError::UnsupportedFormat(message),
+            BomProcessorError::EncodingError(_) => AppError::Encoding(message),
+            BomProcessorError::ColumnError(_) => AppError::Column(message),
+            BomProcessorError::FileNotFound(_) => AppError::FileNotFound(message),
+            BomProcessorError::InvalidInput(_) => AppError::InvalidInput(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_error_serializes_code_and_message() {
+        let err = AppError::from(BomProcessorError::ColumnError("列がありません".to_string()));
+        let value = serde_json::to_value(&err).unwrap();
+
+        assert_eq!(value["code"], "column");
+        assert_eq!(value["message"], "列指定エラー: 列がありません");
+    }
+
+    #[test]
+    fn test_bom_processor_error_codes() {
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "a.csv");
+        assert_eq!(
+            AppError::from(BomProcessorError::from(missing)).code(),
+            "file_not_found"
+        );
+        let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "a.csv");
+        assert_eq!(
+            AppError::from(BomProcessorError::from(denied)).code(),
+            "file_read"
+        );
+
+        let pattern = crate::bom_processor::designator_regex(Some("[")).unwrap_err();
+        assert_eq!(AppError::from(pattern).code(), "invalid_input");
+        let format = BomProcessorError::FormatError("JSON".to_string());
+        assert_eq!(AppError::from(format).code(), "unsupported_format");
+    }
+
+    #[test]
+    fn test_io_error_helpers() {
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "a.json");
+        let err = AppError::read_failed("セッションを開けません", missing);
+        assert_eq!(err.code(), "file_not_found");
+        assert_eq!(err.message(), "セッションを開けません: a.json");
+
+        let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "a.json");
+        assert_eq!(
+            AppError::read_failed("読み込み", denied).code(),
+            "file_read"
+        );
+        assert_eq!(
+            AppError::write_failed("保存", "disk full").code(),
+            "file_write"
+        );
+    }
+
+    #[test]
+    fn test_with_context_keeps_code() {
+        let err = AppError::FileNotFound("a.csv".to_string()).with_context("読み込み(A)");
+
+        assert_eq!(err.code(), "file_not_found");
+        assert_eq!(err.message(), "読み込み(A): a.csv");
+    }
+}
@@ -57,54 +57,189 @@ pub fn add_timestamp_to_filename(file_path: &str, prefix: &str) -> String {
         .to_string()
 }
 
+/// ユーザー入力（メーカー名など）をファイル名に使えるようにする
+/// パス区切りや使用できない文字・制御文字は "_" に置き換え、"." や ".." だけの名前にならないようにする
+pub fn sanitize_file_name(name: &str) -> String {
+    let replaced: String = name
+        .chars()
+        .map(|c| match c {
+            '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    // Windowsでは末尾の空白・ピリオドが無視されるため取り除く
+    let trimmed = replaced.trim().trim_end_matches('.');
+    if trimmed.is_empty() || trimmed.chars().all(|c| c == '.') {
+        "_".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// 保存先の親フォルダが存在しない場合は作成する
+fn ensure_parent_dir(file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = Path::new(file_path).parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent).map_err(|e| {
+                format!("保存先フォルダを作成できません ({}): {e}", parent.display())
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// CSV出力の既定の文字コード（Excelで文字化けしないようBOM付きUTF-8）
+pub const DEFAULT_CSV_ENCODING: &str = "utf-8-bom";
+/// TXT・JSONなどCSV以外の出力の既定の文字コード
+pub const DEFAULT_TEXT_ENCODING: &str = "utf-8";
+
+/// UTF-8系（"utf-8" / "utf-8-bom"）の文字コード指定か
+pub fn is_utf8_encoding(encoding: &str) -> bool {
+    matches!(
+        encoding.trim().to_ascii_lowercase().as_str(),
+        "utf-8" | "utf8" | "utf-8-bom" | "utf8-bom"
+    )
+}
+
+/// 文字列を指定の文字コードでバイト列に変換する
+/// 対応: "utf-8"（BOMなし）、"utf-8-bom"、"shift_jis"（"shift-jis" / "sjis" も可）
+/// Shift_JISで表せない文字を含む場合は、最初の該当文字を示すエラーを返す
+pub fn encode_text(content: &str, encoding: &str) -> Result<Vec<u8>, String> {
+    match encoding.trim().to_ascii_lowercase().as_str() {
+        "utf-8" | "utf8" => Ok(content.as_bytes().to_vec()),
+        "utf-8-bom" | "utf8-bom" => {
+            let mut bytes = vec![0xEF, 0xBB, 0xBF];
+            bytes.extend_from_slice(content.as_bytes());
+            Ok(bytes)
+        }
+        "shift_jis" | "shift-jis" | "sjis" => {
+            let (encoded, _, had_errors) = encoding_rs::SHIFT_JIS.encode(content);
+            if had_errors {
+                let unencodable = content
+                    .chars()
+                    .find(|c| encoding_rs::SHIFT_JIS.encode(&c.to_string()).2)
+                    .unwrap_or('?');
+                return Err(format!(
+                    "Shift_JISで表せない文字が含まれています: {}",
+                    unencodable
+                ));
+            }
+            Ok(encoded.into_owned())
+        }
+        other => Err(format!("サポートされていない文字コードです: {}", other)),
+    }
+}
+
 /// CSV形式でファイルを保存
 pub async fn save_csv_file(
     data: &[Vec<String>],
     file_path: &str,
     encoding: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut file = fs::File::create(file_path)?;
+    let mut content = String::new();
+    for row in data {
+        content.push_str(&row.join(","));
+        content.push('\n');
+    }
+    let bytes = encode_text(&content, encoding)?;
 
-    // BOMを追加（UTF-8の場合）
-    let encoding_lower = encoding.to_ascii_lowercase();
+    ensure_parent_dir(file_path)?;
+    fs::write(file_path, bytes)?;
+    Ok(())
+}
 
-    if encoding_lower == "utf-8" {
-        file.write_all(&[0xEF, 0xBB, 0xBF])?;
-    }
+/// CSV形式でファイルを保存（UTF-8・BOM付き）。カンマ・引用符・改行を含む値は引用符で囲む
+pub async fn save_quoted_csv_file(
+    data: &[Vec<String>],
+    file_path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    ensure_parent_dir(file_path)?;
+    let mut file = fs::File::create(file_path)?;
+    file.write_all(&[0xEF, 0xBB, 0xBF])?;
+    write_quoted_csv(data, file)
+}
 
+fn write_quoted_csv<W: Write>(
+    data: &[Vec<String>],
+    writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(writer);
     for row in data {
-        let csv_line = row.join(",") + "\n";
-        let bytes = match encoding_lower.as_str() {
-            "utf-8" => csv_line.as_bytes().to_vec(),
-            "shift-jis" => {
-                let (encoded, _, _) = encoding_rs::SHIFT_JIS.encode(&csv_line);
-                encoded.to_vec()
-            }
-            _ => csv_line.as_bytes().to_vec(),
-        };
-        file.write_all(&bytes)?;
+        writer.write_record(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 既存のCSVファイルに行を追記する（先頭列に実行ラベルを付与）。
+/// ファイルが存在しない場合はヘッダー行から書き込み、存在する場合はヘッダーが一致することを確認する
+pub async fn append_csv_file(
+    data: &[Vec<String>],
+    file_path: &str,
+    run_label: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    append_csv_rows(data, file_path, run_label)
+}
+
+fn append_csv_rows(
+    data: &[Vec<String>],
+    file_path: &str,
+    run_label: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (header, rows) = match data.split_first() {
+        Some(split) => split,
+        None => return Ok(()),
+    };
+    let mut header_line = vec!["実行ID".to_string()];
+    header_line.extend(header.iter().cloned());
+    let header_line = header_line.join(",");
+
+    let path = Path::new(file_path);
+    let exists = path.exists();
+    if exists {
+        let existing = fs::read_to_string(path)?;
+        let first_line = existing
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .next()
+            .unwrap_or("");
+        if first_line != header_line {
+            return Err("既存ファイルの列構成が一致しないため追記できません".into());
+        }
+    } else {
+        ensure_parent_dir(file_path)?;
     }
 
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    if !exists {
+        file.write_all(&[0xEF, 0xBB, 0xBF])?;
+        file.write_all(format!("{header_line}\n").as_bytes())?;
+    }
+    for row in rows {
+        let line = format!("{},{}\n", run_label, row.join(","));
+        file.write_all(line.as_bytes())?;
+    }
     Ok(())
 }
 
+/// 追記時に各行へ付与する実行ラベル（実行日時）
+pub fn run_label() -> String {
+    Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
 /// TXT形式でファイルを保存
 pub async fn save_txt_file(
     content: &str,
     file_path: &str,
     encoding: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let encoding_lower = encoding.to_ascii_lowercase();
-
-    let bytes = match encoding_lower.as_str() {
-        "utf-8" => content.as_bytes().to_vec(),
-        "shift-jis" => {
-            let (encoded, _, _) = encoding_rs::SHIFT_JIS.encode(content);
-            encoded.to_vec()
-        }
-        _ => content.as_bytes().to_vec(),
-    };
+    let bytes = encode_text(content, encoding)?;
 
+    ensure_parent_dir(file_path)?;
     fs::write(file_path, bytes)?;
     Ok(())
 }
@@ -132,7 +267,7 @@ pub async fn save_parts_saver_format(
         csv_data.push(vec![String::new(); bom_data.headers.len()]);
     }
 
-    save_csv_file(&csv_data, file_path, "utf-8").await?;
+    save_csv_file(&csv_data, file_path, DEFAULT_CSV_ENCODING).await?;
     Ok(())
 }
 
@@ -200,6 +335,16 @@ pub async fn save_part_msf_format(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("TDK"), "TDK");
+        assert_eq!(sanitize_file_name("../../etc/passwd"), ".._.._etc_passwd");
+        assert_eq!(sanitize_file_name("a\\b:c"), "a_b_c");
+        assert_eq!(sanitize_file_name(".."), "_");
+        assert_eq!(sanitize_file_name(" Murata. "), "Murata");
+        assert_eq!(sanitize_file_name("x\ny"), "x_y");
+    }
+
     #[test]
     fn test_get_current_date_string() {
         let date_str = get_current_date_string();
@@ -207,6 +352,75 @@ mod tests {
         assert!(date_str.chars().all(|c| c.is_ascii_digit()));
     }
 
+    #[test]
+    fn test_ensure_parent_dir_creates_missing_folders() {
+        let base = std::env::temp_dir().join(format!("bom_tool_test_{}", std::process::id()));
+        let target = base.join("output").join("2024").join("result.csv");
+
+        ensure_parent_dir(&target.to_string_lossy()).unwrap();
+
+        assert!(target.parent().unwrap().is_dir());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_append_csv_file_checks_header() {
+        let base = std::env::temp_dir().join(format!("bom_tool_append_{}", std::process::id()));
+        let target = base.join("rolling.csv");
+        let target_str = target.to_string_lossy().to_string();
+        let data = vec![
+            vec!["部品番号".to_string(), "型番".to_string()],
+            vec!["C1".to_string(), "CAP100".to_string()],
+        ];
+
+        append_csv_rows(&data, &target_str, "run1").unwrap();
+        append_csv_rows(&data, &target_str, "run2").unwrap();
+        let content = fs::read_to_string(&target).unwrap();
+        let lines: Vec<&str> = content.trim_start_matches('\u{feff}').lines().collect();
+        assert_eq!(
+            lines,
+            vec!["実行ID,部品番号,型番", "run1,C1,CAP100", "run2,C1,CAP100"]
+        );
+
+        let other = vec![vec!["部品番号".to_string()], vec!["C1".to_string()]];
+        assert!(append_csv_rows(&other, &target_str, "run3").is_err());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_write_quoted_csv_escapes_values() {
+        let data = vec![
+            vec!["元の値".to_string(), "修正後".to_string()],
+            vec!["10k, 1%".to_string(), "say \"hi\"".to_string()],
+        ];
+        let mut buffer = Vec::new();
+
+        write_quoted_csv(&data, &mut buffer).unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "元の値,修正後\n\"10k, 1%\",\"say \"\"hi\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_text() {
+        assert_eq!(encode_text("部品", "utf-8").unwrap(), "部品".as_bytes());
+        assert_eq!(
+            encode_text("A", "UTF-8-BOM").unwrap(),
+            vec![0xEF, 0xBB, 0xBF, b'A']
+        );
+        assert_eq!(
+            encode_text("部品", "shift_jis").unwrap(),
+            vec![0x95, 0x94, 0x95, 0x69]
+        );
+        assert_eq!(encode_text("A", "shift-jis").unwrap(), b"A");
+        assert!(encode_text("Ω 😀", "shift_jis").unwrap_err().contains('😀'));
+        assert!(encode_text("A", "euc-jp").is_err());
+        assert!(is_utf8_encoding("utf-8-bom"));
+        assert!(!is_utf8_encoding("shift_jis"));
+    }
+
     #[test]
     fn test_add_timestamp_to_filename() {
         let result = add_timestamp_to_filename("/path/to/file.csv", "comparison");
@@ -0,0 +1,328 @@
+use crate::{BomData, BomRow};
+
+/// 部品表の行を絞り込む条件式
+/// 書式: `manufacturer=="TDK" && qty>10`、`(part_number contains "C" || model_number != "X")`
+/// 演算子: ==, !=, contains, >, <（>, < は数値として比較）、論理演算: &&, ||, 括弧
+/// 値は "..." で囲むか、空白を含まない語で指定する
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    Compare {
+        field: Field,
+        op: CompareOp,
+        value: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    PartNumber,
+    ModelNumber,
+    Quantity,
+    Attribute(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Contains,
+    Gt,
+    Lt,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Text(String),
+    Op(CompareOp),
+    And,
+    Or,
+    LParen,
+    RParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+        let next = chars.get(pos + 1).copied();
+        match c {
+            _ if c.is_whitespace() => pos += 1,
+            '(' => {
+                tokens.push(Token::LParen);
+                pos += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                pos += 1;
+            }
+            '&' if next == Some('&') => {
+                tokens.push(Token::And);
+                pos += 2;
+            }
+            '|' if next == Some('|') => {
+                tokens.push(Token::Or);
+                pos += 2;
+            }
+            '=' if next == Some('=') => {
+                tokens.push(Token::Op(CompareOp::Eq));
+                pos += 2;
+            }
+            '!' if next == Some('=') => {
+                tokens.push(Token::Op(CompareOp::Ne));
+                pos += 2;
+            }
+            '>' => {
+                tokens.push(Token::Op(CompareOp::Gt));
+                pos += 1;
+            }
+            '<' => {
+                tokens.push(Token::Op(CompareOp::Lt));
+                pos += 1;
+            }
+            '"' => {
+                let start = pos + 1;
+                let end = chars[start..]
+                    .iter()
+                    .position(|&ch| ch == '"')
+                    .map(|offset| start + offset)
+                    .ok_or_else(|| "引用符が閉じられていません".to_string())?;
+                tokens.push(Token::Text(chars[start..end].iter().collect()));
+                pos = end + 1;
+            }
+            _ => {
+                let start = pos;
+                while pos < chars.len()
+                    && !chars[pos].is_whitespace()
+                    && !"()&|=!<>\"".contains(chars[pos])
+                {
+                    pos += 1;
+                }
+                if start == pos {
+                    return Err(format!("不明な記号です: {}", c));
+                }
+                let word: String = chars[start..pos].iter().collect();
+                if word.eq_ignore_ascii_case("contains") {
+                    tokens.push(Token::Op(CompareOp::Contains));
+                } else {
+                    tokens.push(Token::Ident(word));
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    resolve: &'a dyn Fn(&str) -> Option<Field>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpr, String> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = FilterExpr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpr, String> {
+        let mut left = self.parse_term()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            let right = self.parse_term()?;
+            left = FilterExpr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_term(&mut self) -> Result<FilterExpr, String> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err("括弧が閉じられていません".to_string()),
+                }
+            }
+            Some(Token::Ident(name)) => {
+                let field =
+                    (self.resolve)(&name).ok_or_else(|| format!("不明な項目です: {}", name))?;
+                let op = match self.next() {
+                    Some(Token::Op(op)) => op,
+                    _ => return Err(format!("{} の後に比較演算子が必要です", name)),
+                };
+                let value = match self.next() {
+                    Some(Token::Text(value)) | Some(Token::Ident(value)) => value,
+                    _ => return Err(format!("{} の比較値が指定されていません", name)),
+                };
+                Ok(FilterExpr::Compare { field, op, value })
+            }
+            _ => Err("条件式が不正です".to_string()),
+        }
+    }
+}
+
+/// 条件式を解析する。項目名は部品番号・型番・数量の組み込み名、別名（aliases）、部品表の列名の順に解決する
+pub fn parse_filter(
+    expression: &str,
+    headers: &[String],
+    aliases: &[(&str, String)],
+) -> Result<FilterExpr, String> {
+    let resolve = |name: &str| -> Option<Field> {
+        let lower = name.to_lowercase();
+        match lower.as_str() {
+            "part_number" | "part" => return Some(Field::PartNumber),
+            "model_number" | "model" => return Some(Field::ModelNumber),
+            "quantity" | "qty" => return Some(Field::Quantity),
+            _ => {}
+        }
+        if let Some((_, header)) = aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+        {
+            return Some(Field::Attribute(header.clone()));
+        }
+        headers
+            .iter()
+            .find(|header| header.to_lowercase() == lower)
+            .map(|header| Field::Attribute(header.clone()))
+    };
+
+    let tokens = tokenize(expression)?;
+    if tokens.is_empty() {
+        return Err("条件式が空です".to_string());
+    }
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        resolve: &resolve,
+    };
+    let expr = parser.parse_or()?;
+    if parser.pos < parser.tokens.len() {
+        return Err("条件式の末尾に不要な記述があります".to_string());
+    }
+    Ok(expr)
+}
+
+fn field_value(row: &BomRow, field: &Field) -> Option<String> {
+    match field {
+        Field::PartNumber => Some(row.part_number.clone()),
+        Field::ModelNumber => Some(row.model_number.clone()),
+        Field::Quantity => row.quantity.map(|q| q.to_string()),
+        Field::Attribute(name) => row.attributes.get(name).cloned(),
+    }
+}
+
+pub fn matches(expr: &FilterExpr, row: &BomRow) -> bool {
+    match expr {
+        FilterExpr::And(left, right) => matches(left, row) && matches(right, row),
+        FilterExpr::Or(left, right) => matches(left, row) || matches(right, row),
+        FilterExpr::Compare { field, op, value } => {
+            let actual = field_value(row, field).unwrap_or_default();
+            match op {
+                CompareOp::Eq => actual == *value,
+                CompareOp::Ne => actual != *value,
+                CompareOp::Contains => actual.to_uppercase().contains(&value.to_uppercase()),
+                CompareOp::Gt | CompareOp::Lt => {
+                    match (actual.trim().parse::<f64>(), value.trim().parse::<f64>()) {
+                        (Ok(a), Ok(b)) if *op == CompareOp::Gt => a > b,
+                        (Ok(a), Ok(b)) => a < b,
+                        _ => false,
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 条件に一致する行を返す
+pub fn filter_rows<'a>(bom: &'a BomData, expr: &FilterExpr) -> Vec<&'a BomRow> {
+    bom.rows.iter().filter(|row| matches(expr, row)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VerbatimColumns;
+    use indexmap::IndexMap;
+
+    fn create_test_bom() -> BomData {
+        let row = |part: &str, model: &str, maker: &str, quantity: u32| {
+            let mut attributes = IndexMap::new();
+            attributes.insert("メーカー".to_string(), maker.to_string());
+            BomRow {
+                part_number: part.to_string(),
+                model_number: model.to_string(),
+                attributes,
+                quantity: Some(quantity),
+            }
+        };
+        BomData {
+            headers: vec![
+                "部品番号".to_string(),
+                "型番".to_string(),
+                "メーカー".to_string(),
+            ],
+            rows: vec![
+                row("C1", "CAP100", "TDK", 20),
+                row("C2", "CAP200", "TDK", 5),
+                row("R1", "RES100", "KOA", 50),
+            ],
+            verbatim: VerbatimColumns::default(),
+        }
+    }
+
+    fn run(expression: &str) -> Result<Vec<String>, String> {
+        let bom = create_test_bom();
+        let aliases = [("manufacturer", "メーカー".to_string())];
+        let expr = parse_filter(expression, &bom.headers, &aliases)?;
+        Ok(filter_rows(&bom, &expr)
+            .into_iter()
+            .map(|row| row.part_number.clone())
+            .collect())
+    }
+
+    #[test]
+    fn test_filter_comparisons_and_logic() {
+        assert_eq!(run(r#"manufacturer=="TDK" && qty>10"#).unwrap(), vec!["C1"]);
+        assert_eq!(run("メーカー != TDK").unwrap(), vec!["R1"]);
+        assert_eq!(
+            run(r#"model contains "cap" || qty < 10"#).unwrap(),
+            vec!["C1", "C2"]
+        );
+        assert_eq!(
+            run(r#"(part == C1 || part == R1) && qty > 30"#).unwrap(),
+            vec!["R1"]
+        );
+    }
+
+    #[test]
+    fn test_filter_rejects_invalid_expressions() {
+        assert!(run("color == red").unwrap_err().contains("color"));
+        assert!(run(r#"part == "C1"#).is_err());
+        assert!(run("(part == C1").is_err());
+        assert!(run("part C1").is_err());
+        assert!(run("").is_err());
+    }
+}
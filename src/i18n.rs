@@ -0,0 +1,185 @@
+/// 設定で選択できる表示言語
+pub const SUPPORTED_LANGUAGES: &[&str] = &["ja", "en"];
+pub const DEFAULT_LANGUAGE: &str = "ja";
+
+/// バックエンドから返すメッセージの識別子
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    BomsNotLoaded,
+    BomNotLoaded,
+    InvalidSide,
+    NoComparisonResult,
+    NoSynthesisResult,
+    BomLoaded,
+    BomUpdated,
+    RegisteredNamesApplied,
+    RegisteredNamesSaved,
+    OverridesUpdated,
+    OverridesApplied,
+    SettingsSaved,
+    DictionarySaved,
+    AllDataCleared,
+    DataClearedKeepingLists,
+    RegisteredNamesCleared,
+    OverridesCleared,
+    SessionRestored,
+}
+
+/// メッセージを指定言語の文言に変換する（未対応の言語は日本語）
+/// 文言中の `{side}` は `text_with_side` で部品表のサイド（A/B）に置き換える
+pub fn text(language: &str, message: Message) -> &'static str {
+    let english = language == "en";
+    match message {
+        Message::BomsNotLoaded => {
+            if english {
+                "BOM A or B has not been loaded"
+            } else {
+                "部品表AまたはBが読み込まれていません"
+            }
+        }
+        Message::BomNotLoaded => {
+            if english {
+                "BOM {side} has not been loaded"
+            } else {
+                "部品表{side}が読み込まれていません"
+            }
+        }
+        Message::InvalidSide => {
+            if english {
+                "Invalid side"
+            } else {
+                "サイド指定が無効です"
+            }
+        }
+        Message::NoComparisonResult => {
+            if english {
+                "No comparison result"
+            } else {
+                "比較結果がありません"
+            }
+        }
+        Message::NoSynthesisResult => {
+            if english {
+                "No synthesis result"
+            } else {
+                "合成結果がありません"
+            }
+        }
+        Message::BomLoaded => {
+            if english {
+                "Loaded BOM {side}"
+            } else {
+                "部品表{side}を読み込みました"
+            }
+        }
+        Message::BomUpdated => {
+            if english {
+                "Updated BOM {side}"
+            } else {
+                "部品表{side}を更新しました"
+            }
+        }
+        Message::RegisteredNamesApplied => {
+            if english {
+                "Applied registered names to BOM {side}"
+            } else {
+                "部品表{side}に登録名を適用しました"
+            }
+        }
+        Message::RegisteredNamesSaved => {
+            if english {
+                "Saved the registered name list"
+            } else {
+                "登録名リストを保存しました"
+            }
+        }
+        Message::OverridesUpdated => {
+            if english {
+                "Updated the override list"
+            } else {
+                "上書きリストを更新しました"
+            }
+        }
+        Message::OverridesApplied => {
+            if english {
+                "Applied overrides to BOM {side}"
+            } else {
+                "部品表{side}に上書きを適用しました"
+            }
+        }
+        Message::SettingsSaved => {
+            if english {
+                "Saved settings"
+            } else {
+                "設定を保存しました"
+            }
+        }
+        Message::DictionarySaved => {
+            if english {
+                "Saved the column dictionary"
+            } else {
+                "辞書を保存しました"
+            }
+        }
+        Message::AllDataCleared => {
+            if english {
+                "Cleared all data"
+            } else {
+                "全データをクリアしました"
+            }
+        }
+        Message::DataClearedKeepingLists => {
+            if english {
+                "Cleared data, keeping registered names and overrides"
+            } else {
+                "登録名と上書きを保持してクリアしました"
+            }
+        }
+        Message::RegisteredNamesCleared => {
+            if english {
+                "Cleared the registered name list"
+            } else {
+                "登録名リストをクリアしました"
+            }
+        }
+        Message::OverridesCleared => {
+            if english {
+                "Cleared the override list"
+            } else {
+                "上書きリストをクリアしました"
+            }
+        }
+        Message::SessionRestored => {
+            if english {
+                "Restored the session"
+            } else {
+                "セッションを復元しました"
+            }
+        }
+    }
+}
+
+pub fn text_with_side(language: &str, message: Message, side: &str) -> String {
+    text(language, message).replace("{side}", &side.to_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_falls_back_to_japanese() {
+        assert_eq!(
+            text("en", Message::BomsNotLoaded),
+            "BOM A or B has not been loaded"
+        );
+        assert_eq!(
+            text("fr", Message::BomsNotLoaded),
+            "部品表AまたはBが読み込まれていません"
+        );
+        assert_eq!(
+            text_with_side("ja", Message::BomLoaded, "b"),
+            "部品表Bを読み込みました"
+        );
+    }
+}
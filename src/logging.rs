@@ -0,0 +1,98 @@
+use chrono::Local;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+const LOG_FILE_NAME: &str = "bom_tool.log";
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+
+/// ログをファイルへ追記するロガー（デバッグビルドでは標準出力にも出力する）
+struct FileLogger {
+    file: Mutex<Option<File>>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "{} [{}] {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.args()
+        );
+
+        if cfg!(debug_assertions) {
+            println!("{line}");
+        }
+
+        if let Ok(mut guard) = self.file.lock() {
+            if let Some(file) = guard.as_mut() {
+                let _ = writeln!(file, "{line}");
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut guard) = self.file.lock() {
+            if let Some(file) = guard.as_mut() {
+                let _ = file.flush();
+            }
+        }
+    }
+}
+
+/// ロガーを初期化する。ログフォルダを作成できない場合は標準出力のみとなる
+pub fn init(log_dir: Option<&Path>, level: &str) {
+    let file = log_dir.and_then(|dir| {
+        fs::create_dir_all(dir).ok()?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG_FILE_NAME))
+            .ok()
+    });
+
+    let logger = FileLogger {
+        file: Mutex::new(file),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(parse_level_filter(level));
+    }
+}
+
+/// 設定変更時にログレベルを反映する
+pub fn set_level(level: &str) {
+    log::set_max_level(parse_level_filter(level));
+}
+
+/// 設定値のログレベルを解釈する（不明な値は info）
+pub fn parse_level_filter(level: &str) -> LevelFilter {
+    match level.trim().to_lowercase().as_str() {
+        "off" => LevelFilter::Off,
+        "error" => LevelFilter::Error,
+        "warn" | "warning" => LevelFilter::Warn,
+        "debug" => LevelFilter::Debug,
+        "trace" => LevelFilter::Trace,
+        _ => LevelFilter::Info,
+    }
+}
+
+/// フロントエンドから渡されたレベル文字列をログレベルに変換する
+pub fn parse_level(level: &str) -> Level {
+    match level.trim().to_lowercase().as_str() {
+        "error" => Level::Error,
+        "warn" | "warning" => Level::Warn,
+        "debug" => Level::Debug,
+        "trace" => Level::Trace,
+        _ => Level::Info,
+    }
+}
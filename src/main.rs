@@ -1,24 +1,32 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::fs::{self, File};
-use std::io::Write;
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
-use tauri::State;
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager, State};
 use tauri_plugin_dialog::DialogExt;
 use tokio::sync::oneshot;
 
 mod bom_processor;
 mod comparison;
+mod error;
 mod file_handler;
+mod filter;
+mod i18n;
+mod logging;
 mod session;
 mod synthesis;
 use comparison::*;
+use error::AppError;
+use i18n::Message;
 use session::{
     collect_snapshots, delete_snapshot, load_snapshot, save_snapshot, SessionKind, SessionSnapshot,
 };
@@ -26,11 +34,22 @@ use synthesis::*;
 
 const SETTINGS_DIR: &str = "../sessions/settings";
 const SETTINGS_FILE_NAME: &str = "bom_settings.json";
+const REMEMBERED_MAPPINGS_FILE_NAME: &str = "remembered_mappings.json";
+const REMEMBERED_MAPPING_LIMIT: usize = 200;
 const SETTINGS_ACTIONS: &[&str] = &["copy_above", "expand_range", "replace_with", "ignore"];
 
 const DICTIONARY_DIR: &str = "../dictionary";
 const DICTIONARY_FILE_NAME: &str = "custom_dict.json";
 const AUTO_PREVIEW_LIMIT: usize = 15;
+const COMPARISON_PAGE_LIMIT: usize = 200;
+const AUTOSAVE_RESULT_ROW_LIMIT: usize = 50_000;
+const REGISTERED_NAME_PREVIEW_LIMIT: usize = 500;
+const COVERAGE_PAGE_LIMIT: usize = 500;
+const CAD_MISSING_MANUFACTURER_LIMIT: usize = 20;
+const CAD_EXPORT_PROGRESS_INTERVAL: usize = 10_000;
+const CAD_EXPORT_PROGRESS_EVENT: &str = "cad-export-progress";
+const FILTER_ROW_LIMIT: usize = 500;
+const EMPTY_PART_ROW_LIST_LIMIT: usize = 20;
 
 // アプリケーションの状態管理
 #[derive(Debug)]
@@ -38,15 +57,34 @@ pub struct AppState {
     pub bom_a: Mutex<Option<BomData>>,
     pub bom_b: Mutex<Option<BomData>>,
     pub comparison_result: Mutex<Option<ComparisonResult>>,
+    /// 最後に比較を実行したときの比較オプション（セッションに保存し、復元時に戻す）
+    pub comparison_options: Mutex<Option<CompareOptions>>,
     pub synthesis_result: Mutex<Option<SynthesisResult>>,
+    /// 合成結果から作成した統合部品表
+    pub consolidated_bom: Mutex<Option<BomData>>,
+    /// 比較結果から追加・変更された部品だけを抜き出した部品表
+    pub changed_bom: Mutex<Option<BomData>>,
     pub registered_name_list: Mutex<Option<RegisteredNameList>>,
     pub override_list: Mutex<Option<OverrideList>>,
     pub file_a_path: Mutex<Option<String>>,
     pub file_b_path: Mutex<Option<String>>,
     pub column_mapping_a: Mutex<Option<ColumnMapping>>,
     pub column_mapping_b: Mutex<Option<ColumnMapping>>,
+    pub column_mapping_by_name_a: Mutex<Option<ColumnMappingByName>>,
+    pub column_mapping_by_name_b: Mutex<Option<ColumnMappingByName>>,
+    /// 読み込み時に自動修正した内容（部品表ごと）
+    pub corrections_a: Mutex<Vec<AutoCorrection>>,
+    pub corrections_b: Mutex<Vec<AutoCorrection>>,
+    /// 読み込み後に部品表へ適用した操作（セッションに保存し、別のファイルに再適用できる）
+    pub operations_a: Mutex<Vec<BomOperation>>,
+    pub operations_b: Mutex<Vec<BomOperation>>,
     pub settings: Mutex<AppSettings>,
     pub column_dictionary: Mutex<ColumnDictionary>,
+    /// 列推定用に正規化済みの辞書（辞書の保存・取り込み時に作り直す）
+    pub compiled_dictionary: Mutex<Arc<bom_processor::CompiledDictionary>>,
+    pub loaded_boms: Mutex<HashMap<String, BomData>>,
+    /// ファイルごとに最後に使った列指定（正規化したパス → 列指定）
+    pub remembered_mappings: Mutex<BTreeMap<String, RememberedMapping>>,
 }
 
 // 部品データ構造
@@ -54,13 +92,27 @@ pub struct AppState {
 pub struct BomData {
     pub headers: Vec<String>,
     pub rows: Vec<BomRow>,
+    #[serde(default)]
+    pub verbatim: VerbatimColumns,
+}
+
+// 正規化せず元のセル値のまま保持する列（読み込み時・前処理時ともに変換しない）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VerbatimColumns {
+    pub part_number: bool,
+    pub model_number: bool,
+    /// 属性列の列名
+    pub attributes: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BomRow {
     pub part_number: String,
     pub model_number: String,
-    pub attributes: HashMap<String, String>,
+    /// 列名 → 値（ヘッダーの並び順を保持する）
+    pub attributes: IndexMap<String, String>,
+    #[serde(default)]
+    pub quantity: Option<u32>,
 }
 
 // 列指定の構造体
@@ -70,6 +122,50 @@ pub struct ColumnMapping {
     pub model_number: usize,
     #[serde(default)]
     pub manufacturer: Option<usize>,
+    #[serde(default)]
+    pub quantity: Option<usize>,
+    /// 保持する列番号（未指定の場合は全列を保持。キー列は常に保持）
+    #[serde(default)]
+    pub keep_columns: Option<Vec<usize>>,
+    /// CSVの先頭行がヘッダーかどうか（false の場合は「列1」「列2」…を付与して全行をデータとして扱う）
+    #[serde(default = "default_has_headers")]
+    pub has_headers: bool,
+    /// 正規化せず元の値のまま保持する列番号
+    #[serde(default)]
+    pub verbatim_columns: Vec<usize>,
+    /// 日付として YYYY-MM-DD に統一する列番号（表記揺れによる差分を防ぐ）
+    #[serde(default)]
+    pub date_columns: Vec<usize>,
+    /// 「部品番号|型番」のように1列に詰めた列を区切り文字で分割する（列番号, 区切り文字）
+    /// 指定時は part_number / model_number の列番号を使わない
+    #[serde(default)]
+    pub split_column: Option<(usize, char)>,
+    /// 列ごとの空白の扱い（列番号, 扱い）。指定のない列は空白を削除する
+    #[serde(default)]
+    pub whitespace_modes: Vec<(usize, bom_processor::WhitespaceMode)>,
+}
+
+fn default_has_headers() -> bool {
+    true
+}
+
+// 前回そのファイルを読み込んだときの列指定（ヘッダーが同じ場合のみ再利用する）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RememberedMapping {
+    pub headers: Vec<String>,
+    pub mapping: ColumnMapping,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 列名による列指定（読み込み時に実際のヘッダー行から列番号を解決する）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnMappingByName {
+    pub part_number: String,
+    pub model_number: String,
+    #[serde(default)]
+    pub manufacturer: Option<String>,
+    #[serde(default)]
+    pub quantity: Option<String>,
 }
 
 // 比較結果
@@ -80,6 +176,23 @@ pub struct ComparisonResult {
     pub b_only_parts: Vec<ComparisonRow>,
     #[serde(default)]
     pub modified_parts: Vec<ComparisonRow>,
+    /// 部品番号のみが変わったとみなした部品（CompareOptions.detect_renames 指定時のみ）
+    #[serde(default)]
+    pub renamed_parts: Vec<ComparisonRow>,
+    #[serde(default)]
+    pub duplicate_parts: Vec<DuplicatePart>,
+    #[serde(default)]
+    pub excluded_a: usize,
+    #[serde(default)]
+    pub excluded_b: usize,
+}
+
+// 同一部品番号が複数行に存在し、比較時に1行へ集約されたもの
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicatePart {
+    pub side: String, // "a" or "b"
+    pub part_number: String,
+    pub occurrences: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -87,9 +200,25 @@ pub struct ComparisonRow {
     pub part_number: String,
     pub model_a: String,
     pub model_b: String,
-    pub status: String, // "common", "a_only", "b_only"
+    pub status: String, // "common", "a_only", "b_only", "modified", "renamed"
     #[serde(default = "default_change_type")]
-    pub change_type: String, // "ADDED", "REMOVED", "MODIFIED", "UNCHANGED"
+    pub change_type: String, // "ADDED", "REMOVED", "MODIFIED", "UNCHANGED", "RENAMED"
+    /// 値が異なった比較対象属性の列名
+    #[serde(default)]
+    pub changed_attributes: Vec<String>,
+    /// changed_attributes の各列のA側の値（列がない場合は空文字）
+    #[serde(default)]
+    pub changed_values_a: IndexMap<String, String>,
+    /// changed_attributes の各列のB側の値（列がない場合は空文字）
+    #[serde(default)]
+    pub changed_values_b: IndexMap<String, String>,
+    #[serde(default)]
+    pub quantity_a: Option<u32>,
+    #[serde(default)]
+    pub quantity_b: Option<u32>,
+    /// 部品番号変更の場合のA側の部品番号（part_number はB側の部品番号）
+    #[serde(default)]
+    pub renamed_from: Option<String>,
 }
 
 fn default_change_type() -> String {
@@ -108,6 +237,56 @@ pub struct SynthesisRow {
     pub model_a: String,
     pub model_b: String,
     pub status: String, // "common", "missing_a", "missing_b"
+    /// 合成時の採用型番（PreferenceStrategy で選択）
+    #[serde(default)]
+    pub preferred_model: String,
+}
+
+/// 部品表に適用した操作の記録（replay_session_operations で順に再適用する）
+/// セルの直接編集など、別のファイルに再適用できない操作は記録しない
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BomOperation {
+    /// ファイルの読み込み（列を絞り込む前の列指定）
+    Load {
+        column_mapping: Box<ColumnMapping>,
+        #[serde(default)]
+        mapping_by_name: Option<ColumnMappingByName>,
+        /// 読み込んだワークシート（Excelのみ。シート名で記録する）
+        #[serde(default)]
+        sheet: Option<bom_processor::SheetSelector>,
+    },
+    Preprocess {
+        rules: PreprocessRules,
+    },
+    /// 登録名リスト・上書きリストの適用（リストは適用時点ではなく再適用時のものを使う）
+    ApplyRegisteredNames {
+        policy: bom_processor::RegisteredNamePolicy,
+    },
+    RenameHeader {
+        old_name: String,
+        new_name: String,
+    },
+    /// 列の連結（drop_sources が true の場合は連結元の列の削除を含む）
+    MergeColumns {
+        source_a: String,
+        source_b: String,
+        target: String,
+        separator: String,
+        drop_sources: bool,
+    },
+    /// 列の分割（remove_source が true の場合は分割元の列の削除を含む）
+    SplitColumn {
+        source: String,
+        separator: String,
+        target_names: Vec<String>,
+        remove_source: bool,
+    },
+    Optimize {
+        strategy: bom_processor::MergeStrategy,
+    },
+    /// 画面上での手動編集（新しいファイルには再適用できないため記録のみ）
+    ManualEdit,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,6 +295,53 @@ pub struct PreprocessRules {
     pub expand_ranges: bool,
     pub fullwidth_to_halfwidth: bool,
     pub lowercase_to_uppercase: bool,
+    /// 範囲展開の対象とする参照記号の正規表現（未指定の場合は設定値または既定パターン）
+    #[serde(default)]
+    pub designator_pattern: Option<String>,
+    /// 展開した行に元の範囲表記を「元参照」属性として残す
+    #[serde(default)]
+    pub keep_range_source: bool,
+    /// 範囲展開する最大幅（超えた範囲は展開せず警告する。MAX_RANGE_SPAN_LIMIT を上限に切り詰める）
+    #[serde(
+        default = "default_max_range_span",
+        deserialize_with = "deserialize_max_range_span"
+    )]
+    pub max_range_span: u32,
+    /// 降順の範囲表記（C10-C8）を降順に展開する（無効時は展開せず警告する）
+    #[serde(default)]
+    pub expand_descending: bool,
+    /// 数値として正規化する属性列（列ごとに桁区切り・小数点の表記を指定する）
+    #[serde(default)]
+    pub numeric_columns: Vec<NumericColumnRule>,
+}
+
+/// 数値列の正規化指定。値を指定の表記で解釈し、桁区切りなし・小数点 `.` の形式に揃える
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NumericColumnRule {
+    pub column: String,
+    #[serde(default)]
+    pub convention: NumberConvention,
+}
+
+/// 数値の表記（桁区切りと小数点の組み合わせ）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberConvention {
+    /// 1,000.5（桁区切り `,`、小数点 `.`）
+    #[default]
+    Us,
+    /// 1.000,5（桁区切り `.`、小数点 `,`）
+    European,
+}
+
+fn default_max_range_span() -> u32 {
+    bom_processor::DEFAULT_MAX_RANGE_SPAN
+}
+
+fn deserialize_max_range_span<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u32, D::Error> {
+    Ok(u32::deserialize(deserializer)?.min(bom_processor::MAX_RANGE_SPAN_LIMIT))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -127,6 +353,8 @@ pub struct RegisteredNameList {
 pub struct RegisteredNameEntry {
     pub part_model: String,
     pub registered_name: String,
+    #[serde(default)]
+    pub manufacturer: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -136,8 +364,20 @@ pub struct OverrideList {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OverrideEntry {
+    /// 照合する値（match_field が model_number の場合は型番）
     pub part_number: String,
     pub registered_name: String,
+    #[serde(default)]
+    pub match_field: OverrideMatchField,
+}
+
+/// 上書きの照合対象。部品番号での上書きが型番での上書きより優先される
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverrideMatchField {
+    #[default]
+    PartNumber,
+    ModelNumber,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -162,15 +402,31 @@ impl Default for AppState {
             bom_a: Mutex::new(None),
             bom_b: Mutex::new(None),
             comparison_result: Mutex::new(None),
+            comparison_options: Mutex::new(None),
             synthesis_result: Mutex::new(None),
+            consolidated_bom: Mutex::new(None),
+            changed_bom: Mutex::new(None),
             registered_name_list: Mutex::new(None),
             override_list: Mutex::new(None),
             file_a_path: Mutex::new(None),
             file_b_path: Mutex::new(None),
             column_mapping_a: Mutex::new(None),
             column_mapping_b: Mutex::new(None),
+            column_mapping_by_name_a: Mutex::new(None),
+            column_mapping_by_name_b: Mutex::new(None),
+            corrections_a: Mutex::new(Vec::new()),
+            corrections_b: Mutex::new(Vec::new()),
+            operations_a: Mutex::new(Vec::new()),
+            operations_b: Mutex::new(Vec::new()),
             settings: Mutex::new(settings),
+            compiled_dictionary: Mutex::new(Arc::new(bom_processor::CompiledDictionary::new(
+                &dictionary,
+            ))),
             column_dictionary: Mutex::new(dictionary),
+            loaded_boms: Mutex::new(HashMap::new()),
+            remembered_mappings: Mutex::new(
+                load_remembered_mappings_from_disk().unwrap_or_default(),
+            ),
         }
     }
 }
@@ -180,6 +436,61 @@ struct LoadFileResponse {
     message: String,
     side: String,
     preview: Option<PreviewTable>,
+    /// 読み込んだワークシート名（Excelのみ）
+    sheet_name: Option<String>,
+    /// 列指定の確認を促す警告（読み込みは完了している）
+    warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct LoadedFileSummary {
+    name: String,
+    rows: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct LoadFailure {
+    name: String,
+    error: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SideStatus {
+    loaded: bool,
+    row_count: usize,
+    file_path: Option<String>,
+    has_column_mapping: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct AppStatus {
+    app_version: String,
+    bom_a: SideStatus,
+    bom_b: SideStatus,
+    has_comparison_result: bool,
+    has_synthesis_result: bool,
+    registered_name_count: usize,
+    override_count: usize,
+    folder_bom_count: usize,
+    language: String,
+    settings_path: String,
+    dictionary_path: String,
+    dictionary_column_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct LoadFolderResponse {
+    message: String,
+    loaded: Vec<LoadedFileSummary>,
+    failed: Vec<LoadFailure>,
+}
+
+#[derive(Debug, Serialize)]
+struct CoverageMatrixResponse {
+    files: Vec<String>,
+    total_parts: usize,
+    offset: usize,
+    rows: Vec<synthesis::CoverageRow>,
 }
 
 #[derive(Debug, Serialize)]
@@ -187,6 +498,15 @@ struct AnalyzeFileResponse {
     headers: Vec<String>,
     suggested_mapping: Option<ColumnMapping>,
     sample_rows: Vec<Vec<String>>,
+    header_likely_missing: bool,
+    column_samples: Vec<Vec<String>>,
+    warnings: Vec<String>,
+    metadata: HashMap<String, String>,
+    /// suggested_mapping が前回このファイルを読み込んだときの列指定である
+    mapping_remembered: bool,
+    /// ブック内の全ワークシート名と解析したワークシート名（Excelのみ）
+    sheet_names: Vec<String>,
+    sheet_name: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -198,6 +518,72 @@ struct SessionListItem {
     file_b_name: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+struct ReplayOperationsResponse {
+    message: String,
+    side: String,
+    /// 読み込み後に再適用した操作の数
+    operation_count: usize,
+    warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct LoadBothResponse {
+    a: LoadFileResponse,
+    b: LoadFileResponse,
+}
+
+#[derive(Debug, Serialize)]
+struct UpdateCellResponse {
+    message: String,
+    /// 比較結果を該当部品のみ更新した場合 true（比較結果を破棄した場合・未比較の場合は false）
+    comparison_patched: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct PreflightCheck {
+    name: String,
+    passed: bool,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PreflightReport {
+    passed: bool,
+    checks: Vec<PreflightCheck>,
+}
+
+#[derive(Debug, Serialize)]
+struct BomValidationEntry {
+    /// "a" / "b" / "folder"
+    source: String,
+    row_count: usize,
+    result: ValidationResult,
+}
+
+#[derive(Debug, Serialize)]
+struct ValidateAllResponse {
+    passed: bool,
+    /// 部品表A/Bは "a:ファイル名" / "b:ファイル名"、フォルダ読み込み分はファイル名をキーとする
+    files: IndexMap<String, BomValidationEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct FilterBomResponse {
+    rows: Vec<BomRow>,
+    total_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct ComparisonTrendPoint {
+    id: String,
+    label: Option<String>,
+    created_at: String,
+    added: usize,
+    removed: usize,
+    modified: usize,
+}
+
 // 自動修正情報
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutoCorrection {
@@ -220,6 +606,48 @@ pub struct FormatRule {
 pub struct AppSettings {
     pub makers: Vec<String>,
     pub format_rules: Vec<FormatRule>,
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    #[serde(default)]
+    pub compare_attributes: Vec<String>,
+    /// 型番比較時の正規化（大文字・小文字、全角・半角、前後の空白）
+    #[serde(default)]
+    pub model_normalize: ModelNormalize,
+    /// 部品番号のみが変わった部品を「部品番号変更」として検出する
+    #[serde(default)]
+    pub detect_renames: bool,
+    /// 型番を比較しない（型番列のない部品表との比較用）
+    #[serde(default)]
+    pub ignore_models: bool,
+    /// 定数表記（"10k"、"0.1uF" など）を数値として比較する
+    #[serde(default)]
+    pub value_equivalence: bool,
+    /// 上書きリストと登録名リストの優先順位
+    #[serde(default)]
+    pub registered_name_policy: bom_processor::RegisteredNamePolicy,
+    #[serde(default)]
+    pub autosave_results: bool,
+    #[serde(default)]
+    pub autosave_result_row_limit: Option<usize>,
+    #[serde(default)]
+    pub last_open_dir: Option<String>,
+    #[serde(default)]
+    pub last_save_dir: Option<String>,
+    #[serde(default)]
+    pub range_designator_pattern: Option<String>,
+    /// 表示言語（"ja" / "en"）
+    #[serde(default = "default_language")]
+    pub language: String,
+    /// ログ出力レベル（"error" / "warn" / "info" / "debug" / "trace"）
+    #[serde(default)]
+    pub log_level: Option<String>,
+    /// 読み込むファイルサイズの上限（MB。未指定時は bom_processor::DEFAULT_MAX_FILE_SIZE_MB）
+    #[serde(default)]
+    pub max_file_size_mb: Option<u64>,
+}
+
+fn default_language() -> String {
+    i18n::DEFAULT_LANGUAGE.to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -279,16 +707,50 @@ struct SessionRestoreResponse {
     file_b_path: Option<String>,
     column_mapping_a: Option<ColumnMapping>,
     column_mapping_b: Option<ColumnMapping>,
+    column_mapping_by_name_a: Option<ColumnMappingByName>,
+    column_mapping_by_name_b: Option<ColumnMappingByName>,
     comparison_result: Option<ComparisonResult>,
     synthesis_result: Option<SynthesisResult>,
     bom_a_headers: Option<Vec<String>>,
     bom_b_headers: Option<Vec<String>>,
+    compare_options: Option<CompareOptions>,
 }
 
 #[derive(Debug, Serialize)]
 struct CompareResponse {
     result: ComparisonResult,
     stats: HashMap<String, usize>,
+    reconciled: bool,
+    /// 並び順のみが異なり、部品番号と型番の組が同一の場合 true
+    reorder_only: bool,
+    /// 正規化設定の不一致が疑われる場合の提案（自動適用はしない）
+    suggestion: Option<String>,
+    warnings: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PipelineRequest {
+    path_a: String,
+    mapping_a: ColumnMapping,
+    path_b: String,
+    mapping_b: ColumnMapping,
+    output_path: String,
+    format: String,
+    /// 未指定時は設定の比較オプションを使う
+    options: Option<CompareOptions>,
+}
+
+#[derive(Debug, Serialize)]
+struct PipelineResponse {
+    output_path: String,
+    stats: HashMap<String, usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct ComparisonPage {
+    total: usize,
+    offset: usize,
+    rows: Vec<ComparisonRow>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -302,6 +764,7 @@ struct PreprocessRequest {
 #[derive(Debug, Serialize)]
 struct PreprocessResponse {
     bom_data: BomSnapshot,
+    warnings: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -322,6 +785,14 @@ struct OverrideListResponse {
 struct RegisteredNameListResponse {
     list: RegisteredNameList,
     message: String,
+    conflicts: Vec<bom_processor::RegisteredNameConflict>,
+}
+
+#[derive(Debug, Serialize)]
+struct RegisteredNameListPreviewResponse {
+    entries: Vec<RegisteredNameEntry>,
+    total_count: usize,
+    errors: Vec<ValidationError>,
 }
 
 #[derive(Debug, Serialize)]
@@ -329,27 +800,183 @@ struct MessageResponse {
     message: String,
 }
 
+/// clear_data でクリアされる（dry_run 時はクリアされる予定の）データ
+#[derive(Debug, Serialize)]
+struct ClearTarget {
+    key: String,   // "bom_a" / "comparison_result" など AppState の項目名
+    label: String, // 表示用の名前（件数付き）
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct ClearDataResponse {
+    message: String,
+    dry_run: bool,
+    targets: Vec<ClearTarget>,
+}
+
 // ファイル読み込みコマンド
 #[tauri::command]
 async fn load_file(
     file_path: String,
     column_mapping: ColumnMapping,
     side: String, // "a" or "b"
+    strict: Option<bool>,
+    sheet: Option<bom_processor::SheetSelector>, // Excelのワークシート（シート名または0始まりの番号）
+    state: State<'_, AppState>,
+) -> Result<LoadFileResponse, AppError> {
+    load_file_with_mapping(
+        &state,
+        file_path,
+        column_mapping,
+        None,
+        sheet,
+        side,
+        strict.unwrap_or(false),
+    )
+    .await
+}
+
+// 列名で列を指定してファイルを読み込む（列の並び替えに影響されない）
+#[tauri::command]
+async fn load_file_by_name(
+    file_path: String,
+    mapping: ColumnMappingByName,
+    side: String,
+    strict: Option<bool>,
+    sheet: Option<bom_processor::SheetSelector>,
+    state: State<'_, AppState>,
+) -> Result<LoadFileResponse, AppError> {
+    let dictionary = Arc::clone(&state.compiled_dictionary.lock().unwrap());
+    let analysis = bom_processor::analyze_bom_file(
+        &file_path,
+        &dictionary,
+        true,
+        false,
+        max_file_size(&state),
+        sheet.as_ref(),
+    )
+    .await?;
+    let column_mapping = bom_processor::resolve_mapping_by_name(&analysis.headers, &mapping)?;
+    load_file_with_mapping(
+        &state,
+        file_path,
+        column_mapping,
+        Some(mapping),
+        sheet,
+        side,
+        strict.unwrap_or(false),
+    )
+    .await
+}
+
+// 同じファイルを列指定を変えて部品表A・Bの両方に読み込む（同一ファイル内の新旧列の比較用）
+// mapping_b を省略した場合は mapping_a を使用する。Excelでは sheet_a / sheet_b で別のシートを指定できる
+#[tauri::command]
+async fn load_file_as_both(
+    file_path: String,
+    mapping_a: ColumnMapping,
+    mapping_b: Option<ColumnMapping>,
+    sheet_a: Option<bom_processor::SheetSelector>,
+    sheet_b: Option<bom_processor::SheetSelector>,
     state: State<'_, AppState>,
-) -> Result<LoadFileResponse, String> {
+) -> Result<LoadBothResponse, AppError> {
+    let mapping_b = mapping_b.unwrap_or_else(|| mapping_a.clone());
+    let a = load_file_with_mapping(
+        &state,
+        file_path.clone(),
+        mapping_a,
+        None,
+        sheet_a,
+        "a".to_string(),
+        false,
+    )
+    .await?;
+    let b = load_file_with_mapping(
+        &state,
+        file_path,
+        mapping_b,
+        None,
+        sheet_b,
+        "b".to_string(),
+        false,
+    )
+    .await?;
+    Ok(LoadBothResponse { a, b })
+}
+
+async fn load_file_with_mapping(
+    state: &AppState,
+    file_path: String,
+    column_mapping: ColumnMapping,
+    mapping_by_name: Option<ColumnMappingByName>,
+    sheet: Option<bom_processor::SheetSelector>,
+    side: String,
+    strict: bool,
+) -> Result<LoadFileResponse, AppError> {
     let side_normalized = side.to_lowercase();
     if side_normalized != "a" && side_normalized != "b" {
-        return Err("無効なサイド指定です".to_string());
+        return Err(AppError::InvalidInput(tr(state, Message::InvalidSide)));
     }
 
-    match bom_processor::load_bom_file(&file_path, &column_mapping).await {
+    match bom_processor::load_bom_file(
+        &file_path,
+        &column_mapping,
+        max_file_size(state),
+        sheet.as_ref(),
+    )
+    .await
+    {
         Ok(load_result) => {
+            let load_operation = BomOperation::Load {
+                column_mapping: Box::new(column_mapping.clone()),
+                mapping_by_name: mapping_by_name.clone(),
+                sheet: load_result
+                    .sheet_name
+                    .clone()
+                    .map(bom_processor::SheetSelector::Name),
+            };
+            // 厳密モードでは部品番号が空の行を読み飛ばさず、読み込みを中止する
+            if strict && !load_result.skipped_empty_rows.is_empty() {
+                let rows: Vec<String> = load_result
+                    .skipped_empty_rows
+                    .iter()
+                    .take(EMPTY_PART_ROW_LIST_LIMIT)
+                    .map(|row| row.to_string())
+                    .collect();
+                return Err(AppError::InvalidInput(format!(
+                    "部品番号が空の行が{}行あります（{}行目）",
+                    load_result.skipped_empty_rows.len(),
+                    rows.join(", ")
+                )));
+            }
+            remember_column_mapping(
+                state,
+                &file_path,
+                load_result.source_headers.clone(),
+                column_mapping,
+            );
             let bom_data = load_result.bom;
-
-            let preview = match generate_preprocessed_preview(&bom_data, &column_mapping) {
+            let corrections = load_result.corrections;
+            let sheet_name = load_result.sheet_name;
+            let warnings = load_result.warnings;
+            for warning in &warnings {
+                warn!(
+                    "[load_file][mapping_warning] side={}, path={}, {}",
+                    side_normalized, file_path, warning
+                );
+            }
+            // 列を絞り込んだ場合は列番号が読み込み後の部品表に合わせて変わる
+            let column_mapping = load_result.column_mapping;
+
+            let preview = match generate_preprocessed_preview(
+                &bom_data,
+                &column_mapping,
+                range_designator_pattern(state),
+            ) {
                 Ok(table) => Some(table),
                 Err(err) => {
-                    println!(
+                    warn!(
                         "[load_file][preview_error] side={}, path={}, err={}",
                         side_normalized, file_path, err
                     );
@@ -357,52 +984,209 @@ async fn load_file(
                 }
             };
 
-            println!("[load_file] side={}, path={}", side_normalized, file_path);
+            info!("[load_file] side={}, path={}", side_normalized, file_path);
             if side_normalized == "a" {
                 *state.bom_a.lock().unwrap() = Some(bom_data.clone());
                 *state.file_a_path.lock().unwrap() = Some(file_path.clone());
                 *state.column_mapping_a.lock().unwrap() = Some(column_mapping.clone());
+                *state.column_mapping_by_name_a.lock().unwrap() = mapping_by_name;
+                *state.corrections_a.lock().unwrap() = corrections;
+                *state.operations_a.lock().unwrap() = vec![load_operation];
             } else {
                 *state.bom_b.lock().unwrap() = Some(bom_data.clone());
                 *state.file_b_path.lock().unwrap() = Some(file_path.clone());
                 *state.column_mapping_b.lock().unwrap() = Some(column_mapping.clone());
+                *state.column_mapping_by_name_b.lock().unwrap() = mapping_by_name;
+                *state.corrections_b.lock().unwrap() = corrections;
+                *state.operations_b.lock().unwrap() = vec![load_operation];
             }
 
             *state.comparison_result.lock().unwrap() = None;
             *state.synthesis_result.lock().unwrap() = None;
+            *state.consolidated_bom.lock().unwrap() = None;
+            *state.changed_bom.lock().unwrap() = None;
 
-            save_auto_session(&state)?;
+            save_auto_session(state)?;
 
             Ok(LoadFileResponse {
-                message: format!("部品表{}を読み込みました", side_normalized.to_uppercase()),
+                message: tr_side(state, Message::BomLoaded, &side_normalized),
                 side: side_normalized,
                 preview,
+                sheet_name,
+                warnings,
             })
         }
         Err(e) => {
-            println!(
+            error!(
                 "[load_file][error] side={}, path={}, err={}",
                 side_normalized, file_path, e
             );
-            Err(format!("ファイル読み込みエラー: {}", e))
+            Err(AppError::from(e))
+        }
+    }
+}
+
+#[tauri::command]
+async fn load_folder(
+    dir_path: String,
+    mapping: ColumnMapping,
+    state: State<'_, AppState>,
+) -> Result<LoadFolderResponse, AppError> {
+    let dir = Path::new(&dir_path);
+    if !dir.is_dir() {
+        return Err(AppError::FileNotFound(
+            "フォルダが見つかりません".to_string(),
+        ));
+    }
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| AppError::FileRead(format!("フォルダの読み込みに失敗しました: {e}")))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(|ext| {
+                        bom_processor::SUPPORTED_BOM_EXTENSIONS
+                            .contains(&ext.to_lowercase().as_str())
+                    })
+                    .unwrap_or(false)
+        })
+        .collect();
+    paths.sort();
+    let max_file_size = max_file_size(&state);
+
+    let mut loaded = Vec::new();
+    let mut failed = Vec::new();
+    let mut boms = HashMap::new();
+
+    for path in paths {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let path_str = path.to_string_lossy().to_string();
+        match bom_processor::load_bom_file(&path_str, &mapping, max_file_size, None).await {
+            Ok(load_result) => {
+                loaded.push(LoadedFileSummary {
+                    name: name.clone(),
+                    rows: load_result.bom.rows.len(),
+                });
+                boms.insert(name, load_result.bom);
+            }
+            Err(e) => {
+                error!("[load_folder][error] path={}, err={}", path_str, e);
+                failed.push(LoadFailure {
+                    name,
+                    error: e.to_string(),
+                });
+            }
         }
     }
+
+    state.loaded_boms.lock().unwrap().extend(boms);
+
+    Ok(LoadFolderResponse {
+        message: format!("{}件のファイルを読み込みました", loaded.len()),
+        loaded,
+        failed,
+    })
+}
+
+#[tauri::command]
+async fn compare_loaded(
+    name_a: String,
+    name_b: String,
+    state: State<'_, AppState>,
+) -> Result<CompareResponse, AppError> {
+    let (a, b) = {
+        let boms = state.loaded_boms.lock().unwrap();
+        let a = boms
+            .get(&name_a)
+            .cloned()
+            .ok_or_else(|| AppError::NoData(format!("{name_a} は読み込まれていません")))?;
+        let b = boms
+            .get(&name_b)
+            .cloned()
+            .ok_or_else(|| AppError::NoData(format!("{name_b} は読み込まれていません")))?;
+        (a, b)
+    };
+    let options = compare_options_from_settings(&state);
+    Ok(build_compare_response(&a, &b, &options))
+}
+
+#[tauri::command]
+async fn build_coverage_matrix(
+    offset: Option<usize>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<CoverageMatrixResponse, AppError> {
+    let boms = state.loaded_boms.lock().unwrap().clone();
+    if boms.is_empty() {
+        return Err(AppError::NoData(
+            "フォルダから読み込まれた部品表がありません".to_string(),
+        ));
+    }
+
+    let mut files: Vec<String> = boms.keys().cloned().collect();
+    files.sort();
+    let rows = synthesis::build_coverage_matrix(&boms);
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(COVERAGE_PAGE_LIMIT).max(1);
+
+    Ok(CoverageMatrixResponse {
+        files,
+        total_parts: rows.len(),
+        offset,
+        rows: rows.into_iter().skip(offset).take(limit).collect(),
+    })
 }
 
 #[tauri::command]
 async fn analyze_file(
     file_path: String,
+    has_headers: Option<bool>,
+    heuristic_only: Option<bool>,
+    sheet: Option<bom_processor::SheetSelector>,
     state: State<'_, AppState>,
-) -> Result<AnalyzeFileResponse, String> {
-    let dictionary = state.column_dictionary.lock().unwrap().clone();
-    let analysis = bom_processor::analyze_bom_file(&file_path, &dictionary)
-        .await
-        .map_err(|e| format!("ファイル解析エラー: {e}"))?;
+) -> Result<AnalyzeFileResponse, AppError> {
+    let dictionary = Arc::clone(&state.compiled_dictionary.lock().unwrap());
+    let analysis = bom_processor::analyze_bom_file(
+        &file_path,
+        &dictionary,
+        has_headers.unwrap_or(true),
+        heuristic_only.unwrap_or(false),
+        max_file_size(&state),
+        sheet.as_ref(),
+    )
+    .await
+    .map_err(AppError::from)?;
+
+    // 同じパス・同じヘッダーのファイルは辞書による推定より前回の列指定を優先する
+    let remembered = state
+        .remembered_mappings
+        .lock()
+        .unwrap()
+        .get(&normalize_mapping_path(&file_path))
+        .filter(|entry| entry.headers == analysis.headers)
+        .map(|entry| entry.mapping.clone());
+    let mapping_remembered = remembered.is_some();
+    if mapping_remembered {
+        info!("[analyze_file][remembered_mapping] path={}", file_path);
+    }
 
     Ok(AnalyzeFileResponse {
         headers: analysis.headers,
-        suggested_mapping: analysis.suggested_mapping,
+        suggested_mapping: remembered.or(analysis.suggested_mapping),
         sample_rows: analysis.sample_rows,
+        header_likely_missing: analysis.header_likely_missing,
+        column_samples: analysis.column_samples,
+        warnings: analysis.warnings,
+        metadata: analysis.metadata,
+        mapping_remembered,
+        sheet_names: analysis.sheet_names,
+        sheet_name: analysis.sheet_name,
     })
 }
 
@@ -410,97 +1194,530 @@ async fn analyze_file(
 async fn preview_file(
     file_path: String,
     limit: Option<usize>,
-) -> Result<bom_processor::FilePreview, String> {
+    state: State<'_, AppState>,
+) -> Result<bom_processor::FilePreview, AppError> {
     let row_limit = limit.unwrap_or(200);
-    bom_processor::preview_raw_file(&file_path, row_limit)
+    bom_processor::preview_raw_file(&file_path, row_limit, max_file_size(&state))
         .await
-        .map_err(|e| format!("プレビュー取得エラー: {e}"))
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn preview_with_normalization(
+    file_path: String,
+    mapping: ColumnMapping,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<bom_processor::NormalizedPreview, AppError> {
+    let row_limit = limit.unwrap_or(200);
+    bom_processor::preview_with_normalization(
+        &file_path,
+        &mapping,
+        row_limit,
+        max_file_size(&state),
+    )
+    .await
+    .map_err(AppError::from)
 }
 
 // 比較実行コマンド
-fn fetch_boms(state: &State<'_, AppState>) -> Result<(BomData, BomData), String> {
+fn fetch_boms(state: &State<'_, AppState>) -> Result<(BomData, BomData), AppError> {
     let bom_a = state
         .bom_a
         .lock()
-        .map_err(|_| "部品表Aのロックに失敗しました".to_string())?
+        .map_err(|_| AppError::Internal("部品表Aのロックに失敗しました".to_string()))?
         .clone();
     let bom_b = state
         .bom_b
         .lock()
-        .map_err(|_| "部品表Bのロックに失敗しました".to_string())?
+        .map_err(|_| AppError::Internal("部品表Bのロックに失敗しました".to_string()))?
         .clone();
 
     match (bom_a, bom_b) {
         (Some(a), Some(b)) => Ok((a, b)),
-        _ => Err("部品表AまたはBが読み込まれていません".to_string()),
+        _ => Err(AppError::NoData(tr(state, Message::BomsNotLoaded))),
     }
 }
 
-fn get_bom_from_state(state: &State<'_, AppState>, side: &str) -> Result<Option<BomData>, String> {
+fn get_bom_from_state(
+    state: &State<'_, AppState>,
+    side: &str,
+) -> Result<Option<BomData>, AppError> {
     match side {
         "a" => state
             .bom_a
             .lock()
-            .map_err(|_| "部品表Aのロックに失敗しました".to_string())
+            .map_err(|_| AppError::Internal("部品表Aのロックに失敗しました".to_string()))
             .map(|guard| guard.clone()),
         "b" => state
             .bom_b
             .lock()
-            .map_err(|_| "部品表Bのロックに失敗しました".to_string())
+            .map_err(|_| AppError::Internal("部品表Bのロックに失敗しました".to_string()))
             .map(|guard| guard.clone()),
-        _ => Err("サイド指定が無効です".to_string()),
+        _ => Err(AppError::InvalidInput(tr(state, Message::InvalidSide))),
     }
 }
 
-#[tauri::command]
-async fn compare_boms(state: State<'_, AppState>) -> Result<ComparisonResult, String> {
-    let (a, b) = fetch_boms(&state)?;
-    let result = perform_comparison(&a, &b);
-    *state.comparison_result.lock().unwrap() = Some(result.clone());
-    Ok(result)
+/// 読み込むファイルサイズの上限（バイト）
+fn max_file_size(state: &AppState) -> u64 {
+    let megabytes = state
+        .settings
+        .lock()
+        .unwrap()
+        .max_file_size_mb
+        .unwrap_or(bom_processor::DEFAULT_MAX_FILE_SIZE_MB);
+    megabytes.saturating_mul(1024 * 1024)
 }
 
-#[tauri::command]
-async fn compare_with_comments(state: State<'_, AppState>) -> Result<CompareResponse, String> {
-    let (a, b) = fetch_boms(&state)?;
-    let result = perform_comparison(&a, &b);
-    let stats = get_comparison_stats(&result);
-    *state.comparison_result.lock().unwrap() = Some(result.clone());
-    Ok(CompareResponse { result, stats })
+fn tr(state: &AppState, message: Message) -> String {
+    let language = state.settings.lock().unwrap().language.clone();
+    i18n::text(&language, message).to_string()
 }
 
-// 合成実行コマンド
-#[tauri::command]
-async fn synthesize_boms(state: State<'_, AppState>) -> Result<SynthesisResult, String> {
-    let (bom_a, bom_b) = {
-        let bom_a_guard = state.bom_a.lock().unwrap();
-        let bom_b_guard = state.bom_b.lock().unwrap();
-        (bom_a_guard.clone(), bom_b_guard.clone())
-    };
+fn tr_side(state: &AppState, message: Message, side: &str) -> String {
+    let language = state.settings.lock().unwrap().language.clone();
+    i18n::text_with_side(&language, message, side)
+}
 
-    match (bom_a, bom_b) {
-        (Some(a), Some(b)) => {
-            let result = perform_synthesis(&a, &b);
-            *state.synthesis_result.lock().unwrap() = Some(result.clone());
-            Ok(result)
+fn compare_options_from_settings(state: &AppState) -> CompareOptions {
+    let settings = state.settings.lock().unwrap();
+    CompareOptions {
+        exclude_patterns: settings.exclude_patterns.clone(),
+        compare_attributes: settings.compare_attributes.clone(),
+        model_normalize: settings.model_normalize,
+        detect_renames: settings.detect_renames,
+        ignore_models: settings.ignore_models,
+        value_equivalence: settings.value_equivalence,
+    }
+}
+
+// 比較コマンドに渡されたオプションを使い、次回以降の既定として設定に保存する。
+// 未指定の場合は設定に保存されている前回のオプションを使う
+fn resolve_compare_options(state: &AppState, options: Option<CompareOptions>) -> CompareOptions {
+    match options {
+        Some(mut options) => {
+            options.exclude_patterns = normalize_exclude_patterns(options.exclude_patterns);
+            store_compare_options(state, &options);
+            options
+        }
+        None => compare_options_from_settings(state),
+    }
+}
+
+// 設定画面から保存した場合と同じく、除外パターンを正規化してから保存する
+fn store_compare_options(state: &AppState, options: &CompareOptions) {
+    let exclude_patterns = normalize_exclude_patterns(options.exclude_patterns.clone());
+    let mut settings = state.settings.lock().unwrap();
+    let changed = settings.exclude_patterns != exclude_patterns
+        || settings.compare_attributes != options.compare_attributes
+        || settings.model_normalize != options.model_normalize
+        || settings.detect_renames != options.detect_renames
+        || settings.ignore_models != options.ignore_models
+        || settings.value_equivalence != options.value_equivalence;
+    if changed {
+        settings.exclude_patterns = exclude_patterns;
+        settings.compare_attributes = options.compare_attributes.clone();
+        settings.model_normalize = options.model_normalize;
+        settings.detect_renames = options.detect_renames;
+        settings.ignore_models = options.ignore_models;
+        settings.value_equivalence = options.value_equivalence;
+        if let Err(err) = write_settings_to_disk(&settings) {
+            error!("[compare][settings_error] {err}");
         }
-        _ => Err("部品表AまたはBが読み込まれていません".to_string()),
     }
 }
 
+// 比較結果と、その結果を作成した比較オプションを合わせて記録する
+fn store_comparison_result(state: &AppState, result: ComparisonResult, options: &CompareOptions) {
+    *state.comparison_result.lock().unwrap() = Some(result);
+    *state.comparison_options.lock().unwrap() = Some(options.clone());
+}
+
 #[tauri::command]
-async fn preprocess_bom(
-    request: PreprocessRequest,
+async fn compare_boms(
+    options: Option<CompareOptions>,
     state: State<'_, AppState>,
-) -> Result<PreprocessResponse, String> {
-    let side = request.side.as_ref().map(|s| s.to_lowercase());
-    let persist = request.persist.unwrap_or(side.is_some());
+) -> Result<ComparisonResult, AppError> {
+    let (a, b) = fetch_boms(&state)?;
+    let options = resolve_compare_options(&state, options);
+    let result = perform_comparison(&a, &b, &options);
+    store_comparison_result(&state, result.clone(), &options);
+    autosave_after_result(&state);
+    Ok(result)
+}
 
-    if persist && side.is_none() {
-        return Err("前処理結果を保存する場合は対象サイドを指定してください".to_string());
-    }
+#[tauri::command]
+async fn compare_with_comments(
+    options: Option<CompareOptions>,
+    state: State<'_, AppState>,
+) -> Result<CompareResponse, AppError> {
+    let (a, b) = fetch_boms(&state)?;
+    let options = resolve_compare_options(&state, options);
+    let response = build_compare_response(&a, &b, &options);
+    store_comparison_result(&state, response.result.clone(), &options);
+    autosave_after_result(&state);
+    Ok(response)
+}
+
+// 読み込み→比較→保存を一度に実行する（自動化用）。
+// 画面の状態（読み込み済みの部品表や比較結果）は変更しない。失敗した工程名をエラーに付けて返す
+#[tauri::command]
+async fn run_pipeline(
+    request: PipelineRequest,
+    state: State<'_, AppState>,
+) -> Result<PipelineResponse, AppError> {
+    let PipelineRequest {
+        path_a,
+        mapping_a,
+        path_b,
+        mapping_b,
+        output_path,
+        format,
+        options,
+    } = request;
+    // 読み込みと比較を終えてから形式の誤りに気付かないよう、先に確認する
+    if !COMPARISON_OUTPUT_FORMATS.contains(&format.as_str()) {
+        return Err(AppError::UnsupportedFormat(
+            "サポートされていないフォーマットです".to_string(),
+        )
+        .with_context("保存"));
+    }
+    let max_size = max_file_size(&state);
+    let a = bom_processor::load_bom_file(&path_a, &mapping_a, max_size, None)
+        .await
+        .map_err(|e| AppError::from(e).with_context("読み込み(A)"))?
+        .bom;
+    let b = bom_processor::load_bom_file(&path_b, &mapping_b, max_size, None)
+        .await
+        .map_err(|e| AppError::from(e).with_context("読み込み(B)"))?
+        .bom;
+
+    let options = options.unwrap_or_else(|| compare_options_from_settings(&state));
+    let result = perform_comparison(&a, &b, &options);
+    let stats = get_comparison_stats(&result);
+
+    save_comparison_result(&result, &output_path, &format, false, None)
+        .await
+        .map_err(|e| e.with_context("保存"))?;
+    info!(
+        "[run_pipeline] a={}, b={}, output={}, format={}",
+        path_a, path_b, output_path, format
+    );
+    Ok(PipelineResponse { output_path, stats })
+}
+
+// 比較結果を保持せずに1行ずつCSVへ書き出す（大きな部品表向け。比較結果の状態は変更しない）
+// 部品番号変更の検出は行わない。書き出した行数を返す
+#[tauri::command]
+async fn export_comparison_stream(
+    file_path: String,
+    state: State<'_, AppState>,
+) -> Result<usize, AppError> {
+    let (a, b) = fetch_boms(&state)?;
+    let options = compare_options_from_settings(&state);
+    let file = fs::File::create(&file_path)
+        .map_err(|e| AppError::write_failed("ファイルの作成に失敗しました", e))?;
+    let mut writer = BufWriter::new(file);
+    // 既定のCSV出力（utf-8-bom）と同じくBOMを付ける
+    writer
+        .write_all("\u{feff}".as_bytes())
+        .map_err(|e| AppError::write_failed("CSV書き込みエラー", e))?;
+    let count = write_comparison_rows_csv(compare_rows(&a, &b, &options), writer)?;
+    info!(
+        "[export_comparison_stream] path={}, rows={}",
+        file_path, count
+    );
+    Ok(count)
+}
+
+fn build_compare_response(a: &BomData, b: &BomData, options: &CompareOptions) -> CompareResponse {
+    let result = perform_comparison(a, b, options);
+    let mut stats = get_comparison_stats(&result);
+    let reconciled = reconcile_stats(&mut stats, a, b, options);
+    let reorder_only = is_reorder_only(&stats, a, b, options);
+    let suggestion = normalization_suggestion(a, b, options);
+    let mut warnings = comparison_warnings(&result);
+    if !reconciled {
+        warnings.push(reconciliation_warning(&stats));
+    }
+    CompareResponse {
+        result,
+        stats,
+        reconciled,
+        reorder_only,
+        suggestion,
+        warnings,
+    }
+}
+
+// 読み込み済みの部品表（編集・前処理後）を元ファイルと比較する（状態は変更しない）
+// 元ファイルを保存済みの列指定で読み直したものをA、現在の部品表をBとして比較する
+#[tauri::command]
+async fn diff_against_source(
+    side: String,
+    state: State<'_, AppState>,
+) -> Result<ComparisonResult, AppError> {
+    let side_key = side.to_lowercase();
+    let (path_slot, mapping_slot) = match side_key.as_str() {
+        "a" => (&state.file_a_path, &state.column_mapping_a),
+        "b" => (&state.file_b_path, &state.column_mapping_b),
+        _ => return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide))),
+    };
+    let current = get_bom_from_state(&state, &side_key)?
+        .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, &side_key)))?;
+    let file_path =
+        path_slot.lock().unwrap().clone().ok_or_else(|| {
+            AppError::NoData("読み込み元のファイルが記録されていません".to_string())
+        })?;
+    let mapping = mapping_slot
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| AppError::NoData("列指定が記録されていません".to_string()))?;
+    if !Path::new(&file_path).is_file() {
+        return Err(AppError::FileNotFound(format!(
+            "読み込み元のファイルが見つかりません: {}",
+            file_path
+        )));
+    }
+
+    let dictionary = Arc::clone(&state.compiled_dictionary.lock().unwrap());
+    let max_file_size = max_file_size(&state);
+    let sheet = loaded_sheet(&state, &side_key);
+    let analysis = bom_processor::analyze_bom_file(
+        &file_path,
+        &dictionary,
+        mapping.has_headers,
+        false,
+        max_file_size,
+        sheet.as_ref(),
+    )
+    .await?;
+    let source_mapping =
+        bom_processor::remap_to_source_columns(&mapping, &current.headers, &analysis.headers)?;
+    let source =
+        bom_processor::load_bom_file(&file_path, &source_mapping, max_file_size, sheet.as_ref())
+            .await?
+            .bom;
+
+    let options = compare_options_from_settings(&state);
+    info!(
+        "[diff_against_source] side={}, path={}",
+        side_key, file_path
+    );
+    Ok(perform_comparison(&source, &current, &options))
+}
+
+// 未保存の編集中データで一方の部品表を置き換えて比較する（状態は変更しない）
+#[tauri::command]
+async fn compare_snapshot(
+    side_to_replace: String,
+    snapshot: BomSnapshot,
+    state: State<'_, AppState>,
+) -> Result<CompareResponse, AppError> {
+    let side_key = side_to_replace.to_lowercase();
+    snapshot
+        .check_schema_version()
+        .map_err(AppError::InvalidInput)?;
+    let replacement = BomData::from(snapshot);
+    let (a, b) = match side_key.as_str() {
+        "a" => {
+            let b = get_bom_from_state(&state, "b")?
+                .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, "b")))?;
+            (replacement, b)
+        }
+        "b" => {
+            let a = get_bom_from_state(&state, "a")?
+                .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, "a")))?;
+            (a, replacement)
+        }
+        _ => return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide))),
+    };
+    let options = compare_options_from_settings(&state);
+    Ok(build_compare_response(&a, &b, &options))
+}
+
+#[tauri::command]
+async fn compare_stats_only(
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, usize>, AppError> {
+    let (a, b) = fetch_boms(&state)?;
+    let options = compare_options_from_settings(&state);
+    let result = perform_comparison(&a, &b, &options);
+    let mut stats = get_comparison_stats(&result);
+    if !reconcile_stats(&mut stats, &a, &b, &options) {
+        warn!(
+            "[compare_stats_only][warn] {}",
+            reconciliation_warning(&stats)
+        );
+    }
+    store_comparison_result(&state, result, &options);
+    autosave_after_result(&state);
+    Ok(stats)
+}
+
+#[tauri::command]
+async fn get_comparison_page(
+    category: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<ComparisonPage, AppError> {
+    let guard = state.comparison_result.lock().unwrap();
+    let result = guard
+        .as_ref()
+        .ok_or_else(|| AppError::NoData(tr(&state, Message::NoComparisonResult)))?;
+
+    let rows = match category.to_lowercase().as_str() {
+        "common" => &result.common_parts,
+        "a_only" => &result.a_only_parts,
+        "b_only" => &result.b_only_parts,
+        "modified" => &result.modified_parts,
+        "renamed" => &result.renamed_parts,
+        _ => return Err(AppError::InvalidInput("無効な比較カテゴリです".to_string())),
+    };
+
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(COMPARISON_PAGE_LIMIT).max(1);
+    Ok(ComparisonPage {
+        total: rows.len(),
+        offset,
+        rows: rows.iter().skip(offset).take(limit).cloned().collect(),
+    })
+}
+
+// 合成実行コマンド
+#[tauri::command]
+async fn synthesize_boms(
+    strategy: Option<PreferenceStrategy>,
+    state: State<'_, AppState>,
+) -> Result<SynthesisResult, AppError> {
+    let (bom_a, bom_b) = {
+        let bom_a_guard = state.bom_a.lock().unwrap();
+        let bom_b_guard = state.bom_b.lock().unwrap();
+        (bom_a_guard.clone(), bom_b_guard.clone())
+    };
+
+    match (bom_a, bom_b) {
+        (Some(a), Some(b)) => {
+            let registered_list = state.registered_name_list.lock().unwrap().clone();
+            let result = perform_synthesis(&a, &b, strategy.unwrap_or_default(), &registered_list);
+            *state.synthesis_result.lock().unwrap() = Some(result.clone());
+            *state.consolidated_bom.lock().unwrap() = None;
+            autosave_after_result(&state);
+            Ok(result)
+        }
+        _ => Err(AppError::NoData(tr(&state, Message::BomsNotLoaded))),
+    }
+}
+
+// 合成結果から統合部品表（1部品1型番＋由来列）を作成して保持し、file_path 指定時はCSVにも書き出す
+#[tauri::command]
+async fn build_consolidated_bom(
+    file_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<BomData, AppError> {
+    let synthesis = state
+        .synthesis_result
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| AppError::NoData(tr(&state, Message::NoSynthesisResult)))?;
+    let bom = synthesis::build_consolidated_bom(&synthesis);
+
+    if let Some(path) = file_path.as_deref() {
+        save_bom_csv(&bom, path).await?;
+    }
+
+    *state.consolidated_bom.lock().unwrap() = Some(bom.clone());
+    Ok(bom)
+}
+
+/// 部品表をヘッダー行付きのCSVとして保存する（各行は属性の値をヘッダー順に並べる）
+async fn save_bom_csv(bom: &BomData, path: &str) -> Result<(), AppError> {
+    let data: Vec<Vec<String>> = std::iter::once(bom.headers.clone())
+        .chain(bom.rows.iter().map(|row| {
+            bom.headers
+                .iter()
+                .map(|header| row.attributes.get(header).cloned().unwrap_or_default())
+                .collect()
+        }))
+        .collect();
+    file_handler::save_csv_file(&data, path, file_handler::DEFAULT_CSV_ENCODING)
+        .await
+        .map_err(|e| AppError::write_failed("CSV保存エラー", e))?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct ExtractChangedBomResponse {
+    bom: BomData,
+    side: String,
+    /// 抜き出した部品の数（同じ部品番号の行は1件と数える）
+    part_count: usize,
+    row_count: usize,
+}
+
+// 比較結果で追加・変更された部品の行だけを指定サイドの部品表から抜き出す（部分発注用）
+#[tauri::command]
+async fn extract_changed_bom(
+    from_side: String,
+    file_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ExtractChangedBomResponse, AppError> {
+    let side = from_side.to_lowercase();
+    let bom = get_bom_from_state(&state, &side)?
+        .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, &side)))?;
+    let result = state
+        .comparison_result
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| AppError::NoData(tr(&state, Message::NoComparisonResult)))?;
+
+    let changed = comparison::extract_changed_bom(&bom, &result);
+    if let Some(path) = file_path.as_deref() {
+        save_bom_csv(&changed, path).await?;
+    }
+    let part_count = changed
+        .rows
+        .iter()
+        .map(|row| row.part_number.as_str())
+        .collect::<HashSet<_>>()
+        .len();
+    let row_count = changed.rows.len();
+    info!(
+        "[extract_changed_bom] side={}, parts={}, rows={}",
+        side, part_count, row_count
+    );
+
+    *state.changed_bom.lock().unwrap() = Some(changed.clone());
+    Ok(ExtractChangedBomResponse {
+        bom: changed,
+        side,
+        part_count,
+        row_count,
+    })
+}
+
+#[tauri::command]
+async fn preprocess_bom(
+    request: PreprocessRequest,
+    state: State<'_, AppState>,
+) -> Result<PreprocessResponse, AppError> {
+    let side = request.side.as_ref().map(|s| s.to_lowercase());
+    let persist = request.persist.unwrap_or(side.is_some());
+
+    if persist && side.is_none() {
+        return Err(AppError::InvalidInput(
+            "前処理結果を保存する場合は対象サイドを指定してください".to_string(),
+        ));
+    }
 
     let maybe_bom = if let Some(snapshot) = request.bom_data {
+        snapshot
+            .check_schema_version()
+            .map_err(AppError::InvalidInput)?;
         Some(BomData::from(snapshot))
     } else if let Some(ref side_key) = side {
         get_bom_from_state(&state, side_key)?
@@ -508,10 +1725,17 @@ async fn preprocess_bom(
         None
     };
 
-    let source_bom = maybe_bom.ok_or_else(|| "前処理対象の部品表がありません".to_string())?;
+    let source_bom =
+        maybe_bom.ok_or_else(|| AppError::NoData("前処理対象の部品表がありません".to_string()))?;
 
-    let processed_bom = bom_processor::preprocess_bom_data(&source_bom, &request.rules)
-        .map_err(|e| format!("前処理エラー: {e}"))?;
+    let mut rules = request.rules;
+    if rules.designator_pattern.is_none() {
+        rules.designator_pattern = range_designator_pattern(&state);
+    }
+
+    let processed =
+        bom_processor::preprocess_bom_data(&source_bom, &rules).map_err(AppError::from)?;
+    let processed_bom = processed.bom;
 
     if persist {
         if let Some(ref side_key) = side {
@@ -522,8 +1746,9 @@ async fn preprocess_bom(
                 "b" => {
                     *state.bom_b.lock().unwrap() = Some(processed_bom.clone());
                 }
-                _ => return Err("サイド指定が無効です".to_string()),
+                _ => return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide))),
             }
+            record_operation(&state, side_key, BomOperation::Preprocess { rules });
             *state.comparison_result.lock().unwrap() = None;
             save_auto_session(&state)?;
         }
@@ -531,6 +1756,7 @@ async fn preprocess_bom(
 
     Ok(PreprocessResponse {
         bom_data: BomSnapshot::from(processed_bom),
+        warnings: processed.warnings,
     })
 }
 
@@ -539,51 +1765,562 @@ async fn update_bom_data(
     side: String,
     bom_data: BomSnapshot,
     state: State<'_, AppState>,
-) -> Result<MessageResponse, String> {
+) -> Result<MessageResponse, AppError> {
     let side_key = side.to_lowercase();
+    bom_data
+        .check_schema_version()
+        .map_err(AppError::InvalidInput)?;
     let bom: BomData = bom_data.into();
 
+    // 置き換えた部品表には読み込み時の自動修正の記録が当てはまらないため破棄する
     match side_key.as_str() {
         "a" => {
             *state.bom_a.lock().unwrap() = Some(bom);
+            state.corrections_a.lock().unwrap().clear();
         }
         "b" => {
             *state.bom_b.lock().unwrap() = Some(bom);
+            state.corrections_b.lock().unwrap().clear();
         }
-        _ => return Err("サイド指定が無効です".to_string()),
+        _ => return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide))),
+    }
+    record_operation(&state, &side_key, BomOperation::ManualEdit);
+
+    *state.comparison_result.lock().unwrap() = None;
+    save_auto_session(&state)?;
+
+    Ok(MessageResponse {
+        message: tr_side(&state, Message::BomUpdated, &side_key),
+    })
+}
+
+// 1セルだけを編集する。部品番号以外の編集では比較結果の該当部品のみを再計算する
+// column: "model_number" / "quantity" / 属性列名
+#[tauri::command]
+async fn update_cell(
+    side: String,
+    part_number: String,
+    column: String,
+    value: String,
+    state: State<'_, AppState>,
+) -> Result<UpdateCellResponse, AppError> {
+    let side_key = side.to_lowercase();
+    let (bom_slot, mapping_slot) = match side_key.as_str() {
+        "a" => (&state.bom_a, &state.column_mapping_a),
+        "b" => (&state.bom_b, &state.column_mapping_b),
+        _ => return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide))),
+    };
+    let mapping = mapping_slot.lock().unwrap().clone();
+
+    let key_changed = {
+        let mut guard = bom_slot.lock().unwrap();
+        let bom = guard
+            .as_mut()
+            .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, &side_key)))?;
+        let mapped_header = |idx: Option<usize>| idx.and_then(|i| bom.headers.get(i).cloned());
+        let part_header = mapped_header(mapping.as_ref().map(|m| m.part_number));
+        let model_header = mapped_header(mapping.as_ref().map(|m| m.model_number));
+        let quantity_header = mapped_header(mapping.as_ref().and_then(|m| m.quantity));
+
+        let is_model = column == "model_number" || model_header.as_deref() == Some(&column);
+        let is_quantity = column == "quantity" || quantity_header.as_deref() == Some(&column);
+        let is_part = column == "part_number" || part_header.as_deref() == Some(&column);
+        if !is_model && !is_quantity && !is_part && !bom.headers.contains(&column) {
+            return Err(AppError::Column(format!(
+                "列「{}」が見つかりません",
+                column
+            )));
+        }
+
+        let mut matched = bom
+            .rows
+            .iter_mut()
+            .filter(|row| row.part_number == part_number);
+        let row = match (matched.next(), matched.next()) {
+            (Some(row), None) => row,
+            (None, _) => {
+                return Err(AppError::NoData(format!(
+                    "部品番号 '{}' が見つかりません",
+                    part_number
+                )))
+            }
+            (Some(_), Some(_)) => {
+                return Err(AppError::InvalidInput(format!(
+                    "部品番号 '{}' が重複しているため編集できません",
+                    part_number
+                )))
+            }
+        };
+
+        if is_part {
+            row.part_number = value.clone();
+        } else if is_model {
+            row.model_number = value.clone();
+        } else if is_quantity {
+            row.quantity = bom_processor::parse_quantity(&value);
+        }
+        let header = match (is_part, is_model, is_quantity) {
+            (true, _, _) => part_header,
+            (_, true, _) => model_header,
+            (_, _, true) => quantity_header,
+            _ => Some(column.clone()),
+        };
+        if let Some(header) = header {
+            row.attributes.insert(header, value.clone());
+        }
+        is_part
+    };
+    record_operation(&state, &side_key, BomOperation::ManualEdit);
+
+    let mut comparison_patched = false;
+    if key_changed {
+        *state.comparison_result.lock().unwrap() = None;
+    } else {
+        let cached = state.comparison_result.lock().unwrap().take();
+        if let Some(mut result) = cached {
+            let (a, b) = fetch_boms(&state)?;
+            // 結果を作成したときと同じオプションで該当部品だけを比較し直す
+            let options = state
+                .comparison_options
+                .lock()
+                .unwrap()
+                .clone()
+                .unwrap_or_else(|| compare_options_from_settings(&state));
+            patch_comparison_part(&mut result, &a, &b, &part_number, &options);
+            *state.comparison_result.lock().unwrap() = Some(result);
+            comparison_patched = true;
+        }
+    }
+    save_auto_session(&state)?;
+
+    Ok(UpdateCellResponse {
+        message: tr_side(&state, Message::BomUpdated, &side_key),
+        comparison_patched,
+    })
+}
+
+// 読み込み済み部品表の概要（行数・重複・空欄・列ごとの充填率）を返す
+#[tauri::command]
+async fn bom_stats(
+    side: String,
+    state: State<'_, AppState>,
+) -> Result<bom_processor::BomStats, AppError> {
+    let side_key = side.to_lowercase();
+    let bom = get_bom_from_state(&state, &side_key)?
+        .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, &side_key)))?;
+    let manufacturer_header = mapped_manufacturer_header(&state, &side_key);
+    Ok(bom_processor::compute_bom_stats(
+        &bom,
+        manufacturer_header.as_deref(),
+    ))
+}
+
+// 読み込み時の自動修正の一覧をCSVに書き出す（監査用）
+#[tauri::command]
+async fn export_corrections_csv(
+    side: String,
+    file_path: String,
+    state: State<'_, AppState>,
+) -> Result<usize, AppError> {
+    let side_key = side.to_lowercase();
+    let corrections = match side_key.as_str() {
+        "a" => state.corrections_a.lock().unwrap().clone(),
+        "b" => state.corrections_b.lock().unwrap().clone(),
+        _ => return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide))),
+    };
+    if get_bom_from_state(&state, &side_key)?.is_none() {
+        return Err(AppError::NoData(tr_side(
+            &state,
+            Message::BomNotLoaded,
+            &side_key,
+        )));
+    }
+
+    let header = ["行番号", "列番号", "列名", "元の値", "修正後の値", "規則"];
+    let data: Vec<Vec<String>> = std::iter::once(header.iter().map(|h| h.to_string()).collect())
+        .chain(corrections.iter().map(|c| {
+            vec![
+                c.row_number.to_string(),
+                (c.column_index + 1).to_string(),
+                c.column_name.clone(),
+                c.original_value.clone(),
+                c.corrected_value.clone(),
+                c.rule.clone(),
+            ]
+        }))
+        .collect();
+    file_handler::save_quoted_csv_file(&data, &file_path)
+        .await
+        .map_err(|e| AppError::write_failed("CSV保存エラー", e))?;
+
+    info!(
+        "[export_corrections_csv] side={}, path={}, count={}",
+        side_key,
+        file_path,
+        corrections.len()
+    );
+    Ok(corrections.len())
+}
+
+// 部品表の内容のハッシュ（行の並び順に依存しない）を返す
+#[tauri::command]
+async fn bom_fingerprint(side: String, state: State<'_, AppState>) -> Result<String, AppError> {
+    let side_key = side.to_lowercase();
+    let bom = get_bom_from_state(&state, &side_key)?
+        .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, &side_key)))?;
+    Ok(bom_processor::bom_fingerprint(&bom))
+}
+
+// 指定列の値を重複なく自然順で書き出す（.csv は1列のCSV、それ以外は1行1件のテキスト）
+#[tauri::command]
+async fn export_unique_column(
+    side: String,
+    column_name: String,
+    file_path: String,
+    state: State<'_, AppState>,
+) -> Result<usize, AppError> {
+    let side_key = side.to_lowercase();
+    let bom = get_bom_from_state(&state, &side_key)?
+        .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, &side_key)))?;
+    let values = bom_processor::unique_column_values(&bom, &column_name)?;
+
+    let is_csv = Path::new(&file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("csv"))
+        .unwrap_or(false);
+    if is_csv {
+        let data: Vec<Vec<String>> = std::iter::once(vec![column_name.clone()])
+            .chain(values.iter().map(|value| vec![value.clone()]))
+            .collect();
+        file_handler::save_csv_file(&data, &file_path, file_handler::DEFAULT_CSV_ENCODING)
+            .await
+            .map_err(|e| AppError::write_failed("CSV保存エラー", e))?;
+    } else {
+        let mut content = values.join("\n");
+        content.push('\n');
+        file_handler::save_txt_file(&content, &file_path, file_handler::DEFAULT_TEXT_ENCODING)
+            .await
+            .map_err(|e| AppError::write_failed("TXT保存エラー", e))?;
+    }
+
+    info!(
+        "[export_unique_column] side={}, column={}, count={}",
+        side_key,
+        column_name,
+        values.len()
+    );
+    Ok(values.len())
+}
+
+// 条件式で部品表の行を絞り込む（例: manufacturer=="TDK" && qty>10）
+#[tauri::command]
+async fn filter_bom(
+    side: String,
+    expression: String,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<FilterBomResponse, AppError> {
+    let side_key = side.to_lowercase();
+    let bom = get_bom_from_state(&state, &side_key)?
+        .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, &side_key)))?;
+
+    // 列指定のメーカー列は "manufacturer" でも参照できる
+    let aliases: Vec<(&str, String)> = mapped_manufacturer_header(&state, &side_key)
+        .map(|header| ("manufacturer", header))
+        .into_iter()
+        .collect();
+    let expr = filter::parse_filter(&expression, &bom.headers, &aliases)
+        .map_err(AppError::InvalidInput)?;
+    let matched = filter::filter_rows(&bom, &expr);
+
+    Ok(FilterBomResponse {
+        total_count: matched.len(),
+        rows: matched
+            .into_iter()
+            .take(limit.unwrap_or(FILTER_ROW_LIMIT))
+            .cloned()
+            .collect(),
+    })
+}
+
+// 重複した部品番号を統合して部品番号順に並べ替える
+#[tauri::command]
+async fn optimize_bom(
+    side: String,
+    strategy: Option<bom_processor::MergeStrategy>,
+    state: State<'_, AppState>,
+) -> Result<bom_processor::OptimizeResult, AppError> {
+    let side_key = side.to_lowercase();
+    let bom_slot = match side_key.as_str() {
+        "a" => &state.bom_a,
+        "b" => &state.bom_b,
+        _ => return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide))),
+    };
+
+    let strategy = strategy.unwrap_or_default();
+    let result = {
+        let mut guard = bom_slot.lock().unwrap();
+        let bom = guard
+            .as_mut()
+            .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, &side_key)))?;
+        bom_processor::optimize_bom_data(bom, strategy)
+    };
+    record_operation(&state, &side_key, BomOperation::Optimize { strategy });
+
+    info!(
+        "[optimize_bom] side={}, before={}, after={}",
+        side_key, result.rows_before, result.rows_after
+    );
+    *state.comparison_result.lock().unwrap() = None;
+    save_auto_session(&state)?;
+
+    Ok(result)
+}
+
+// 2つの属性列を連結した列を追加する（drop_sources が true の場合は連結元の列を削除）
+#[tauri::command]
+async fn merge_columns(
+    side: String,
+    source_a: String,
+    source_b: String,
+    target: String,
+    separator: Option<String>,
+    drop_sources: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<MessageResponse, AppError> {
+    let side_key = side.to_lowercase();
+    let (bom_slot, mapping_slot) = match side_key.as_str() {
+        "a" => (&state.bom_a, &state.column_mapping_a),
+        "b" => (&state.bom_b, &state.column_mapping_b),
+        _ => return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide))),
+    };
+
+    let target = target.trim().to_string();
+    let separator = separator.unwrap_or_else(|| " ".to_string());
+    let drop_sources = drop_sources.unwrap_or(false);
+    {
+        let mut guard = bom_slot.lock().unwrap();
+        let bom = guard
+            .as_mut()
+            .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, &side_key)))?;
+        let mut mapping_guard = mapping_slot.lock().unwrap();
+        bom_processor::merge_columns(
+            bom,
+            &source_a,
+            &source_b,
+            &target,
+            &separator,
+            drop_sources,
+            mapping_guard.as_mut(),
+        )?;
+    }
+    record_operation(
+        &state,
+        &side_key,
+        BomOperation::MergeColumns {
+            source_a: source_a.clone(),
+            source_b: source_b.clone(),
+            target: target.clone(),
+            separator,
+            drop_sources,
+        },
+    );
+
+    *state.comparison_result.lock().unwrap() = None;
+    save_auto_session(&state)?;
+
+    Ok(MessageResponse {
+        message: format!(
+            "列「{}」と「{}」を連結して列「{}」を追加しました",
+            source_a, source_b, target
+        ),
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct SplitColumnResponse {
+    message: String,
+    /// 分割数が列名の数と異なった行
+    warnings: Vec<String>,
+}
+
+// 属性列を区切り文字で複数の列に分割する（remove_source が true の場合は分割元の列を削除）
+#[tauri::command]
+async fn split_column(
+    side: String,
+    source: String,
+    separator: String,
+    target_names: Vec<String>,
+    remove_source: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<SplitColumnResponse, AppError> {
+    let side_key = side.to_lowercase();
+    let (bom_slot, mapping_slot) = match side_key.as_str() {
+        "a" => (&state.bom_a, &state.column_mapping_a),
+        "b" => (&state.bom_b, &state.column_mapping_b),
+        _ => return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide))),
+    };
+
+    let target_names: Vec<String> = target_names
+        .iter()
+        .map(|name| name.trim().to_string())
+        .collect();
+    let remove_source = remove_source.unwrap_or(false);
+    let warnings = {
+        let mut guard = bom_slot.lock().unwrap();
+        let bom = guard
+            .as_mut()
+            .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, &side_key)))?;
+        let mut mapping_guard = mapping_slot.lock().unwrap();
+        bom_processor::split_column(
+            bom,
+            &source,
+            &separator,
+            &target_names,
+            remove_source,
+            mapping_guard.as_mut(),
+        )?
+    };
+    record_operation(
+        &state,
+        &side_key,
+        BomOperation::SplitColumn {
+            source: source.clone(),
+            separator,
+            target_names: target_names.clone(),
+            remove_source,
+        },
+    );
+
+    *state.comparison_result.lock().unwrap() = None;
+    save_auto_session(&state)?;
+
+    Ok(SplitColumnResponse {
+        message: format!("列「{}」を{}列に分割しました", source, target_names.len()),
+        warnings,
+    })
+}
+
+#[tauri::command]
+async fn rename_header(
+    side: String,
+    old_name: String,
+    new_name: String,
+    state: State<'_, AppState>,
+) -> Result<MessageResponse, AppError> {
+    let side_key = side.to_lowercase();
+    let (bom_slot, by_name_slot) = match side_key.as_str() {
+        "a" => (&state.bom_a, &state.column_mapping_by_name_a),
+        "b" => (&state.bom_b, &state.column_mapping_by_name_b),
+        _ => return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide))),
+    };
+
+    let new_name = new_name.trim().to_string();
+    {
+        let mut guard = bom_slot.lock().unwrap();
+        let bom = guard
+            .as_mut()
+            .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, &side_key)))?;
+        bom_processor::rename_header(bom, &old_name, &new_name)?;
     }
+    rename_mapped_column(by_name_slot.lock().unwrap().as_mut(), &old_name, &new_name);
+    record_operation(
+        &state,
+        &side_key,
+        BomOperation::RenameHeader {
+            old_name: old_name.clone(),
+            new_name: new_name.clone(),
+        },
+    );
 
     *state.comparison_result.lock().unwrap() = None;
     save_auto_session(&state)?;
 
     Ok(MessageResponse {
-        message: format!("部品表{}を更新しました", side_key.to_uppercase()),
+        message: format!("列名「{}」を「{}」に変更しました", old_name, new_name),
     })
 }
 
+// 列番号による列指定は列位置が変わらないため、列名による列指定のみ更新する
+fn rename_mapped_column(mapping: Option<&mut ColumnMappingByName>, old_name: &str, new_name: &str) {
+    if let Some(mapping) = mapping {
+        for name in [&mut mapping.part_number, &mut mapping.model_number]
+            .into_iter()
+            .chain(mapping.manufacturer.as_mut())
+        {
+            if name == old_name {
+                *name = new_name.to_string();
+            }
+        }
+    }
+}
+
+async fn read_registered_name_list(
+    file_path: &str,
+    format: &str,
+    state: &State<'_, AppState>,
+) -> Result<RegisteredNameList, AppError> {
+    let dictionary = state.column_dictionary.lock().unwrap().clone();
+    match format.to_lowercase().as_str() {
+        "csv" => bom_processor::load_registered_name_csv(file_path, &dictionary)
+            .await
+            .map_err(AppError::from),
+        "json" => bom_processor::load_registered_name_json(file_path)
+            .await
+            .map_err(AppError::from),
+        _ => Err(AppError::UnsupportedFormat(
+            "サポートされていないフォーマットです".to_string(),
+        )),
+    }
+}
+
 #[tauri::command(name = "load_registered_name_list")]
 async fn load_registered_name_list_cmd(
     file_path: String,
     format: String,
     state: State<'_, AppState>,
-) -> Result<RegisteredNameListResponse, String> {
-    let format_norm = format.to_lowercase();
-    let list = match format_norm.as_str() {
-        "csv" => bom_processor::load_registered_name_csv(&file_path)
-            .await
-            .map_err(|e| format!("CSV読み込みエラー: {e}"))?,
-        "json" => bom_processor::load_registered_name_json(&file_path)
-            .await
-            .map_err(|e| format!("JSON読み込みエラー: {e}"))?,
-        _ => return Err("サポートされていないフォーマットです".to_string()),
-    };
+) -> Result<RegisteredNameListResponse, AppError> {
+    let list = read_registered_name_list(&file_path, &format, &state).await?;
+    let (list, conflicts) = bom_processor::dedupe_registered_names(list);
 
     *state.registered_name_list.lock().unwrap() = Some(list.clone());
     save_auto_session(&state)?;
 
+    let message = if conflicts.is_empty() {
+        "登録名リストを読み込みました".to_string()
+    } else {
+        format!(
+            "登録名リストを読み込みました（登録名が競合する{}件は先頭の行を採用しました）",
+            conflicts.len()
+        )
+    };
+
     Ok(RegisteredNameListResponse {
         list,
-        message: "登録名リストを読み込みました".to_string(),
+        message,
+        conflicts,
+    })
+}
+
+// 取り込み前に登録名リストの内容と問題行を確認する（状態には保存しない）
+#[tauri::command]
+async fn preview_registered_name_list(
+    file_path: String,
+    format: String,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<RegisteredNameListPreviewResponse, AppError> {
+    let list = read_registered_name_list(&file_path, &format, &state).await?;
+    let errors = bom_processor::validate_registered_name_list(&list);
+    let total_count = list.entries.len();
+    let mut entries = list.entries;
+    entries.truncate(limit.unwrap_or(REGISTERED_NAME_PREVIEW_LIMIT));
+
+    Ok(RegisteredNameListPreviewResponse {
+        entries,
+        total_count,
+        errors,
     })
 }
 
@@ -592,27 +2329,31 @@ async fn save_registered_name_list_cmd(
     file_path: String,
     format: String,
     state: State<'_, AppState>,
-) -> Result<MessageResponse, String> {
+) -> Result<MessageResponse, AppError> {
     let list = state
         .registered_name_list
         .lock()
         .unwrap()
         .clone()
-        .ok_or_else(|| "登録名リストがありません".to_string())?;
+        .ok_or_else(|| AppError::NoData("登録名リストがありません".to_string()))?;
 
     let format_norm = format.to_lowercase();
     match format_norm.as_str() {
         "csv" => bom_processor::save_registered_name_csv(&list, &file_path)
             .await
-            .map_err(|e| format!("CSV保存エラー: {e}"))?,
+            .map_err(AppError::from)?,
         "json" => bom_processor::save_registered_name_json(&list, &file_path)
             .await
-            .map_err(|e| format!("JSON保存エラー: {e}"))?,
-        _ => return Err("サポートされていないフォーマットです".to_string()),
+            .map_err(AppError::from)?,
+        _ => {
+            return Err(AppError::UnsupportedFormat(
+                "サポートされていないフォーマットです".to_string(),
+            ))
+        }
     }
 
     Ok(MessageResponse {
-        message: "登録名リストを保存しました".to_string(),
+        message: tr(&state, Message::RegisteredNamesSaved),
     })
 }
 
@@ -620,41 +2361,346 @@ async fn save_registered_name_list_cmd(
 async fn apply_registered_names(
     side: String,
     state: State<'_, AppState>,
-) -> Result<MessageResponse, String> {
+) -> Result<MessageResponse, AppError> {
     let side_key = side.to_lowercase();
     let registered_list = state.registered_name_list.lock().unwrap().clone();
     let overrides = state.override_list.lock().unwrap().clone();
+    let manufacturer_header = mapped_manufacturer_header(&state, &side_key);
+    let policy = state.settings.lock().unwrap().registered_name_policy;
 
     match side_key.as_str() {
         "a" => {
             let mut bom_lock = state.bom_a.lock().unwrap();
             if let Some(ref mut bom) = *bom_lock {
-                bom_processor::apply_registered_names_to_bom(bom, &registered_list, &overrides);
+                bom_processor::apply_registered_names_to_bom(
+                    bom,
+                    &registered_list,
+                    &overrides,
+                    manufacturer_header.as_deref(),
+                    policy,
+                );
             } else {
-                return Err("部品表Aが読み込まれていません".to_string());
+                return Err(AppError::NoData(tr_side(
+                    &state,
+                    Message::BomNotLoaded,
+                    "a",
+                )));
             }
         }
         "b" => {
             let mut bom_lock = state.bom_b.lock().unwrap();
             if let Some(ref mut bom) = *bom_lock {
-                bom_processor::apply_registered_names_to_bom(bom, &registered_list, &overrides);
+                bom_processor::apply_registered_names_to_bom(
+                    bom,
+                    &registered_list,
+                    &overrides,
+                    manufacturer_header.as_deref(),
+                    policy,
+                );
             } else {
-                return Err("部品表Bが読み込まれていません".to_string());
+                return Err(AppError::NoData(tr_side(
+                    &state,
+                    Message::BomNotLoaded,
+                    "b",
+                )));
             }
         }
-        _ => return Err("サイド指定が無効です".to_string()),
+        _ => return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide))),
     }
 
+    record_operation(
+        &state,
+        &side_key,
+        BomOperation::ApplyRegisteredNames { policy },
+    );
     *state.comparison_result.lock().unwrap() = None;
     save_auto_session(&state)?;
 
     Ok(MessageResponse {
-        message: format!("部品表{}に登録名を適用しました", side_key.to_uppercase()),
+        message: tr_side(&state, Message::RegisteredNamesApplied, &side_key),
+    })
+}
+
+fn operations_for<'a>(state: &'a AppState, side: &str) -> Option<&'a Mutex<Vec<BomOperation>>> {
+    match side {
+        "a" => Some(&state.operations_a),
+        "b" => Some(&state.operations_b),
+        _ => None,
+    }
+}
+
+/// 現在の部品表を読み込んだワークシート（記録がない場合やExcel以外は None）
+fn loaded_sheet(state: &AppState, side: &str) -> Option<bom_processor::SheetSelector> {
+    let operations = operations_for(state, side)?.lock().unwrap();
+    match operations.first() {
+        Some(BomOperation::Load { sheet, .. }) => sheet.clone(),
+        _ => None,
+    }
+}
+
+fn record_operation(state: &AppState, side: &str, operation: BomOperation) {
+    if let Some(operations) = operations_for(state, side) {
+        operations.lock().unwrap().push(operation);
+    }
+}
+
+// セッションに記録された操作を、新しいファイルを読み込み直して順に再適用する
+// 列名で指定して読み込んでいた場合は、新しいファイルのヘッダーから列番号を求め直す
+#[tauri::command]
+async fn replay_session_operations(
+    side: String,
+    new_file_path: String,
+    state: State<'_, AppState>,
+) -> Result<ReplayOperationsResponse, AppError> {
+    replay_operations_on_file(&state, &side.to_lowercase(), &new_file_path).await
+}
+
+// 読み込みと操作の再適用はすべて手元の部品表に対して行い、全操作が成功してから状態に反映する
+// 途中で失敗した場合は、読み込み済みの部品表・列指定・記録された操作をそのまま残す
+async fn replay_operations_on_file(
+    state: &AppState,
+    side_key: &str,
+    new_file_path: &str,
+) -> Result<ReplayOperationsResponse, AppError> {
+    let operations = match operations_for(state, side_key) {
+        Some(operations) => operations.lock().unwrap().clone(),
+        None => return Err(AppError::InvalidInput(tr(state, Message::InvalidSide))),
+    };
+    let (column_mapping, mapping_by_name, sheet) = match operations.first() {
+        Some(BomOperation::Load {
+            column_mapping,
+            mapping_by_name,
+            sheet,
+        }) => (
+            (**column_mapping).clone(),
+            mapping_by_name.clone(),
+            sheet.clone(),
+        ),
+        _ => {
+            return Err(AppError::NoData(
+                "再適用できる読み込み操作が記録されていません".to_string(),
+            ))
+        }
+    };
+    let column_mapping = match mapping_by_name {
+        Some(ref by_name) => {
+            let dictionary = Arc::clone(&state.compiled_dictionary.lock().unwrap());
+            let analysis = bom_processor::analyze_bom_file(
+                new_file_path,
+                &dictionary,
+                true,
+                false,
+                max_file_size(state),
+                sheet.as_ref(),
+            )
+            .await?;
+            bom_processor::resolve_mapping_by_name(&analysis.headers, by_name)?
+        }
+        None => column_mapping,
+    };
+
+    let load_result = bom_processor::load_bom_file(
+        new_file_path,
+        &column_mapping,
+        max_file_size(state),
+        sheet.as_ref(),
+    )
+    .await?;
+    let load_operation = BomOperation::Load {
+        column_mapping: Box::new(column_mapping.clone()),
+        mapping_by_name: mapping_by_name.clone(),
+        sheet: load_result
+            .sheet_name
+            .clone()
+            .map(bom_processor::SheetSelector::Name),
+    };
+    let mut bom = load_result.bom;
+    let mut loaded_mapping = load_result.column_mapping;
+    let mut loaded_by_name = mapping_by_name;
+    let mut warnings = load_result.warnings;
+
+    let registered_list = state.registered_name_list.lock().unwrap().clone();
+    let overrides = state.override_list.lock().unwrap().clone();
+    let replayed = &operations[1..];
+    warnings.extend(apply_recorded_operations(
+        &mut bom,
+        &mut loaded_mapping,
+        loaded_by_name.as_mut(),
+        replayed,
+        &registered_list,
+        &overrides,
+    )?);
+
+    let recorded: Vec<BomOperation> = std::iter::once(load_operation)
+        .chain(replayed.iter().cloned())
+        .collect();
+    let path = Some(new_file_path.to_string());
+    match side_key {
+        "a" => {
+            *state.bom_a.lock().unwrap() = Some(bom);
+            *state.file_a_path.lock().unwrap() = path;
+            *state.column_mapping_a.lock().unwrap() = Some(loaded_mapping);
+            *state.column_mapping_by_name_a.lock().unwrap() = loaded_by_name;
+            *state.corrections_a.lock().unwrap() = load_result.corrections;
+            *state.operations_a.lock().unwrap() = recorded;
+        }
+        _ => {
+            *state.bom_b.lock().unwrap() = Some(bom);
+            *state.file_b_path.lock().unwrap() = path;
+            *state.column_mapping_b.lock().unwrap() = Some(loaded_mapping);
+            *state.column_mapping_by_name_b.lock().unwrap() = loaded_by_name;
+            *state.corrections_b.lock().unwrap() = load_result.corrections;
+            *state.operations_b.lock().unwrap() = recorded;
+        }
+    }
+    *state.comparison_result.lock().unwrap() = None;
+    *state.synthesis_result.lock().unwrap() = None;
+    *state.consolidated_bom.lock().unwrap() = None;
+    *state.changed_bom.lock().unwrap() = None;
+
+    remember_column_mapping(
+        state,
+        new_file_path,
+        load_result.source_headers,
+        column_mapping,
+    );
+    save_auto_session(state)?;
+    info!(
+        "[replay_session_operations] side={}, path={}, operations={}",
+        side_key,
+        new_file_path,
+        replayed.len()
+    );
+
+    Ok(ReplayOperationsResponse {
+        message: tr_side(state, Message::BomLoaded, side_key),
+        side: side_key.to_string(),
+        operation_count: replayed.len(),
+        warnings,
     })
 }
 
+// 記録された操作（読み込みを除く）を部品表と列指定に順に適用し、警告を返す
+fn apply_recorded_operations(
+    bom: &mut BomData,
+    column_mapping: &mut ColumnMapping,
+    mut mapping_by_name: Option<&mut ColumnMappingByName>,
+    operations: &[BomOperation],
+    registered_list: &Option<RegisteredNameList>,
+    overrides: &Option<OverrideList>,
+) -> Result<Vec<String>, AppError> {
+    let mut warnings = Vec::new();
+    let mut skipped_edits = 0;
+    for operation in operations {
+        match operation {
+            BomOperation::Load { .. } => {}
+            BomOperation::Preprocess { rules } => {
+                let processed = bom_processor::preprocess_bom_data(bom, rules)?;
+                warnings.extend(processed.warnings);
+                *bom = processed.bom;
+            }
+            BomOperation::ApplyRegisteredNames { policy } => {
+                // 列の連結・分割で列位置が変わっている可能性があるため、適用時点の列指定から求める
+                let manufacturer_header = column_mapping
+                    .manufacturer
+                    .and_then(|idx| bom.headers.get(idx).cloned());
+                bom_processor::apply_registered_names_to_bom(
+                    bom,
+                    registered_list,
+                    overrides,
+                    manufacturer_header.as_deref(),
+                    *policy,
+                );
+            }
+            BomOperation::RenameHeader { old_name, new_name } => {
+                bom_processor::rename_header(bom, old_name, new_name)?;
+                rename_mapped_column(mapping_by_name.as_deref_mut(), old_name, new_name);
+            }
+            BomOperation::MergeColumns {
+                source_a,
+                source_b,
+                target,
+                separator,
+                drop_sources,
+            } => {
+                bom_processor::merge_columns(
+                    bom,
+                    source_a,
+                    source_b,
+                    target,
+                    separator,
+                    *drop_sources,
+                    Some(column_mapping),
+                )?;
+            }
+            BomOperation::SplitColumn {
+                source,
+                separator,
+                target_names,
+                remove_source,
+            } => {
+                warnings.extend(bom_processor::split_column(
+                    bom,
+                    source,
+                    separator,
+                    target_names,
+                    *remove_source,
+                    Some(column_mapping),
+                )?);
+            }
+            BomOperation::Optimize { strategy } => {
+                bom_processor::optimize_bom_data(bom, *strategy);
+            }
+            BomOperation::ManualEdit => skipped_edits += 1,
+        }
+    }
+    if skipped_edits > 0 {
+        warnings.push(format!(
+            "手動で編集した内容（{}件）は再適用されていません",
+            skipped_edits
+        ));
+    }
+    Ok(warnings)
+}
+
+// 列指定で指定されたメーカー列のヘッダー名を取得する
+fn mapped_manufacturer_header(state: &AppState, side: &str) -> Option<String> {
+    let (mapping, bom) = match side {
+        "a" => (&state.column_mapping_a, &state.bom_a),
+        "b" => (&state.column_mapping_b, &state.bom_b),
+        _ => return None,
+    };
+    let manufacturer_idx = mapping.lock().unwrap().as_ref()?.manufacturer?;
+    let bom_guard = bom.lock().unwrap();
+    bom_guard.as_ref()?.headers.get(manufacturer_idx).cloned()
+}
+
+#[tauri::command]
+async fn apply_registered_names_preview(
+    side: String,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<bom_processor::RegisteredNamePreview, AppError> {
+    let side_key = side.to_lowercase();
+    let bom = get_bom_from_state(&state, &side_key)?
+        .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, &side_key)))?;
+    let registered_list = state.registered_name_list.lock().unwrap().clone();
+    let overrides = state.override_list.lock().unwrap().clone();
+    let manufacturer_header = mapped_manufacturer_header(&state, &side_key);
+    let policy = state.settings.lock().unwrap().registered_name_policy;
+
+    Ok(bom_processor::preview_registered_names(
+        &bom,
+        &registered_list,
+        &overrides,
+        manufacturer_header.as_deref(),
+        policy,
+        limit.unwrap_or(REGISTERED_NAME_PREVIEW_LIMIT),
+    ))
+}
+
 #[tauri::command]
-async fn load_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
+async fn load_settings(state: State<'_, AppState>) -> Result<AppSettings, AppError> {
     let settings = state.settings.lock().unwrap().clone();
     Ok(settings)
 }
@@ -663,13 +2709,14 @@ async fn load_settings(state: State<'_, AppState>) -> Result<AppSettings, String
 async fn save_settings(
     settings: AppSettings,
     state: State<'_, AppState>,
-) -> Result<MessageResponse, String> {
-    let normalized = normalize_settings(settings)?;
+) -> Result<MessageResponse, AppError> {
+    let normalized = normalize_settings(settings).map_err(AppError::InvalidInput)?;
     write_settings_to_disk(&normalized)?;
+    logging::set_level(&configured_log_level(&normalized));
     *state.settings.lock().unwrap() = normalized;
 
     Ok(MessageResponse {
-        message: "設定を保存しました".to_string(),
+        message: tr(&state, Message::SettingsSaved),
     })
 }
 
@@ -677,20 +2724,23 @@ async fn save_settings(
 async fn import_settings(
     file_path: String,
     state: State<'_, AppState>,
-) -> Result<AppSettings, String> {
+) -> Result<AppSettings, AppError> {
     let path = Path::new(&file_path);
     if !path.exists() {
-        return Err("設定ファイルが見つかりません".to_string());
+        return Err(AppError::FileNotFound(
+            "設定ファイルが見つかりません".to_string(),
+        ));
     }
 
     let content = fs::read_to_string(path)
-        .map_err(|e| format!("設定ファイルの読み込みに失敗しました: {e}"))?;
+        .map_err(|e| AppError::FileRead(format!("設定ファイルの読み込みに失敗しました: {e}")))?;
 
     let raw: AppSettings = serde_json::from_str(&content)
-        .map_err(|e| format!("設定ファイルの解析に失敗しました: {e}"))?;
+        .map_err(|e| AppError::InvalidInput(format!("設定ファイルの解析に失敗しました: {e}")))?;
 
-    let normalized = normalize_settings(raw)?;
+    let normalized = normalize_settings(raw).map_err(AppError::InvalidInput)?;
     write_settings_to_disk(&normalized)?;
+    logging::set_level(&configured_log_level(&normalized));
     *state.settings.lock().unwrap() = normalized.clone();
 
     Ok(normalized)
@@ -700,17 +2750,19 @@ async fn import_settings(
 async fn export_settings(
     file_path: String,
     state: State<'_, AppState>,
-) -> Result<MessageResponse, String> {
+) -> Result<MessageResponse, AppError> {
     let settings = state.settings.lock().unwrap().clone();
     let json = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("設定JSONの生成に失敗しました: {e}"))?;
+        .map_err(|e| AppError::Internal(format!("設定JSONの生成に失敗しました: {e}")))?;
 
     let path = Path::new(&file_path);
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("ディレクトリの作成に失敗しました: {e}"))?;
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::write_failed("ディレクトリの作成に失敗しました", e))?;
     }
 
-    fs::write(path, json).map_err(|e| format!("設定ファイルの書き込みに失敗しました: {e}"))?;
+    fs::write(path, json)
+        .map_err(|e| AppError::write_failed("設定ファイルの書き込みに失敗しました", e))?;
 
     Ok(MessageResponse {
         message: format!("設定をエクスポートしました: {}", file_path),
@@ -718,7 +2770,7 @@ async fn export_settings(
 }
 
 #[tauri::command]
-async fn load_column_dictionary(state: State<'_, AppState>) -> Result<ColumnDictionary, String> {
+async fn load_column_dictionary(state: State<'_, AppState>) -> Result<ColumnDictionary, AppError> {
     Ok(state.column_dictionary.lock().unwrap().clone())
 }
 
@@ -726,13 +2778,13 @@ async fn load_column_dictionary(state: State<'_, AppState>) -> Result<ColumnDict
 async fn save_column_dictionary(
     dictionary: ColumnDictionary,
     state: State<'_, AppState>,
-) -> Result<MessageResponse, String> {
-    let normalized = normalize_dictionary(dictionary)?;
+) -> Result<MessageResponse, AppError> {
+    let normalized = normalize_dictionary(dictionary).map_err(AppError::InvalidInput)?;
     write_dictionary_to_disk(&normalized)?;
-    *state.column_dictionary.lock().unwrap() = normalized;
+    set_column_dictionary(&state, normalized);
 
     Ok(MessageResponse {
-        message: "辞書を保存しました".to_string(),
+        message: tr(&state, Message::DictionarySaved),
     })
 }
 
@@ -740,21 +2792,23 @@ async fn save_column_dictionary(
 async fn import_column_dictionary(
     file_path: String,
     state: State<'_, AppState>,
-) -> Result<ColumnDictionary, String> {
+) -> Result<ColumnDictionary, AppError> {
     let path = Path::new(&file_path);
     if !path.exists() {
-        return Err("辞書ファイルが見つかりません".to_string());
+        return Err(AppError::FileNotFound(
+            "辞書ファイルが見つかりません".to_string(),
+        ));
     }
 
     let content = fs::read_to_string(path)
-        .map_err(|e| format!("辞書ファイルの読み込みに失敗しました: {e}"))?;
+        .map_err(|e| AppError::FileRead(format!("辞書ファイルの読み込みに失敗しました: {e}")))?;
 
     let raw: ColumnDictionary = serde_json::from_str(&content)
-        .map_err(|e| format!("辞書ファイルの解析に失敗しました: {e}"))?;
+        .map_err(|e| AppError::InvalidInput(format!("辞書ファイルの解析に失敗しました: {e}")))?;
 
-    let normalized = normalize_dictionary(raw)?;
+    let normalized = normalize_dictionary(raw).map_err(AppError::InvalidInput)?;
     write_dictionary_to_disk(&normalized)?;
-    *state.column_dictionary.lock().unwrap() = normalized.clone();
+    set_column_dictionary(&state, normalized.clone());
 
     Ok(normalized)
 }
@@ -763,17 +2817,19 @@ async fn import_column_dictionary(
 async fn export_column_dictionary(
     file_path: String,
     state: State<'_, AppState>,
-) -> Result<MessageResponse, String> {
+) -> Result<MessageResponse, AppError> {
     let dictionary = state.column_dictionary.lock().unwrap().clone();
     let json = serde_json::to_string_pretty(&dictionary)
-        .map_err(|e| format!("辞書JSONの生成に失敗しました: {e}"))?;
+        .map_err(|e| AppError::Internal(format!("辞書JSONの生成に失敗しました: {e}")))?;
 
     let path = Path::new(&file_path);
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("ディレクトリの作成に失敗しました: {e}"))?;
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::write_failed("ディレクトリの作成に失敗しました", e))?;
     }
 
-    fs::write(path, json).map_err(|e| format!("辞書ファイルの書き込みに失敗しました: {e}"))?;
+    fs::write(path, json)
+        .map_err(|e| AppError::write_failed("辞書ファイルの書き込みに失敗しました", e))?;
 
     Ok(MessageResponse {
         message: format!("辞書をエクスポートしました: {}", file_path),
@@ -784,56 +2840,56 @@ async fn export_column_dictionary(
 async fn get_processed_preview(
     side: String,
     state: State<'_, AppState>,
-) -> Result<PreviewTable, String> {
+) -> Result<PreviewTable, AppError> {
     let side_key = side.to_lowercase();
     let bom = match side_key.as_str() {
         "a" => state
             .bom_a
             .lock()
-            .map_err(|_| "部品表Aのロックに失敗しました".to_string())?
+            .map_err(|_| AppError::Internal("部品表Aのロックに失敗しました".to_string()))?
             .clone(),
         "b" => state
             .bom_b
             .lock()
-            .map_err(|_| "部品表Bのロックに失敗しました".to_string())?
+            .map_err(|_| AppError::Internal("部品表Bのロックに失敗しました".to_string()))?
             .clone(),
-        _ => return Err("無効なサイド指定です".to_string()),
+        _ => return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide))),
     };
 
     let mapping = match side_key.as_str() {
         "a" => state
             .column_mapping_a
             .lock()
-            .map_err(|_| "列指定Aのロックに失敗しました".to_string())?
+            .map_err(|_| AppError::Internal("列指定Aのロックに失敗しました".to_string()))?
             .clone(),
         "b" => state
             .column_mapping_b
             .lock()
-            .map_err(|_| "列指定Bのロックに失敗しました".to_string())?
+            .map_err(|_| AppError::Internal("列指定Bのロックに失敗しました".to_string()))?
             .clone(),
         _ => None,
     };
 
     let bom = bom.ok_or_else(|| match side_key.as_str() {
-        "a" => "部品表Aが読み込まれていません".to_string(),
-        "b" => "部品表Bが読み込まれていません".to_string(),
-        _ => "無効なサイド指定です".to_string(),
+        "a" => AppError::NoData(tr_side(&state, Message::BomNotLoaded, "a")),
+        "b" => AppError::NoData(tr_side(&state, Message::BomNotLoaded, "b")),
+        _ => AppError::InvalidInput(tr(&state, Message::InvalidSide)),
     })?;
 
     let mapping = mapping.ok_or_else(|| match side_key.as_str() {
-        "a" => "部品表Aの列設定が未指定です".to_string(),
-        "b" => "部品表Bの列設定が未指定です".to_string(),
-        _ => "無効なサイド指定です".to_string(),
+        "a" => AppError::Column("部品表Aの列設定が未指定です".to_string()),
+        "b" => AppError::Column("部品表Bの列設定が未指定です".to_string()),
+        _ => AppError::InvalidInput(tr(&state, Message::InvalidSide)),
     })?;
 
-    generate_preprocessed_preview(&bom, &mapping)
+    generate_preprocessed_preview(&bom, &mapping, range_designator_pattern(&state))
 }
 
 #[tauri::command]
 async fn set_overrides(
     request: SetOverridesRequest,
     state: State<'_, AppState>,
-) -> Result<OverrideListResponse, String> {
+) -> Result<OverrideListResponse, AppError> {
     let mut guard = state.override_list.lock().unwrap();
     let mut overrides = guard.clone().unwrap_or_default();
 
@@ -866,7 +2922,88 @@ async fn set_overrides(
 
     Ok(OverrideListResponse {
         overrides,
-        message: "上書きリストを更新しました".to_string(),
+        message: tr(&state, Message::OverridesUpdated),
+    })
+}
+
+// 上書きを1件追加・更新する
+#[tauri::command]
+async fn add_override(
+    part_number: String,
+    registered_name: String,
+    match_field: Option<OverrideMatchField>,
+    state: State<'_, AppState>,
+) -> Result<OverrideListResponse, AppError> {
+    let part_number = part_number.trim().to_string();
+    if part_number.is_empty() {
+        return Err(AppError::InvalidInput(
+            "部品番号が指定されていません".to_string(),
+        ));
+    }
+
+    let mut overrides = state
+        .override_list
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or_default();
+    upsert_override_entry(
+        &mut overrides,
+        OverrideEntry {
+            part_number,
+            registered_name,
+            match_field: match_field.unwrap_or_default(),
+        },
+    );
+    overrides
+        .entries
+        .sort_by(|a, b| a.part_number.cmp(&b.part_number));
+
+    // スナップショット作成時に上書きリストをロックするため、ここではロックを保持しない
+    *state.override_list.lock().unwrap() = Some(overrides.clone());
+    save_auto_session(&state)?;
+
+    Ok(OverrideListResponse {
+        overrides,
+        message: tr(&state, Message::OverridesUpdated),
+    })
+}
+
+// 上書きを1件削除する
+#[tauri::command]
+async fn remove_override(
+    part_number: String,
+    match_field: Option<OverrideMatchField>,
+    state: State<'_, AppState>,
+) -> Result<OverrideListResponse, AppError> {
+    let mut overrides = state
+        .override_list
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or_default();
+    let match_field = match_field.unwrap_or_default();
+    let before = overrides.entries.len();
+    overrides
+        .entries
+        .retain(|entry| entry.part_number != part_number || entry.match_field != match_field);
+    if overrides.entries.len() == before {
+        let field = match match_field {
+            OverrideMatchField::PartNumber => "部品番号",
+            OverrideMatchField::ModelNumber => "型番",
+        };
+        return Err(AppError::InvalidInput(format!(
+            "{} {} の上書きは登録されていません",
+            field, part_number
+        )));
+    }
+
+    *state.override_list.lock().unwrap() = Some(overrides.clone());
+    save_auto_session(&state)?;
+
+    Ok(OverrideListResponse {
+        overrides,
+        message: tr(&state, Message::OverridesUpdated),
     })
 }
 
@@ -874,7 +3011,7 @@ fn upsert_override_entry(list: &mut OverrideList, entry: OverrideEntry) {
     if let Some(existing) = list
         .entries
         .iter_mut()
-        .find(|e| e.part_number == entry.part_number)
+        .find(|e| e.part_number == entry.part_number && e.match_field == entry.match_field)
     {
         existing.registered_name = entry.registered_name;
     } else {
@@ -886,144 +3023,663 @@ fn upsert_override_entry(list: &mut OverrideList, entry: OverrideEntry) {
 async fn apply_overrides_ipc(
     side: String,
     state: State<'_, AppState>,
-) -> Result<MessageResponse, String> {
+) -> Result<MessageResponse, AppError> {
     let side_key = side.to_lowercase();
     let registered_list = state.registered_name_list.lock().unwrap().clone();
     let overrides = state.override_list.lock().unwrap().clone();
+    let manufacturer_header = mapped_manufacturer_header(&state, &side_key);
+    let policy = state.settings.lock().unwrap().registered_name_policy;
 
     match side_key.as_str() {
         "a" => {
             let mut bom_lock = state.bom_a.lock().unwrap();
             if let Some(ref mut bom) = *bom_lock {
-                bom_processor::apply_registered_names_to_bom(bom, &registered_list, &overrides);
+                bom_processor::apply_registered_names_to_bom(
+                    bom,
+                    &registered_list,
+                    &overrides,
+                    manufacturer_header.as_deref(),
+                    policy,
+                );
             } else {
-                return Err("部品表Aが読み込まれていません".to_string());
+                return Err(AppError::NoData(tr_side(
+                    &state,
+                    Message::BomNotLoaded,
+                    "a",
+                )));
             }
         }
         "b" => {
             let mut bom_lock = state.bom_b.lock().unwrap();
             if let Some(ref mut bom) = *bom_lock {
-                bom_processor::apply_registered_names_to_bom(bom, &registered_list, &overrides);
+                bom_processor::apply_registered_names_to_bom(
+                    bom,
+                    &registered_list,
+                    &overrides,
+                    manufacturer_header.as_deref(),
+                    policy,
+                );
             } else {
-                return Err("部品表Bが読み込まれていません".to_string());
+                return Err(AppError::NoData(tr_side(
+                    &state,
+                    Message::BomNotLoaded,
+                    "b",
+                )));
             }
         }
-        _ => return Err("サイド指定が無効です".to_string()),
+        _ => return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide))),
     }
 
+    record_operation(
+        &state,
+        &side_key,
+        BomOperation::ApplyRegisteredNames { policy },
+    );
     *state.comparison_result.lock().unwrap() = None;
     save_auto_session(&state)?;
 
     Ok(MessageResponse {
-        message: format!("部品表{}に上書きを適用しました", side_key.to_uppercase()),
+        message: tr_side(&state, Message::OverridesApplied, &side_key),
     })
 }
 
 #[tauri::command(name = "get_registered_name_list")]
 async fn get_registered_name_list_cmd(
     state: State<'_, AppState>,
-) -> Result<Option<RegisteredNameList>, String> {
+) -> Result<Option<RegisteredNameList>, AppError> {
     Ok(state.registered_name_list.lock().unwrap().clone())
 }
 
+#[tauri::command]
+async fn get_app_status(state: State<'_, AppState>) -> Result<AppStatus, AppError> {
+    let side_status = |bom: &Mutex<Option<BomData>>,
+                       path: &Mutex<Option<String>>,
+                       mapping: &Mutex<Option<ColumnMapping>>| {
+        let row_count = bom.lock().unwrap().as_ref().map(|b| b.rows.len());
+        SideStatus {
+            loaded: row_count.is_some(),
+            row_count: row_count.unwrap_or(0),
+            file_path: path.lock().unwrap().clone(),
+            has_column_mapping: mapping.lock().unwrap().is_some(),
+        }
+    };
+
+    Ok(AppStatus {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        bom_a: side_status(&state.bom_a, &state.file_a_path, &state.column_mapping_a),
+        bom_b: side_status(&state.bom_b, &state.file_b_path, &state.column_mapping_b),
+        has_comparison_result: state.comparison_result.lock().unwrap().is_some(),
+        has_synthesis_result: state.synthesis_result.lock().unwrap().is_some(),
+        registered_name_count: state
+            .registered_name_list
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|list| list.entries.len())
+            .unwrap_or(0),
+        override_count: state
+            .override_list
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|list| list.entries.len())
+            .unwrap_or(0),
+        folder_bom_count: state.loaded_boms.lock().unwrap().len(),
+        language: state.settings.lock().unwrap().language.clone(),
+        settings_path: settings_file_path().display().to_string(),
+        dictionary_path: dictionary_file_path().display().to_string(),
+        dictionary_column_count: state.column_dictionary.lock().unwrap().columns.len(),
+    })
+}
+
 #[tauri::command(name = "get_override_list")]
-async fn get_override_list_cmd(state: State<'_, AppState>) -> Result<Option<OverrideList>, String> {
+async fn get_override_list_cmd(
+    state: State<'_, AppState>,
+) -> Result<Option<OverrideList>, AppError> {
     Ok(state.override_list.lock().unwrap().clone())
 }
 
+// 出力前の確認をまとめて行う（部品表・列指定の有無、バリデーション、登録名の未解決）
+#[tauri::command]
+async fn preflight_check(state: State<'_, AppState>) -> Result<PreflightReport, AppError> {
+    let registered_list = state.registered_name_list.lock().unwrap().clone();
+    let overrides = state.override_list.lock().unwrap().clone();
+    let mut checks = Vec::new();
+    let mut check = |name: String, passed: bool, message: String| {
+        checks.push(PreflightCheck {
+            name,
+            passed,
+            message,
+        });
+    };
+
+    for side in ["a", "b"] {
+        let label = side.to_uppercase();
+        let bom = get_bom_from_state(&state, side)?;
+        let mapping_present = match side {
+            "a" => state.column_mapping_a.lock().unwrap().is_some(),
+            _ => state.column_mapping_b.lock().unwrap().is_some(),
+        };
+
+        let bom = match bom {
+            Some(bom) => {
+                check(
+                    format!("bom_{side}_loaded"),
+                    true,
+                    format!("部品表{label}は読み込み済みです"),
+                );
+                bom
+            }
+            None => {
+                check(
+                    format!("bom_{side}_loaded"),
+                    false,
+                    tr_side(&state, Message::BomNotLoaded, side),
+                );
+                continue;
+            }
+        };
+
+        check(
+            format!("mapping_{side}"),
+            mapping_present,
+            if mapping_present {
+                format!("部品表{label}の列指定があります")
+            } else {
+                format!("部品表{label}の列指定がありません")
+            },
+        );
+
+        let validation = bom_processor::validate_bom_data(&bom);
+        check(
+            format!("validation_{side}"),
+            validation.is_valid,
+            if validation.is_valid {
+                format!("部品表{label}のバリデーションに問題はありません")
+            } else {
+                format!(
+                    "部品表{label}にバリデーションエラーが{}件あります",
+                    validation.errors.len()
+                )
+            },
+        );
+
+        if registered_list.is_some() {
+            let manufacturer_header = mapped_manufacturer_header(&state, side);
+            let preview = bom_processor::preview_registered_names(
+                &bom,
+                &registered_list,
+                &overrides,
+                manufacturer_header.as_deref(),
+                state.settings.lock().unwrap().registered_name_policy,
+                0,
+            );
+            let unresolved = preview.summary.get("none").copied().unwrap_or(0);
+            check(
+                format!("registered_names_{side}"),
+                unresolved == 0,
+                if unresolved == 0 {
+                    format!("部品表{label}の全部品に登録名があります")
+                } else {
+                    format!("部品表{label}に登録名のない部品が{unresolved}件あります")
+                },
+            );
+        }
+    }
+
+    Ok(PreflightReport {
+        passed: checks.iter().all(|c| c.passed),
+        checks,
+    })
+}
+
 #[tauri::command]
 async fn validate_bom_data(
     side: Option<String>,
     bom_data: Option<BomSnapshot>,
     state: State<'_, AppState>,
-) -> Result<ValidationResult, String> {
+) -> Result<ValidationResult, AppError> {
     let bom = if let Some(snapshot) = bom_data {
+        snapshot
+            .check_schema_version()
+            .map_err(AppError::InvalidInput)?;
         BomData::from(snapshot)
     } else if let Some(side_value) = side {
         let side_key = side_value.to_lowercase();
         get_bom_from_state(&state, &side_key)?
-            .ok_or_else(|| format!("部品表{}が読み込まれていません", side_key.to_uppercase()))?
+            .ok_or_else(|| AppError::NoData(tr_side(&state, Message::BomNotLoaded, &side_key)))?
     } else {
-        return Err("バリデーション対象の部品表が指定されていません".to_string());
+        return Err(AppError::InvalidInput(
+            "バリデーション対象の部品表が指定されていません".to_string(),
+        ));
     };
 
     Ok(bom_processor::validate_bom_data(&bom))
 }
 
+// 読み込み済みの全部品表（部品表A/B・フォルダ読み込み分）をまとめて検証する
+#[tauri::command]
+async fn validate_all(state: State<'_, AppState>) -> Result<ValidateAllResponse, AppError> {
+    let mut targets: Vec<(String, String, BomData)> = Vec::new();
+    for side in ["a", "b"] {
+        let bom = match get_bom_from_state(&state, side)? {
+            Some(bom) => bom,
+            None => continue,
+        };
+        let path = match side {
+            "a" => state.file_a_path.lock().unwrap().clone(),
+            _ => state.file_b_path.lock().unwrap().clone(),
+        };
+        let name = path
+            .as_deref()
+            .and_then(|p| Path::new(p).file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        targets.push((format!("{side}:{name}"), side.to_string(), bom));
+    }
+
+    let mut folder: Vec<(String, BomData)> = state
+        .loaded_boms
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, bom)| (name.clone(), bom.clone()))
+        .collect();
+    folder.sort_by(|a, b| a.0.cmp(&b.0));
+    targets.extend(
+        folder
+            .into_iter()
+            .map(|(name, bom)| (name, "folder".to_string(), bom)),
+    );
+
+    if targets.is_empty() {
+        return Err(AppError::NoData(
+            "検証対象の部品表が読み込まれていません".to_string(),
+        ));
+    }
+
+    let mut files = IndexMap::new();
+    for (key, source, bom) in targets {
+        let result = bom_processor::validate_bom_data(&bom);
+        info!(
+            "[validate_all][file] key={}, rows={}, errors={}",
+            key,
+            bom.rows.len(),
+            result.errors.len()
+        );
+        files.insert(
+            key,
+            BomValidationEntry {
+                source,
+                row_count: bom.rows.len(),
+                result,
+            },
+        );
+    }
+
+    Ok(ValidateAllResponse {
+        passed: files.values().all(|entry| entry.result.is_valid),
+        files,
+    })
+}
+
 // 結果保存コマンド
 #[tauri::command]
 async fn save_result(
     file_path: String,
-    format: String,      // "csv" or "txt"
+    format: String, // "csv" / "txt" / "json" / "diff" / "html"（html は比較結果のみ）
     result_type: String, // "comparison" or "synthesis"
+    include_unchanged: Option<bool>,
+    append: Option<bool>,
+    encoding: Option<String>, // "utf-8" / "utf-8-bom" / "shift_jis"（未指定時は形式ごとの既定）
     state: State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
+    let append = append.unwrap_or(false);
+    let encoding = encoding.as_deref();
     match result_type.as_str() {
         "comparison" => {
             let comparison = state.comparison_result.lock().unwrap().clone();
+            let comparison = if include_unchanged.unwrap_or(true) {
+                comparison
+            } else {
+                comparison.as_ref().map(without_unchanged)
+            };
             match comparison {
-                Some(result) => save_comparison_result(&result, &file_path, &format).await,
-                None => Err("比較結果がありません".to_string()),
+                Some(result) => {
+                    save_comparison_result(&result, &file_path, &format, append, encoding).await
+                }
+                None => Err(AppError::NoData(tr(&state, Message::NoComparisonResult))),
             }
         }
         "synthesis" => {
             let synthesis = state.synthesis_result.lock().unwrap().clone();
             match synthesis {
-                Some(result) => save_synthesis_result(&result, &file_path, &format).await,
-                None => Err("合成結果がありません".to_string()),
+                Some(result) => {
+                    save_synthesis_result(&result, &file_path, &format, append, encoding).await
+                }
+                None => Err(AppError::NoData(tr(&state, Message::NoSynthesisResult))),
+            }
+        }
+        _ => Err(AppError::InvalidInput("無効な結果タイプです".to_string())),
+    }
+}
+
+// 比較結果をカテゴリ（common / a_only / b_only / modified / renamed）ごとのファイルに分けて保存し、
+// 集計を summary.txt に書き出す。書き出したパスを返す（行のないカテゴリのファイルは作らない）
+#[tauri::command]
+async fn save_comparison_split(
+    dir_path: String,
+    format: String,
+    encoding: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, AppError> {
+    let comparison = state
+        .comparison_result
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| AppError::NoData(tr(&state, Message::NoComparisonResult)))?;
+    if !COMPARISON_OUTPUT_FORMATS.contains(&format.as_str()) {
+        return Err(AppError::UnsupportedFormat(
+            "サポートされていないフォーマットです".to_string(),
+        ));
+    }
+
+    let dir = PathBuf::from(&dir_path);
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::write_failed("出力ディレクトリを作成できません", e))?;
+
+    let mut written = Vec::new();
+    for (category, part) in split_by_category(&comparison) {
+        let path = dir.join(format!("{}.{}", category, format));
+        let path = path.to_string_lossy().to_string();
+        save_comparison_result(&part, &path, &format, false, encoding.as_deref()).await?;
+        written.push(path);
+    }
+
+    let summary_path = dir.join("summary.txt").to_string_lossy().to_string();
+    let summary_encoding = match format.as_str() {
+        "json" => file_handler::DEFAULT_TEXT_ENCODING,
+        _ => encoding
+            .as_deref()
+            .unwrap_or(file_handler::DEFAULT_TEXT_ENCODING),
+    };
+    file_handler::save_txt_file(
+        &render_comparison_summary(&comparison),
+        &summary_path,
+        summary_encoding,
+    )
+    .await
+    .map_err(|e| AppError::write_failed("TXT保存エラー", e))?;
+    written.push(summary_path);
+
+    info!(
+        "[save_comparison_split] {}件のファイルを保存しました",
+        written.len()
+    );
+    Ok(written)
+}
+
+// 比較結果をメーカーごとのファイルに分けて保存し、書き出したパスを返す
+// メーカーは辞書で見つけたメーカー列から取得する（B側を優先し、Bにない部品はA側）
+#[tauri::command]
+async fn save_comparison_by_manufacturer(
+    dir_path: String,
+    format: String,
+    encoding: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, AppError> {
+    let comparison = state
+        .comparison_result
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| AppError::NoData(tr(&state, Message::NoComparisonResult)))?;
+    if !COMPARISON_OUTPUT_FORMATS.contains(&format.as_str()) {
+        return Err(AppError::UnsupportedFormat(
+            "サポートされていないフォーマットです".to_string(),
+        ));
+    }
+
+    let dictionary = state.column_dictionary.lock().unwrap().clone();
+    let mut manufacturers: HashMap<String, String> = HashMap::new();
+    for side in ["a", "b"] {
+        let bom = match get_bom_from_state(&state, side)? {
+            Some(bom) => bom,
+            None => continue,
+        };
+        let header = match bom_processor::find_manufacturer_header(&bom, &dictionary) {
+            Some(header) => header,
+            None => continue,
+        };
+        for row in &bom.rows {
+            match row.attributes.get(&header) {
+                Some(value) if !value.trim().is_empty() => {
+                    manufacturers.insert(row.part_number.clone(), value.clone());
+                }
+                _ => {}
             }
         }
-        _ => Err("無効な結果タイプです".to_string()),
     }
+
+    let dir = PathBuf::from(&dir_path);
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::write_failed("出力ディレクトリを作成できません", e))?;
+
+    let mut written = Vec::new();
+    for (manufacturer, group) in group_by_manufacturer(&comparison, &manufacturers) {
+        let file_name = file_handler::sanitize_file_name(&manufacturer);
+        let path = dir.join(format!("{}.{}", file_name, format));
+        let path = path.to_string_lossy().to_string();
+        save_comparison_result(&group, &path, &format, false, encoding.as_deref()).await?;
+        written.push(path);
+    }
+
+    info!(
+        "[save_comparison_by_manufacturer] {}件のファイルを保存しました",
+        written.len()
+    );
+    Ok(written)
+}
+
+#[tauri::command]
+async fn clear_data(
+    mode: String,
+    dry_run: Option<bool>, // true の場合は何も消さず、クリアされる内容だけを返す
+    state: State<'_, AppState>,
+) -> Result<ClearDataResponse, AppError> {
+    let keep_lists = match mode.to_lowercase().as_str() {
+        "all" => false,
+        "session_keep" => true,
+        _ => return Err(AppError::InvalidInput("無効なクリアモードです".to_string())),
+    };
+    let targets = collect_clear_targets(&state, keep_lists);
+    if dry_run.unwrap_or(false) {
+        let message = if targets.is_empty() {
+            "削除されるデータはありません".to_string()
+        } else {
+            let labels: Vec<&str> = targets.iter().map(|t| t.label.as_str()).collect();
+            format!("これにより{}が削除されます", labels.join("、"))
+        };
+        return Ok(ClearDataResponse {
+            message,
+            dry_run: true,
+            targets,
+        });
+    }
+
+    *state.bom_a.lock().unwrap() = None;
+    *state.bom_b.lock().unwrap() = None;
+    *state.comparison_result.lock().unwrap() = None;
+    *state.synthesis_result.lock().unwrap() = None;
+    *state.consolidated_bom.lock().unwrap() = None;
+    *state.changed_bom.lock().unwrap() = None;
+    if !keep_lists {
+        *state.registered_name_list.lock().unwrap() = None;
+        *state.override_list.lock().unwrap() = None;
+    }
+    *state.file_a_path.lock().unwrap() = None;
+    *state.file_b_path.lock().unwrap() = None;
+    *state.column_mapping_a.lock().unwrap() = None;
+    *state.column_mapping_b.lock().unwrap() = None;
+    *state.column_mapping_by_name_a.lock().unwrap() = None;
+    *state.column_mapping_by_name_b.lock().unwrap() = None;
+    state.corrections_a.lock().unwrap().clear();
+    state.corrections_b.lock().unwrap().clear();
+    state.operations_a.lock().unwrap().clear();
+    state.operations_b.lock().unwrap().clear();
+    state.loaded_boms.lock().unwrap().clear();
+    save_auto_session(&state)?;
+    let message = if keep_lists {
+        Message::DataClearedKeepingLists
+    } else {
+        Message::AllDataCleared
+    };
+    Ok(ClearDataResponse {
+        message: tr(&state, message),
+        dry_run: false,
+        targets,
+    })
+}
+
+/// 現在データが入っている項目のうち clear_data で消えるものを列挙する
+/// keep_lists が true の場合（session_keep）は登録名リスト・上書きリストを含めない
+fn collect_clear_targets(state: &AppState, keep_lists: bool) -> Vec<ClearTarget> {
+    fn target(key: &str, label: String, count: usize) -> ClearTarget {
+        ClearTarget {
+            key: key.to_string(),
+            label,
+            count,
+        }
+    }
+
+    let mut targets = Vec::new();
+    for (side, slot) in [("A", &state.bom_a), ("B", &state.bom_b)] {
+        if let Some(bom) = slot.lock().unwrap().as_ref() {
+            let count = bom.rows.len();
+            targets.push(target(
+                &format!("bom_{}", side.to_lowercase()),
+                format!("部品表{side}({}行)", format_count(count)),
+                count,
+            ));
+        }
+    }
+    if let Some(result) = state.comparison_result.lock().unwrap().as_ref() {
+        let count = result.common_parts.len()
+            + result.modified_parts.len()
+            + result.renamed_parts.len()
+            + result.a_only_parts.len()
+            + result.b_only_parts.len();
+        targets.push(target("comparison_result", "比較結果".to_string(), count));
+    }
+    if let Some(result) = state.synthesis_result.lock().unwrap().as_ref() {
+        let count = result.rows.len();
+        targets.push(target("synthesis_result", "合成結果".to_string(), count));
+    }
+    if let Some(bom) = state.consolidated_bom.lock().unwrap().as_ref() {
+        let count = bom.rows.len();
+        targets.push(target(
+            "consolidated_bom",
+            format!("統合部品表({}行)", format_count(count)),
+            count,
+        ));
+    }
+    if let Some(bom) = state.changed_bom.lock().unwrap().as_ref() {
+        let count = bom.rows.len();
+        targets.push(target(
+            "changed_bom",
+            format!("変更部品の部品表({}行)", format_count(count)),
+            count,
+        ));
+    }
+    let loaded = state.loaded_boms.lock().unwrap().len();
+    if loaded > 0 {
+        targets.push(target(
+            "loaded_boms",
+            format!("読み込み済み部品表({}件)", format_count(loaded)),
+            loaded,
+        ));
+    }
+    if !keep_lists {
+        if let Some(list) = state.registered_name_list.lock().unwrap().as_ref() {
+            let count = list.entries.len();
+            targets.push(target(
+                "registered_name_list",
+                format!("登録名リスト({}件)", format_count(count)),
+                count,
+            ));
+        }
+        if let Some(list) = state.override_list.lock().unwrap().as_ref() {
+            let count = list.entries.len();
+            targets.push(target(
+                "override_list",
+                format!("上書きリスト({}件)", format_count(count)),
+                count,
+            ));
+        }
+    }
+    targets
+}
+
+/// 件数を3桁区切りで表示する（例: 1203 → "1,203"）
+fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
 }
 
+// 登録名リストだけをクリアする（上書きリストは保持）
 #[tauri::command]
-async fn clear_data(mode: String, state: State<'_, AppState>) -> Result<MessageResponse, String> {
-    match mode.to_lowercase().as_str() {
-        "all" => {
-            *state.bom_a.lock().unwrap() = None;
-            *state.bom_b.lock().unwrap() = None;
-            *state.comparison_result.lock().unwrap() = None;
-            *state.synthesis_result.lock().unwrap() = None;
-            *state.registered_name_list.lock().unwrap() = None;
-            *state.override_list.lock().unwrap() = None;
-            *state.file_a_path.lock().unwrap() = None;
-            *state.file_b_path.lock().unwrap() = None;
-            *state.column_mapping_a.lock().unwrap() = None;
-            *state.column_mapping_b.lock().unwrap() = None;
-            save_auto_session(&state)?;
-            Ok(MessageResponse {
-                message: "全データをクリアしました".to_string(),
-            })
-        }
-        "session_keep" => {
-            *state.bom_a.lock().unwrap() = None;
-            *state.bom_b.lock().unwrap() = None;
-            *state.comparison_result.lock().unwrap() = None;
-            *state.synthesis_result.lock().unwrap() = None;
-            *state.file_a_path.lock().unwrap() = None;
-            *state.file_b_path.lock().unwrap() = None;
-            *state.column_mapping_a.lock().unwrap() = None;
-            *state.column_mapping_b.lock().unwrap() = None;
-            save_auto_session(&state)?;
-            Ok(MessageResponse {
-                message: "登録名と上書きを保持してクリアしました".to_string(),
-            })
-        }
-        _ => Err("無効なクリアモードです".to_string()),
+async fn clear_registered_names(state: State<'_, AppState>) -> Result<MessageResponse, AppError> {
+    *state.registered_name_list.lock().unwrap() = None;
+    invalidate_comparison_if_names_applied(&state);
+    save_auto_session(&state)?;
+    Ok(MessageResponse {
+        message: tr(&state, Message::RegisteredNamesCleared),
+    })
+}
+
+// 上書きリストだけをクリアする（登録名リストは保持）
+#[tauri::command]
+async fn clear_overrides(state: State<'_, AppState>) -> Result<MessageResponse, AppError> {
+    *state.override_list.lock().unwrap() = None;
+    invalidate_comparison_if_names_applied(&state);
+    save_auto_session(&state)?;
+    Ok(MessageResponse {
+        message: tr(&state, Message::OverridesCleared),
+    })
+}
+
+// 登録名が適用済みの部品表があれば、リスト変更で古くなる比較結果を破棄する
+fn invalidate_comparison_if_names_applied(state: &AppState) {
+    let applied = [&state.bom_a, &state.bom_b].iter().any(|bom| {
+        bom.lock().unwrap().as_ref().is_some_and(|bom| {
+            bom.rows
+                .iter()
+                .any(|row| row.attributes.contains_key("登録名"))
+        })
+    });
+    if applied {
+        *state.comparison_result.lock().unwrap() = None;
     }
 }
 
 // シートクリアコマンド（後方互換）
 #[tauri::command]
-async fn clear_sheets(state: State<'_, AppState>) -> Result<String, String> {
-    clear_data("all".to_string(), state)
+async fn clear_sheets(state: State<'_, AppState>) -> Result<String, AppError> {
+    clear_data("all".to_string(), None, state)
         .await
         .map(|resp| resp.message)
 }
 
 #[tauri::command]
-async fn list_sessions(kind: String) -> Result<Vec<SessionListItem>, String> {
+async fn list_sessions(kind: String) -> Result<Vec<SessionListItem>, AppError> {
     let kind_enum = parse_session_kind(&kind)?;
     let summaries = collect_snapshots(kind_enum)?;
     Ok(summaries
@@ -1038,11 +3694,44 @@ async fn list_sessions(kind: String) -> Result<Vec<SessionListItem>, String> {
         .collect())
 }
 
+// 比較結果を含むセッションから変更件数の推移を作成する（古い順。比較結果のないセッションは除外）
+#[tauri::command]
+async fn comparison_trend(kind: String) -> Result<Vec<ComparisonTrendPoint>, AppError> {
+    let kind_enum = parse_session_kind(&kind)?;
+    let mut summaries = collect_snapshots(kind_enum)?;
+    summaries.sort_by_key(|summary| summary.created_at);
+
+    let mut points = Vec::new();
+    for summary in summaries {
+        let snapshot = match load_snapshot(kind_enum, &summary.id) {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                warn!("[comparison_trend][skip] id={}, err={}", summary.id, err);
+                continue;
+            }
+        };
+        if let Some(result) = snapshot.comparison_result.as_ref() {
+            let stats = get_comparison_stats(result);
+            let count = |key: &str| stats.get(key).copied().unwrap_or(0);
+            points.push(ComparisonTrendPoint {
+                id: snapshot.id.clone(),
+                label: snapshot.label.clone(),
+                created_at: snapshot.created_at.to_rfc3339(),
+                added: count("b_only"),
+                removed: count("a_only"),
+                modified: count("modified"),
+            });
+        }
+    }
+
+    Ok(points)
+}
+
 #[tauri::command]
 async fn save_manual_session(
     label: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Vec<SessionListItem>, String> {
+) -> Result<Vec<SessionListItem>, AppError> {
     let cleaned_label = label.and_then(|l| {
         let trimmed = l.trim().to_string();
         if trimmed.is_empty() {
@@ -1061,26 +3750,32 @@ async fn restore_session(
     kind: String,
     id: String,
     state: State<'_, AppState>,
-) -> Result<SessionRestoreResponse, String> {
+) -> Result<SessionRestoreResponse, AppError> {
     let kind_enum = parse_session_kind(&kind)?;
     let snapshot = load_snapshot(kind_enum, &id)?;
     apply_snapshot(&state, &snapshot);
 
     Ok(SessionRestoreResponse {
-        message: "セッションを復元しました".to_string(),
+        message: tr(&state, Message::SessionRestored),
         file_a_path: snapshot.file_a_path.clone(),
         file_b_path: snapshot.file_b_path.clone(),
         column_mapping_a: snapshot.column_mapping_a.clone(),
         column_mapping_b: snapshot.column_mapping_b.clone(),
+        column_mapping_by_name_a: snapshot.column_mapping_by_name_a.clone(),
+        column_mapping_by_name_b: snapshot.column_mapping_by_name_b.clone(),
         comparison_result: snapshot.comparison_result.clone(),
         synthesis_result: snapshot.synthesis_result.clone(),
         bom_a_headers: snapshot.bom_a.as_ref().map(|b| b.headers.clone()),
         bom_b_headers: snapshot.bom_b.as_ref().map(|b| b.headers.clone()),
+        compare_options: snapshot.compare_options.clone(),
     })
 }
 
 #[tauri::command]
-async fn delete_session_command(kind: String, id: String) -> Result<Vec<SessionListItem>, String> {
+async fn delete_session_command(
+    kind: String,
+    id: String,
+) -> Result<Vec<SessionListItem>, AppError> {
     let kind_enum = parse_session_kind(&kind)?;
     delete_snapshot(kind_enum, &id)?;
     list_sessions(kind).await
@@ -1088,28 +3783,69 @@ async fn delete_session_command(kind: String, id: String) -> Result<Vec<SessionL
 
 fn main() {
     ensure_watcher_ignore();
+    let state = AppState::default();
+    let log_level = configured_log_level(&state.settings.lock().unwrap());
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
-        .manage(AppState::default())
+        .manage(state)
+        .setup(move |app| {
+            let log_dir = app.path().app_log_dir().ok();
+            logging::init(log_dir.as_deref(), &log_level);
+            info!("[startup] version={}", env!("CARGO_PKG_VERSION"));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             open_file_dialog,
             load_file,
+            load_file_by_name,
+            load_file_as_both,
+            load_folder,
+            compare_loaded,
+            build_coverage_matrix,
             analyze_file,
             preview_file,
+            preview_with_normalization,
             compare_boms,
             compare_with_comments,
+            run_pipeline,
+            export_comparison_stream,
+            compare_stats_only,
+            compare_snapshot,
+            diff_against_source,
+            get_comparison_page,
             synthesize_boms,
+            build_consolidated_bom,
+            extract_changed_bom,
             preprocess_bom,
             update_bom_data,
+            rename_header,
+            merge_columns,
+            split_column,
+            optimize_bom,
+            filter_bom,
+            update_cell,
+            bom_stats,
+            bom_fingerprint,
+            export_corrections_csv,
+            export_unique_column,
             save_result,
+            save_comparison_by_manufacturer,
+            save_comparison_split,
             load_registered_name_list_cmd,
+            preview_registered_name_list,
             save_registered_name_list_cmd,
             apply_registered_names,
+            apply_registered_names_preview,
             set_overrides,
+            add_override,
+            remove_override,
             apply_overrides_ipc,
             get_registered_name_list_cmd,
             get_override_list_cmd,
+            get_app_status,
             validate_bom_data,
+            validate_all,
+            preflight_check,
             load_settings,
             save_settings,
             import_settings,
@@ -1121,9 +3857,13 @@ fn main() {
             get_processed_preview,
             clear_sheets,
             clear_data,
+            clear_registered_names,
+            clear_overrides,
             list_sessions,
+            comparison_trend,
             save_manual_session,
             restore_session,
+            replay_session_operations,
             delete_session_command,
             log_client_event,
             generate_cad_file,
@@ -1135,6 +3875,13 @@ fn main() {
         .expect("error while running tauri application");
 }
 
+fn configured_log_level(settings: &AppSettings) -> String {
+    settings
+        .log_level
+        .clone()
+        .unwrap_or_else(|| logging::DEFAULT_LOG_LEVEL.to_string())
+}
+
 fn ensure_watcher_ignore() {
     const IGNORE_ENTRY: &str = "sessions/**";
     const IGNORE_ENTRY_PARENT: &str = "../sessions/**";
@@ -1168,52 +3915,158 @@ fn ensure_watcher_ignore() {
 }
 
 #[tauri::command]
-async fn open_file_dialog(app: tauri::AppHandle) -> Result<Option<String>, String> {
+async fn open_file_dialog(
+    app: tauri::AppHandle,
+    extra_extensions: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, AppError> {
+    let mut extensions: Vec<String> = bom_processor::SUPPORTED_BOM_EXTENSIONS
+        .iter()
+        .map(|ext| ext.to_string())
+        .collect();
+    for ext in extra_extensions.unwrap_or_default() {
+        let normalized = ext.trim().trim_start_matches('.').to_lowercase();
+        if !normalized.is_empty() && !extensions.contains(&normalized) {
+            extensions.push(normalized);
+        }
+    }
+    let extension_refs: Vec<&str> = extensions.iter().map(|s| s.as_str()).collect();
+
     let (tx, rx) = oneshot::channel();
-    app.dialog()
+    let mut builder = app
+        .dialog()
         .file()
         .set_title("部品表ファイルを選択")
         .set_can_create_directories(false)
-        .add_filter("BOM ファイル", &["csv", "xls", "xlsx"])
-        .pick_file(move |file| {
-            let path = file
-                .and_then(|fp| fp.into_path().ok())
-                .map(|p| p.to_string_lossy().into_owned());
-            let _ = tx.send(path);
-        });
-
-    match rx.await {
-        Ok(path) => Ok(path),
-        Err(_) => Ok(None),
+        .add_filter("BOM ファイル", &extension_refs);
+    if let Some(dir) = last_dialog_dir(&state, DialogKind::Open) {
+        builder = builder.set_directory(dir);
     }
+    builder.pick_file(move |file| {
+        let _ = tx.send(dialog_outcome(file));
+    });
+
+    finish_dialog(&state, DialogKind::Open, rx.await)
 }
 
 #[tauri::command]
-async fn open_settings_import_dialog(app: tauri::AppHandle) -> Result<Option<String>, String> {
+async fn open_settings_import_dialog(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, AppError> {
     let (tx, rx) = oneshot::channel();
-    app.dialog()
+    let mut builder = app
+        .dialog()
         .file()
         .set_title("設定ファイルを選択")
         .set_can_create_directories(false)
-        .add_filter("設定ファイル", &["json"])
-        .pick_file(move |file| {
-            let path = file
-                .and_then(|fp| fp.into_path().ok())
-                .map(|p| p.to_string_lossy().into_owned());
-            let _ = tx.send(path);
-        });
+        .add_filter("設定ファイル", &["json"]);
+    if let Some(dir) = last_dialog_dir(&state, DialogKind::Open) {
+        builder = builder.set_directory(dir);
+    }
+    builder.pick_file(move |file| {
+        let _ = tx.send(dialog_outcome(file));
+    });
+
+    finish_dialog(&state, DialogKind::Open, rx.await)
+}
+
+/// ダイアログの選択結果。キャンセルと内部エラーを区別してフロントエンドへ返す
+#[derive(Debug)]
+enum DialogOutcome {
+    Selected(String),
+    Cancelled,
+    Failed(String),
+}
+
+fn dialog_outcome(file: Option<tauri_plugin_dialog::FilePath>) -> DialogOutcome {
+    match file {
+        Some(fp) => match fp.into_path() {
+            Ok(path) => DialogOutcome::Selected(path.to_string_lossy().into_owned()),
+            Err(e) => DialogOutcome::Failed(e.to_string()),
+        },
+        None => DialogOutcome::Cancelled,
+    }
+}
+
+// キャンセル時は Ok(None)、パス取得の失敗やコールバックが呼ばれずに終了した場合はエラーを返す
+fn finish_dialog(
+    state: &AppState,
+    kind: DialogKind,
+    received: Result<DialogOutcome, oneshot::error::RecvError>,
+) -> Result<Option<String>, AppError> {
+    match received {
+        Ok(DialogOutcome::Selected(path)) => {
+            remember_dialog_dir(state, kind, Some(&path));
+            Ok(Some(path))
+        }
+        Ok(DialogOutcome::Cancelled) => Ok(None),
+        Ok(DialogOutcome::Failed(err)) => {
+            error!("[dialog][path_error] {err}");
+            Err(AppError::Internal(format!(
+                "選択したファイルのパスを取得できませんでした: {err}"
+            )))
+        }
+        Err(_) => {
+            error!("[dialog][channel_closed] ダイアログが結果を返さずに終了しました");
+            Err(AppError::Internal(
+                "ファイルダイアログが結果を返さずに終了しました".to_string(),
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DialogKind {
+    Open,
+    Save,
+}
+
+// 前回ダイアログで選択したフォルダ（存在する場合のみ）
+fn last_dialog_dir(state: &AppState, kind: DialogKind) -> Option<PathBuf> {
+    let settings = state.settings.lock().unwrap();
+    let dir = match kind {
+        DialogKind::Open => settings.last_open_dir.as_ref(),
+        DialogKind::Save => settings.last_save_dir.as_ref(),
+    }?;
+    let path = PathBuf::from(dir);
+    if path.is_dir() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+fn remember_dialog_dir(state: &AppState, kind: DialogKind, selected: Option<&str>) {
+    let dir = match selected.and_then(|path| Path::new(path).parent()) {
+        Some(parent) => parent.to_string_lossy().to_string(),
+        None => return,
+    };
+    if dir.is_empty() {
+        return;
+    }
 
-    match rx.await {
-        Ok(path) => Ok(path),
-        Err(_) => Ok(None),
+    let mut settings = state.settings.lock().unwrap();
+    let slot = match kind {
+        DialogKind::Open => &mut settings.last_open_dir,
+        DialogKind::Save => &mut settings.last_save_dir,
+    };
+    if slot.as_deref() == Some(dir.as_str()) {
+        return;
+    }
+    *slot = Some(dir);
+    if let Err(err) = write_settings_to_disk(&settings) {
+        error!("[dialog][settings_error] {err}");
     }
 }
 
-fn parse_session_kind(kind: &str) -> Result<SessionKind, String> {
+fn parse_session_kind(kind: &str) -> Result<SessionKind, AppError> {
     match kind.to_lowercase().as_str() {
         "auto" => Ok(SessionKind::Auto),
         "manual" => Ok(SessionKind::Manual),
-        _ => Err("不明なセッション種別です".to_string()),
+        _ => Err(AppError::InvalidInput(
+            "不明なセッション種別です".to_string(),
+        )),
     }
 }
 
@@ -1245,12 +4098,17 @@ fn create_snapshot(
         file_b_path: state.file_b_path.lock().unwrap().clone(),
         column_mapping_a: state.column_mapping_a.lock().unwrap().clone(),
         column_mapping_b: state.column_mapping_b.lock().unwrap().clone(),
+        column_mapping_by_name_a: state.column_mapping_by_name_a.lock().unwrap().clone(),
+        column_mapping_by_name_b: state.column_mapping_by_name_b.lock().unwrap().clone(),
         bom_a,
         bom_b,
         comparison_result: comparison,
         synthesis_result: synthesis,
         registered_name_list,
         override_list,
+        operations_a: state.operations_a.lock().unwrap().clone(),
+        operations_b: state.operations_b.lock().unwrap().clone(),
+        compare_options: state.comparison_options.lock().unwrap().clone(),
     }
 }
 
@@ -1261,45 +4119,241 @@ fn apply_snapshot(state: &AppState, snapshot: &SessionSnapshot) {
     *state.file_b_path.lock().unwrap() = snapshot.file_b_path.clone();
     *state.column_mapping_a.lock().unwrap() = snapshot.column_mapping_a.clone();
     *state.column_mapping_b.lock().unwrap() = snapshot.column_mapping_b.clone();
+    *state.column_mapping_by_name_a.lock().unwrap() = snapshot.column_mapping_by_name_a.clone();
+    *state.column_mapping_by_name_b.lock().unwrap() = snapshot.column_mapping_by_name_b.clone();
+    // 自動修正の記録はセッションに保存しないため、復元時は破棄する
+    state.corrections_a.lock().unwrap().clear();
+    state.corrections_b.lock().unwrap().clear();
     *state.comparison_result.lock().unwrap() = snapshot.comparison_result.clone();
     *state.synthesis_result.lock().unwrap() = snapshot.synthesis_result.clone();
     *state.registered_name_list.lock().unwrap() = snapshot.registered_name_list.clone();
     *state.override_list.lock().unwrap() = snapshot.override_list.clone();
+    *state.operations_a.lock().unwrap() = snapshot.operations_a.clone();
+    *state.operations_b.lock().unwrap() = snapshot.operations_b.clone();
+    // 比較オプションは比較結果の状態として戻し、設定ファイルには書き込まない。
+    // 同じ条件で再比較する場合は、復元結果の compare_options を比較コマンドに渡す
+    *state.comparison_options.lock().unwrap() = snapshot.compare_options.clone();
 }
 
-fn save_auto_session(state: &AppState) -> Result<(), String> {
+fn save_auto_session(state: &AppState) -> Result<(), AppError> {
     let bom_a_exists = state.bom_a.lock().unwrap().is_some();
     let bom_b_exists = state.bom_b.lock().unwrap().is_some();
     if !bom_a_exists && !bom_b_exists {
         return Ok(());
     }
 
-    let snapshot = create_snapshot(state, false, None);
+    let snapshot = create_snapshot(state, should_autosave_results(state), None);
     let _ = save_snapshot(snapshot, SessionKind::Auto)?;
     Ok(())
 }
 
+// 結果の自動保存は設定で有効な場合のみ、かつ行数が上限以下のときに限る
+fn should_autosave_results(state: &AppState) -> bool {
+    let (enabled, limit) = {
+        let settings = state.settings.lock().unwrap();
+        (
+            settings.autosave_results,
+            settings
+                .autosave_result_row_limit
+                .unwrap_or(AUTOSAVE_RESULT_ROW_LIMIT),
+        )
+    };
+    if !enabled {
+        return false;
+    }
+
+    let comparison_rows = state
+        .comparison_result
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|result| {
+            result.common_parts.len()
+                + result.a_only_parts.len()
+                + result.b_only_parts.len()
+                + result.modified_parts.len()
+                + result.renamed_parts.len()
+        })
+        .unwrap_or(0);
+    let synthesis_rows = state
+        .synthesis_result
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|result| result.rows.len())
+        .unwrap_or(0);
+
+    comparison_rows + synthesis_rows <= limit
+}
+
+// 自動保存に失敗しても比較・合成の結果は返せるため、ログに残して処理を続ける
+fn autosave_after_result(state: &AppState) {
+    if !state.settings.lock().unwrap().autosave_results {
+        return;
+    }
+    if let Err(err) = save_auto_session(state) {
+        error!("[autosave][session_error] {err}");
+    }
+}
+
+fn remembered_mappings_file_path() -> PathBuf {
+    Path::new(SETTINGS_DIR).join(REMEMBERED_MAPPINGS_FILE_NAME)
+}
+
+/// 列指定を記憶するためのキー。同じファイルを別表記のパスで開いても一致するよう正規化する
+fn normalize_mapping_path(file_path: &str) -> String {
+    let trimmed = file_path.trim();
+    let path = fs::canonicalize(trimmed)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| trimmed.to_string());
+    let path = path
+        .strip_prefix(r"\\?\")
+        .unwrap_or(&path)
+        .replace('\\', "/");
+    if cfg!(windows) {
+        path.to_lowercase()
+    } else {
+        path
+    }
+}
+
+fn load_remembered_mappings_from_disk() -> Result<BTreeMap<String, RememberedMapping>, String> {
+    let path = remembered_mappings_file_path();
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("列指定の記憶ファイルの読み込みに失敗しました: {e}"))?;
+
+    if content.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    let raw: BTreeMap<String, RememberedMapping> = serde_json::from_str(&content)
+        .map_err(|e| format!("列指定の記憶ファイルの解析に失敗しました: {e}"))?;
+
+    Ok(normalize_remembered_mappings(raw))
+}
+
+/// キーのパスを正規化し、同じファイルに複数の記録がある場合は新しいものを残す
+fn normalize_remembered_mappings(
+    mappings: BTreeMap<String, RememberedMapping>,
+) -> BTreeMap<String, RememberedMapping> {
+    let mut normalized: BTreeMap<String, RememberedMapping> = BTreeMap::new();
+    for (path, entry) in mappings {
+        if path.trim().is_empty() || entry.headers.is_empty() {
+            continue;
+        }
+        let key = normalize_mapping_path(&path);
+        let newer = normalized
+            .get(&key)
+            .is_none_or(|existing| existing.updated_at < entry.updated_at);
+        if newer {
+            normalized.insert(key, entry);
+        }
+    }
+    prune_remembered_mappings(&mut normalized);
+    normalized
+}
+
+/// 記憶している件数が上限を超えた場合は古いものから削除する
+fn prune_remembered_mappings(mappings: &mut BTreeMap<String, RememberedMapping>) {
+    if mappings.len() <= REMEMBERED_MAPPING_LIMIT {
+        return;
+    }
+    let mut by_age: Vec<(DateTime<Utc>, String)> = mappings
+        .iter()
+        .map(|(path, entry)| (entry.updated_at, path.clone()))
+        .collect();
+    by_age.sort();
+    let excess = mappings.len() - REMEMBERED_MAPPING_LIMIT;
+    for (_, path) in by_age.into_iter().take(excess) {
+        mappings.remove(&path);
+    }
+}
+
+fn write_remembered_mappings_to_disk(
+    mappings: &BTreeMap<String, RememberedMapping>,
+) -> Result<(), AppError> {
+    let path = remembered_mappings_file_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::write_failed("設定フォルダの作成に失敗しました", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(mappings)
+        .map_err(|e| AppError::Internal(format!("列指定の記憶JSONの生成に失敗しました: {e}")))?;
+
+    fs::write(&path, json)
+        .map_err(|e| AppError::write_failed("列指定の記憶ファイルの保存に失敗しました", e))?;
+
+    Ok(())
+}
+
+/// 読み込みに使った列指定をファイルごとに記憶する（保存に失敗しても読み込みは続行する）
+fn remember_column_mapping(
+    state: &AppState,
+    file_path: &str,
+    headers: Vec<String>,
+    mapping: ColumnMapping,
+) {
+    let mut mappings = state.remembered_mappings.lock().unwrap();
+    mappings.insert(
+        normalize_mapping_path(file_path),
+        RememberedMapping {
+            headers,
+            mapping,
+            updated_at: Utc::now(),
+        },
+    );
+    prune_remembered_mappings(&mut mappings);
+    if let Err(err) = write_remembered_mappings_to_disk(&mappings) {
+        warn!(
+            "[load_file][remember_mapping_error] path={}, err={}",
+            file_path, err
+        );
+    }
+}
+
 fn settings_file_path() -> PathBuf {
     Path::new(SETTINGS_DIR).join(SETTINGS_FILE_NAME)
 }
 
-fn load_settings_from_disk() -> Result<AppSettings, String> {
+fn load_settings_from_disk() -> Result<AppSettings, AppError> {
     let path = settings_file_path();
     if !path.exists() {
         return Ok(AppSettings::default());
     }
 
     let content = fs::read_to_string(&path)
-        .map_err(|e| format!("設定ファイルの読み込みに失敗しました: {e}"))?;
+        .map_err(|e| AppError::read_failed("設定ファイルの読み込みに失敗しました", e))?;
 
     if content.trim().is_empty() {
         return Ok(AppSettings::default());
     }
 
     let raw: AppSettings = serde_json::from_str(&content)
-        .map_err(|e| format!("設定ファイルの解析に失敗しました: {e}"))?;
+        .map_err(|e| AppError::InvalidInput(format!("設定ファイルの解析に失敗しました: {e}")))?;
 
-    normalize_settings(raw)
+    normalize_settings(raw).map_err(AppError::InvalidInput)
+}
+
+// 除外パターンの前後の空白を除き、空のものと大文字小文字違いの重複を取り除く
+fn normalize_exclude_patterns(patterns: Vec<String>) -> Vec<String> {
+    let mut exclude_patterns = Vec::new();
+    let mut exclude_seen: HashSet<String> = HashSet::new();
+
+    for pattern in patterns.into_iter() {
+        let trimmed = pattern.trim().to_string();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if exclude_seen.insert(trimmed.to_uppercase()) {
+            exclude_patterns.push(trimmed);
+        }
+    }
+    exclude_patterns
 }
 
 fn normalize_settings(settings: AppSettings) -> Result<AppSettings, String> {
@@ -1331,31 +4385,68 @@ fn normalize_settings(settings: AppSettings) -> Result<AppSettings, String> {
         }
     }
 
+    let exclude_patterns = normalize_exclude_patterns(settings.exclude_patterns);
+
+    let range_designator_pattern = settings
+        .range_designator_pattern
+        .as_deref()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty());
+    if let Some(ref pattern) = range_designator_pattern {
+        bom_processor::designator_regex(Some(pattern)).map_err(|e| e.to_string())?;
+    }
+
+    if settings.max_file_size_mb == Some(0) {
+        return Err("ファイルサイズの上限は1MB以上を指定してください".to_string());
+    }
+
+    let language = settings.language.trim().to_lowercase();
+    let language = if language.is_empty() {
+        i18n::DEFAULT_LANGUAGE.to_string()
+    } else if i18n::SUPPORTED_LANGUAGES.contains(&language.as_str()) {
+        language
+    } else {
+        return Err(format!("未対応の言語です: {language}"));
+    };
+
     Ok(AppSettings {
         makers,
         format_rules: rules,
+        exclude_patterns,
+        range_designator_pattern,
+        language,
+        ..settings
     })
 }
 
-fn write_settings_to_disk(settings: &AppSettings) -> Result<(), String> {
+fn write_settings_to_disk(settings: &AppSettings) -> Result<(), AppError> {
     let path = settings_file_path();
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("設定フォルダの作成に失敗しました: {e}"))?;
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::write_failed("設定フォルダの作成に失敗しました", e))?;
     }
 
     let json = serde_json::to_string_pretty(settings)
-        .map_err(|e| format!("設定JSONの生成に失敗しました: {e}"))?;
+        .map_err(|e| AppError::Internal(format!("設定JSONの生成に失敗しました: {e}")))?;
 
-    fs::write(&path, json).map_err(|e| format!("設定ファイルの保存に失敗しました: {e}"))?;
+    fs::write(&path, json)
+        .map_err(|e| AppError::write_failed("設定ファイルの保存に失敗しました", e))?;
 
     Ok(())
 }
 
+/// 辞書を差し替え、列推定用の正規化済み辞書も作り直す
+fn set_column_dictionary(state: &AppState, dictionary: ColumnDictionary) {
+    *state.compiled_dictionary.lock().unwrap() =
+        Arc::new(bom_processor::CompiledDictionary::new(&dictionary));
+    *state.column_dictionary.lock().unwrap() = dictionary;
+}
+
 fn dictionary_file_path() -> PathBuf {
     Path::new(DICTIONARY_DIR).join(DICTIONARY_FILE_NAME)
 }
 
-fn load_dictionary_from_disk() -> Result<ColumnDictionary, String> {
+fn load_dictionary_from_disk() -> Result<ColumnDictionary, AppError> {
     let path = dictionary_file_path();
     if !path.exists() {
         let defaults = default_column_dictionary();
@@ -1364,16 +4455,16 @@ fn load_dictionary_from_disk() -> Result<ColumnDictionary, String> {
     }
 
     let content = fs::read_to_string(&path)
-        .map_err(|e| format!("辞書ファイルの読み込みに失敗しました: {e}"))?;
+        .map_err(|e| AppError::read_failed("辞書ファイルの読み込みに失敗しました", e))?;
 
     if content.trim().is_empty() {
         return Ok(default_column_dictionary());
     }
 
     let raw: ColumnDictionary = serde_json::from_str(&content)
-        .map_err(|e| format!("辞書ファイルの解析に失敗しました: {e}"))?;
+        .map_err(|e| AppError::InvalidInput(format!("辞書ファイルの解析に失敗しました: {e}")))?;
 
-    normalize_dictionary(raw)
+    normalize_dictionary(raw).map_err(AppError::InvalidInput)
 }
 
 fn normalize_dictionary(dictionary: ColumnDictionary) -> Result<ColumnDictionary, String> {
@@ -1441,16 +4532,18 @@ fn normalize_dictionary(dictionary: ColumnDictionary) -> Result<ColumnDictionary
     Ok(ColumnDictionary { columns })
 }
 
-fn write_dictionary_to_disk(dictionary: &ColumnDictionary) -> Result<(), String> {
+fn write_dictionary_to_disk(dictionary: &ColumnDictionary) -> Result<(), AppError> {
     let path = dictionary_file_path();
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("辞書フォルダの作成に失敗しました: {e}"))?;
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::write_failed("辞書フォルダの作成に失敗しました", e))?;
     }
 
     let json = serde_json::to_string_pretty(dictionary)
-        .map_err(|e| format!("辞書JSONの生成に失敗しました: {e}"))?;
+        .map_err(|e| AppError::Internal(format!("辞書JSONの生成に失敗しました: {e}")))?;
 
-    fs::write(&path, json).map_err(|e| format!("辞書ファイルの保存に失敗しました: {e}"))?;
+    fs::write(&path, json)
+        .map_err(|e| AppError::write_failed("辞書ファイルの保存に失敗しました", e))?;
 
     Ok(())
 }
@@ -1495,23 +4588,54 @@ fn default_column_dictionary() -> ColumnDictionary {
                     "vendor".to_string(),
                 ],
             },
+            ColumnDictionaryEntry {
+                column_type: "part_model".to_string(),
+                display_name: Some("登録名リストの部品型番".to_string()),
+                patterns: vec![
+                    "部品型番".to_string(),
+                    "型番".to_string(),
+                    "part model".to_string(),
+                    "model".to_string(),
+                ],
+            },
+            ColumnDictionaryEntry {
+                column_type: "registered_name".to_string(),
+                display_name: Some("登録名".to_string()),
+                patterns: vec!["登録名".to_string(), "registered name".to_string()],
+            },
         ],
     }
 }
 
+fn range_designator_pattern(state: &AppState) -> Option<String> {
+    state
+        .settings
+        .lock()
+        .unwrap()
+        .range_designator_pattern
+        .clone()
+}
+
 fn generate_preprocessed_preview(
     bom: &BomData,
     column_mapping: &ColumnMapping,
-) -> Result<PreviewTable, String> {
+    designator_pattern: Option<String>,
+) -> Result<PreviewTable, AppError> {
     let default_rules = PreprocessRules {
         remove_parentheses: true,
         expand_ranges: true,
         fullwidth_to_halfwidth: true,
         lowercase_to_uppercase: true,
+        designator_pattern,
+        keep_range_source: false,
+        max_range_span: bom_processor::DEFAULT_MAX_RANGE_SPAN,
+        expand_descending: false,
+        numeric_columns: Vec::new(),
     };
 
     let processed = bom_processor::preprocess_bom_data(bom, &default_rules)
-        .map_err(|e| format!("前処理エラー: {e}"))?;
+        .map_err(|e| AppError::from(e).with_context("前処理エラー"))?
+        .bom;
 
     let headers = if processed.headers.is_empty() {
         (0..3)
@@ -1560,22 +4684,32 @@ fn generate_preprocessed_preview(
 }
 
 #[tauri::command]
-async fn log_client_event(level: String, message: String) -> Result<(), String> {
-    println!("[client {level}] {message}");
+async fn log_client_event(level: String, message: String) -> Result<(), AppError> {
+    log::log!(logging::parse_level(&level), "[client {level}] {message}");
     Ok(())
 }
 
+/// BomSnapshot の現在のスキーマバージョン（項目を追加・変更したら上げる）
+pub const BOM_SNAPSHOT_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BomSnapshot {
+    /// スキーマバージョン（未指定の旧形式は 0）
+    #[serde(default)]
+    pub schema_version: u32,
     pub headers: Vec<String>,
     pub rows: Vec<BomRow>,
+    #[serde(default)]
+    pub verbatim: VerbatimColumns,
 }
 
 impl From<BomData> for BomSnapshot {
     fn from(value: BomData) -> Self {
         Self {
+            schema_version: BOM_SNAPSHOT_SCHEMA_VERSION,
             headers: value.headers,
             rows: value.rows,
+            verbatim: value.verbatim,
         }
     }
 }
@@ -1585,6 +4719,7 @@ impl From<BomSnapshot> for BomData {
         Self {
             headers: value.headers,
             rows: value.rows,
+            verbatim: value.verbatim,
         }
     }
 }
@@ -1593,53 +4728,108 @@ impl From<BomSnapshot> for BomData {
 async fn get_bom_snapshot(
     side: String,
     state: State<'_, AppState>,
-) -> Result<Option<BomSnapshot>, String> {
+) -> Result<Option<BomSnapshot>, AppError> {
     let side = side.to_lowercase();
     let snapshot = match side.as_str() {
         "a" => state
             .bom_a
             .lock()
-            .map_err(|_| "部品表Aのロックに失敗しました".to_string())?
+            .map_err(|_| AppError::Internal("部品表Aのロックに失敗しました".to_string()))?
             .clone()
             .map(BomSnapshot::from),
         "b" => state
             .bom_b
             .lock()
-            .map_err(|_| "部品表Bのロックに失敗しました".to_string())?
+            .map_err(|_| AppError::Internal("部品表Bのロックに失敗しました".to_string()))?
             .clone()
             .map(BomSnapshot::from),
         _ => {
-            return Err("サイド指定が無効です".to_string());
+            return Err(AppError::InvalidInput(tr(&state, Message::InvalidSide)));
         }
     };
     Ok(snapshot)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn generate_cad_file(
     format: String,
     snapshot: BomSnapshot,
     output_path: Option<String>,
-) -> Result<String, String> {
-    let format = CadFormat::parse(&format)?;
+    title: Option<String>,
+    metadata: Option<Vec<String>>,
+    delimiter: Option<String>,
+    line_ending: Option<String>,
+    require_manufacturer: Option<bool>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let format = CadFormat::parse(&format).map_err(AppError::UnsupportedFormat)?;
+    let line_ending = parse_line_ending(line_ending.as_deref())?;
+    snapshot
+        .check_schema_version()
+        .map_err(AppError::InvalidInput)?;
     let bom: BomData = snapshot.into();
     if bom.rows.is_empty() {
-        return Err("出力対象の部品表にデータがありません".to_string());
+        return Err(AppError::NoData(
+            "出力対象の部品表にデータがありません".to_string(),
+        ));
+    }
+
+    // 未指定の場合はメーカーの有無を確認せずに出力する
+    if require_manufacturer.unwrap_or(false) {
+        let dictionary = state.column_dictionary.lock().unwrap().clone();
+        let missing = bom_processor::find_parts_missing_manufacturer(
+            &bom,
+            &dictionary,
+            CAD_MISSING_MANUFACTURER_LIMIT,
+        )?;
+        if !missing.is_empty() {
+            return Err(AppError::InvalidInput(format!(
+                "メーカーが未設定の部品があります: {}",
+                missing.join(", ")
+            )));
+        }
     }
 
-    let content = build_cad_output(&format, &bom);
     let target_path = determine_cad_output_path(&format, output_path)?;
     if let Some(parent) = target_path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("出力ディレクトリを作成できません: {e}"))?;
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::write_failed("出力ディレクトリを作成できません", e))?;
     }
-    let mut file =
-        File::create(&target_path).map_err(|e| format!("CADファイルを作成できません: {e}"))?;
-    file.write_all(content.join("\n").as_bytes())
-        .map_err(|e| format!("CADファイルの書き込みに失敗しました: {e}"))?;
+    let file = File::create(&target_path)
+        .map_err(|e| AppError::write_failed("CADファイルを作成できません", e))?;
+    let mut writer = BufWriter::new(file);
+    let options = CadOutputOptions {
+        title,
+        metadata: metadata.unwrap_or_default(),
+        delimiter,
+    };
+    let mut report_progress = |progress: CadExportProgress| {
+        if let Err(e) = app.emit(CAD_EXPORT_PROGRESS_EVENT, progress) {
+            warn!("[generate_cad_file][progress] イベント送信に失敗しました: {e}");
+        }
+    };
+    write_cad_output(
+        &mut writer,
+        &format,
+        &bom,
+        &options,
+        line_ending,
+        &mut report_progress,
+    )
+    .and_then(|_| writer.flush())
+    .map_err(|e| AppError::write_failed("CADファイルの書き込みに失敗しました", e))?;
 
     Ok(target_path.to_string_lossy().to_string())
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct CadExportProgress {
+    processed_rows: usize,
+    total_rows: usize,
+}
+
 #[derive(Debug, Clone, Copy)]
 enum CadFormat {
     Pads,
@@ -1672,56 +4862,131 @@ impl CadFormat {
             CadFormat::Pws => "PWS",
         }
     }
+
+    fn default_delimiter(&self) -> &'static str {
+        match self {
+            CadFormat::Pads => "\t",
+            CadFormat::Bd => ",",
+            CadFormat::Pws => "=",
+        }
+    }
+
+    fn comment_prefix(&self) -> &'static str {
+        match self {
+            CadFormat::Pads => "!",
+            CadFormat::Bd | CadFormat::Pws => "# ",
+        }
+    }
+
+    fn default_title(&self) -> &'static str {
+        match self {
+            CadFormat::Pads => "KYODEN BOM TOOL CAD EXPORT - PADS",
+            CadFormat::Bd => "Kyoden BOM Tool CAD Export (BD)",
+            CadFormat::Pws => "Kyoden BOM Tool CAD Export (PWS)",
+        }
+    }
+}
+
+// CAD出力のオプション
+// title/metadata: 先頭に書き出すタイトル行と追加のコメント行（Rev・日付など）
+// delimiter: 部品番号と型番の区切り文字（未指定時はフォーマット既定）
+#[derive(Debug, Default)]
+struct CadOutputOptions {
+    title: Option<String>,
+    metadata: Vec<String>,
+    delimiter: Option<String>,
+}
+
+/// 改行コードの指定を解釈する（未指定時は LF）
+fn parse_line_ending(input: Option<&str>) -> Result<&'static str, AppError> {
+    match input.map(|v| v.trim().to_lowercase()).as_deref() {
+        None | Some("") | Some("lf") => Ok("\n"),
+        Some("crlf") => Ok("\r\n"),
+        Some(other) => Err(AppError::InvalidInput(format!(
+            "未対応の改行コードです: {other}"
+        ))),
+    }
 }
 
-fn build_cad_output(format: &CadFormat, bom: &BomData) -> Vec<String> {
-    let mut lines = Vec::new();
+// 行を生成しながら順次書き出す（大きな部品表でも出力全体をメモリに保持しない）
+// 改行は行の間にのみ挿入し、CAD_EXPORT_PROGRESS_INTERVAL 行ごとと完了時に進捗を通知する
+fn write_cad_output<W: Write>(
+    writer: &mut W,
+    format: &CadFormat,
+    bom: &BomData,
+    options: &CadOutputOptions,
+    line_ending: &str,
+    on_progress: &mut dyn FnMut(CadExportProgress),
+) -> std::io::Result<()> {
+    let prefix = format.comment_prefix();
+    let title = options
+        .title
+        .as_deref()
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or(format.default_title());
+    write!(writer, "{}{}", prefix, title)?;
+    for line in &options.metadata {
+        write!(writer, "{}{}{}", line_ending, prefix, line)?;
+    }
+    let delimiter = options
+        .delimiter
+        .as_deref()
+        .filter(|d| !d.is_empty())
+        .unwrap_or(format.default_delimiter());
     match format {
-        CadFormat::Pads => {
-            lines.push("!KYODEN BOM TOOL CAD EXPORT - PADS".to_string());
-            lines.push("PART_NUMBER\tMODEL_NUMBER".to_string());
-            for row in &bom.rows {
-                lines.push(format!("{}\t{}", row.part_number, row.model_number));
-            }
-        }
-        CadFormat::Bd => {
-            lines.push("# Kyoden BOM Tool CAD Export (BD)".to_string());
-            lines.push("PART_NUMBER,MODEL_NUMBER".to_string());
-            for row in &bom.rows {
-                lines.push(format!("{},{}", row.part_number, row.model_number));
-            }
+        CadFormat::Pads | CadFormat::Bd => {
+            write!(writer, "{line_ending}PART_NUMBER{delimiter}MODEL_NUMBER")?;
         }
         CadFormat::Pws => {
-            lines.push("# Kyoden BOM Tool CAD Export (PWS)".to_string());
-            lines.push("[Component List]".to_string());
-            for row in &bom.rows {
-                lines.push(format!("{}={}", row.part_number, row.model_number));
-            }
+            write!(writer, "{line_ending}[Component List]")?;
+        }
+    }
+
+    let with_attributes = !bom.headers.is_empty();
+    let total_rows = bom.rows.len() * if with_attributes { 2 } else { 1 };
+    let mut processed_rows = 0usize;
+    let mut advance = |processed_rows: &mut usize| {
+        *processed_rows += 1;
+        if processed_rows.is_multiple_of(CAD_EXPORT_PROGRESS_INTERVAL)
+            || *processed_rows == total_rows
+        {
+            on_progress(CadExportProgress {
+                processed_rows: *processed_rows,
+                total_rows,
+            });
         }
+    };
+
+    for row in &bom.rows {
+        write!(
+            writer,
+            "{}{}{}{}",
+            line_ending, row.part_number, delimiter, row.model_number
+        )?;
+        advance(&mut processed_rows);
     }
-    if !bom.headers.is_empty() {
-        lines.push("".to_string());
-        lines.push("# Attributes".to_string());
+    if with_attributes {
+        write!(writer, "{line_ending}{line_ending}# Attributes")?;
         for row in &bom.rows {
-            if row.attributes.is_empty() {
-                continue;
+            if !row.attributes.is_empty() {
+                let attrs = row
+                    .attributes
+                    .iter()
+                    .map(|(k, v)| format!("{k}={v}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(writer, "{}{} => {}", line_ending, row.part_number, attrs)?;
             }
-            let attrs = row
-                .attributes
-                .iter()
-                .map(|(k, v)| format!("{k}={v}"))
-                .collect::<Vec<_>>()
-                .join(", ");
-            lines.push(format!("{} => {}", row.part_number, attrs));
+            advance(&mut processed_rows);
         }
     }
-    lines
+    Ok(())
 }
 
 fn determine_cad_output_path(
     format: &CadFormat,
     provided: Option<String>,
-) -> Result<PathBuf, String> {
+) -> Result<PathBuf, AppError> {
     if let Some(path) = provided {
         let path = PathBuf::from(path);
         if path.extension().is_none() {
@@ -1754,20 +5019,29 @@ async fn save_file_dialog(
     app: tauri::AppHandle,
     default_path: Option<String>,
     filters: Option<Vec<DialogFilter>>,
-) -> Result<Option<String>, String> {
+    state: State<'_, AppState>,
+) -> Result<Option<String>, AppError> {
     let (tx, rx) = oneshot::channel();
     let mut builder = app.dialog().file();
+    let mut directory_set = false;
 
     if let Some(path) = default_path.as_ref() {
         let pb = PathBuf::from(path);
-        if let Some(parent) = pb.parent() {
+        if let Some(parent) = pb.parent().filter(|p| !p.as_os_str().is_empty()) {
             builder = builder.set_directory(parent.to_path_buf());
+            directory_set = true;
         }
         if let Some(file_name) = pb.file_name() {
             builder = builder.set_file_name(file_name.to_string_lossy().to_string());
         }
     }
 
+    if !directory_set {
+        if let Some(dir) = last_dialog_dir(&state, DialogKind::Save) {
+            builder = builder.set_directory(dir);
+        }
+    }
+
     if let Some(filters) = filters {
         for filter in filters {
             let extension_refs: Vec<&str> = filter.extensions.iter().map(|s| s.as_str()).collect();
@@ -1776,14 +5050,333 @@ async fn save_file_dialog(
     }
 
     builder.save_file(move |file| {
-        let path = file
-            .and_then(|fp| fp.into_path().ok())
-            .map(|p| p.to_string_lossy().into_owned());
-        let _ = tx.send(path);
+        let _ = tx.send(dialog_outcome(file));
     });
 
-    match rx.await {
-        Ok(result) => Ok(result),
-        Err(_) => Ok(None),
+    finish_dialog(&state, DialogKind::Save, rx.await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dialog_outcome() {
+        let selected = tauri_plugin_dialog::FilePath::Path(PathBuf::from("bom.csv"));
+        assert!(matches!(
+            dialog_outcome(Some(selected)),
+            DialogOutcome::Selected(path) if path == "bom.csv"
+        ));
+        assert!(matches!(dialog_outcome(None), DialogOutcome::Cancelled));
+    }
+
+    fn load_test_csv(name: &str, content: &str) -> (String, bom_processor::LoadBomResult) {
+        let path = std::env::temp_dir().join(format!("{}_{}.csv", name, std::process::id()));
+        fs::write(&path, content).unwrap();
+        let path = path.to_string_lossy().into_owned();
+        let mapping: ColumnMapping =
+            serde_json::from_str(r#"{"part_number":0,"model_number":1}"#).unwrap();
+        let loaded = bom_processor::block_on(bom_processor::load_bom_file(
+            &path,
+            &mapping,
+            u64::MAX,
+            None,
+        ))
+        .unwrap();
+        (path, loaded)
+    }
+
+    fn load_operation() -> BomOperation {
+        BomOperation::Load {
+            column_mapping: Box::new(
+                serde_json::from_str(r#"{"part_number":0,"model_number":1}"#).unwrap(),
+            ),
+            mapping_by_name: None,
+            sheet: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_recorded_operations_to_second_file() {
+        let (path, loaded) = load_test_csv(
+            "replay_second",
+            "部品番号,型番,値\nC1-C3,CAP100,10uF\nR1,RES100,1k\n",
+        );
+        let _ = fs::remove_file(&path);
+        let rules: PreprocessRules = serde_json::from_str(
+            r#"{"remove_parentheses":false,"expand_ranges":true,
+                "fullwidth_to_halfwidth":false,"lowercase_to_uppercase":false}"#,
+        )
+        .unwrap();
+        let operations = [
+            load_operation(),
+            BomOperation::RenameHeader {
+                old_name: "型番".to_string(),
+                new_name: "品番".to_string(),
+            },
+            BomOperation::Preprocess { rules },
+        ];
+        let mut by_name = ColumnMappingByName {
+            part_number: "部品番号".to_string(),
+            model_number: "型番".to_string(),
+            manufacturer: None,
+            quantity: None,
+        };
+
+        let mut bom = loaded.bom;
+        let mut mapping = loaded.column_mapping;
+        let warnings = apply_recorded_operations(
+            &mut bom,
+            &mut mapping,
+            Some(&mut by_name),
+            &operations[1..],
+            &None,
+            &None,
+        )
+        .unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(bom.headers, vec!["部品番号", "品番", "値"]);
+        let parts: Vec<&str> = bom.rows.iter().map(|r| r.part_number.as_str()).collect();
+        assert_eq!(parts, vec!["C1", "C2", "C3", "R1"]);
+        assert_eq!(bom.rows[0].attributes["品番"], "CAP100");
+        assert_eq!(by_name.model_number, "品番");
+    }
+
+    #[test]
+    fn test_replay_failure_leaves_state_unchanged() {
+        let state = AppState::default();
+        let (first_path, first) =
+            load_test_csv("replay_first", "部品番号,型番,値\nC1,CAP100,10uF\n");
+        let (second_path, _) = load_test_csv("replay_missing", "部品番号,型番\nC1,CAP100\n");
+        let operations = vec![
+            load_operation(),
+            BomOperation::RenameHeader {
+                old_name: "値".to_string(),
+                new_name: "容量".to_string(),
+            },
+        ];
+        *state.bom_a.lock().unwrap() = Some(first.bom.clone());
+        *state.file_a_path.lock().unwrap() = Some(first_path.clone());
+        *state.operations_a.lock().unwrap() = operations;
+
+        // 新しいファイルには「値」列がないため、名前の変更を再適用できない
+        let err = bom_processor::block_on(replay_operations_on_file(&state, "a", &second_path))
+            .unwrap_err();
+        let _ = fs::remove_file(&first_path);
+        let _ = fs::remove_file(&second_path);
+
+        assert_eq!(err.code(), "column");
+        let bom = state.bom_a.lock().unwrap().clone().unwrap();
+        assert_eq!(bom.headers, first.bom.headers);
+        assert_eq!(
+            state.file_a_path.lock().unwrap().as_deref(),
+            Some(first_path.as_str())
+        );
+        let recorded = state.operations_a.lock().unwrap();
+        assert_eq!(recorded.len(), 2);
+        assert!(matches!(recorded[1], BomOperation::RenameHeader { .. }));
+    }
+
+    #[test]
+    fn test_snapshot_keeps_options_used_for_comparison() {
+        let state = AppState::default();
+        let settings_patterns = state.settings.lock().unwrap().exclude_patterns.clone();
+        let bom = BomData {
+            headers: Vec::new(),
+            rows: Vec::new(),
+            verbatim: VerbatimColumns::default(),
+        };
+        let options = CompareOptions {
+            exclude_patterns: vec!["TP*".to_string()],
+            detect_renames: true,
+            ..CompareOptions::default()
+        };
+        store_comparison_result(&state, perform_comparison(&bom, &bom, &options), &options);
+
+        let snapshot = create_snapshot(&state, true, None);
+        let saved = snapshot.compare_options.clone().unwrap();
+        assert_eq!(saved.exclude_patterns, ["TP*"]);
+        assert!(saved.detect_renames);
+
+        let restored = AppState::default();
+        apply_snapshot(&restored, &snapshot);
+        let applied = restored.comparison_options.lock().unwrap().clone().unwrap();
+        assert_eq!(applied.exclude_patterns, ["TP*"]);
+        assert_eq!(
+            restored.settings.lock().unwrap().exclude_patterns,
+            settings_patterns
+        );
+    }
+
+    #[test]
+    fn test_max_range_span_is_clamped() {
+        let rules: PreprocessRules = serde_json::from_str(
+            r#"{"remove_parentheses":false,"expand_ranges":true,
+                "fullwidth_to_halfwidth":false,"lowercase_to_uppercase":false,
+                "max_range_span":4294967295}"#,
+        )
+        .unwrap();
+        assert_eq!(rules.max_range_span, bom_processor::MAX_RANGE_SPAN_LIMIT);
+    }
+
+    fn cad_test_bom(headers: &[&str]) -> BomData {
+        let row = |part: &str, model: &str, value: Option<&str>| {
+            let mut attributes = IndexMap::new();
+            if let Some(value) = value {
+                attributes.insert("値".to_string(), value.to_string());
+            }
+            BomRow {
+                part_number: part.to_string(),
+                model_number: model.to_string(),
+                attributes,
+                quantity: None,
+            }
+        };
+        BomData {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: vec![
+                row("C1", "CAP100", Some("10uF")),
+                row("C2", "CAP100", None),
+                row("R1", "RES100", Some("1k")),
+            ],
+            verbatim: VerbatimColumns::default(),
+        }
+    }
+
+    fn cad_output(
+        format: CadFormat,
+        bom: &BomData,
+        options: &CadOutputOptions,
+        line_ending: &str,
+    ) -> String {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_cad_output(&mut buffer, &format, bom, options, line_ending, &mut |_| {}).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_cad_output_title_and_metadata() {
+        let bom = cad_test_bom(&[]);
+        let options = CadOutputOptions {
+            title: Some("基板A 部品表".to_string()),
+            metadata: vec!["Rev: B".to_string(), "Date: 2026-10-15".to_string()],
+            delimiter: None,
+        };
+        let output = cad_output(CadFormat::Bd, &bom, &options, "\n");
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[..4],
+            [
+                "# 基板A 部品表",
+                "# Rev: B",
+                "# Date: 2026-10-15",
+                "PART_NUMBER,MODEL_NUMBER"
+            ]
+        );
+
+        // 空白だけのタイトルは指定なしと同じくフォーマット既定のタイトルを使う
+        let blank = CadOutputOptions {
+            title: Some("  ".to_string()),
+            ..CadOutputOptions::default()
+        };
+        let output = cad_output(CadFormat::Pads, &bom, &blank, "\n");
+        assert!(output.starts_with("!KYODEN BOM TOOL CAD EXPORT - PADS\nPART_NUMBER\t"));
+    }
+
+    #[test]
+    fn test_cad_output_delimiter_and_crlf() {
+        let bom = cad_test_bom(&[]);
+        let options = CadOutputOptions {
+            delimiter: Some(";".to_string()),
+            ..CadOutputOptions::default()
+        };
+        let line_ending = parse_line_ending(Some("CRLF")).unwrap();
+        let output = cad_output(CadFormat::Bd, &bom, &options, line_ending);
+        assert_eq!(
+            output.as_bytes(),
+            b"# Kyoden BOM Tool CAD Export (BD)\r\nPART_NUMBER;MODEL_NUMBER\r\n\
+              C1;CAP100\r\nC2;CAP100\r\nR1;RES100"
+        );
+
+        // 区切り文字が空の場合はフォーマット既定（PWS は "="）を使う
+        let empty = CadOutputOptions {
+            delimiter: Some(String::new()),
+            ..CadOutputOptions::default()
+        };
+        let output = cad_output(CadFormat::Pws, &bom, &empty, "\n");
+        assert!(output.ends_with("\nC1=CAP100\nC2=CAP100\nR1=RES100"));
+
+        assert_eq!(parse_line_ending(None).unwrap(), "\n");
+        assert_eq!(parse_line_ending(Some(" lf ")).unwrap(), "\n");
+        assert_eq!(
+            parse_line_ending(Some("cr")).unwrap_err().code(),
+            "invalid_input"
+        );
+    }
+
+    #[test]
+    fn test_cad_output_matches_buffered_output() {
+        let bom = cad_test_bom(&["部品番号", "型番", "値"]);
+        // ストリーミング化する前の出力（行を集めて改行で連結したもの）
+        let expected = [
+            "!KYODEN BOM TOOL CAD EXPORT - PADS",
+            "PART_NUMBER\tMODEL_NUMBER",
+            "C1\tCAP100",
+            "C2\tCAP100",
+            "R1\tRES100",
+            "",
+            "# Attributes",
+            "C1 => 値=10uF",
+            "R1 => 値=1k",
+        ];
+
+        let mut progress = Vec::new();
+        let mut buffer: Vec<u8> = Vec::new();
+        write_cad_output(
+            &mut buffer,
+            &CadFormat::Pads,
+            &bom,
+            &CadOutputOptions::default(),
+            "\n",
+            &mut |event| progress.push((event.processed_rows, event.total_rows)),
+        )
+        .unwrap();
+
+        assert_eq!(buffer, expected.join("\n").into_bytes());
+        assert!(!buffer.ends_with(b"\n"));
+        assert_eq!(progress.last(), Some(&(6, 6)));
+    }
+
+    #[test]
+    fn test_normalize_exclude_patterns() {
+        let patterns = vec![
+            " TP* ".to_string(),
+            "".to_string(),
+            "tp*".to_string(),
+            "MH?".to_string(),
+        ];
+        assert_eq!(normalize_exclude_patterns(patterns), vec!["TP*", "MH?"]);
+    }
+
+    #[test]
+    fn test_finish_dialog() {
+        let state = AppState::default();
+
+        let cancelled = finish_dialog(&state, DialogKind::Open, Ok(DialogOutcome::Cancelled));
+        assert_eq!(cancelled.unwrap(), None);
+
+        // ディレクトリを含まないパスでは前回のディレクトリを記録しない（設定ファイルに書き込まない）
+        let selected = finish_dialog(
+            &state,
+            DialogKind::Open,
+            Ok(DialogOutcome::Selected("bom.csv".to_string())),
+        );
+        assert_eq!(selected.unwrap().as_deref(), Some("bom.csv"));
+
+        let (tx, rx) = oneshot::channel::<DialogOutcome>();
+        drop(tx);
+        let closed = finish_dialog(&state, DialogKind::Save, rx.blocking_recv());
+        assert_eq!(closed.unwrap_err().code(), "internal");
     }
 }
@@ -5,10 +5,51 @@ use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
+use crate::error::AppError;
 use crate::{
-    BomData, ColumnMapping, ComparisonResult, OverrideList, RegisteredNameList, SynthesisResult,
+    BomData, BomOperation, BomSnapshot, ColumnMapping, ColumnMappingByName, CompareOptions,
+    ComparisonResult, OverrideList, RegisteredNameList, SynthesisResult,
+    BOM_SNAPSHOT_SCHEMA_VERSION,
 };
 
+impl BomSnapshot {
+    /// 現在より新しいスキーマバージョンは解釈できないため拒否する（旧バージョンは既定値で補う）
+    pub fn check_schema_version(&self) -> Result<(), String> {
+        check_bom_snapshot_version(self.schema_version as u64)
+    }
+
+    /// JSONから読み込む。項目の解析より先にバージョンを確認し、新しい形式は明確なエラーにする
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| format!("スナップショットの解析に失敗しました: {e}"))?;
+        let version = value
+            .get("schema_version")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        check_bom_snapshot_version(version)?;
+        serde_json::from_value(value)
+            .map_err(|e| format!("スナップショットの解析に失敗しました: {e}"))
+    }
+
+    /// 現在のスキーマバージョンを付けてJSONに書き出す
+    pub fn to_json(&self) -> Result<String, String> {
+        let mut snapshot = self.clone();
+        snapshot.schema_version = BOM_SNAPSHOT_SCHEMA_VERSION;
+        serde_json::to_string(&snapshot)
+            .map_err(|e| format!("スナップショットの生成に失敗しました: {e}"))
+    }
+}
+
+fn check_bom_snapshot_version(version: u64) -> Result<(), String> {
+    if version > BOM_SNAPSHOT_SCHEMA_VERSION as u64 {
+        return Err(format!(
+            "未対応の部品表スナップショットのバージョンです: {}（対応: {}以下）",
+            version, BOM_SNAPSHOT_SCHEMA_VERSION
+        ));
+    }
+    Ok(())
+}
+
 const AUTO_DIR: &str = "../sessions/auto";
 const MANUAL_DIR: &str = "../sessions/manual";
 const AUTO_LIMIT: usize = 10;
@@ -22,12 +63,25 @@ pub struct SessionSnapshot {
     pub file_b_path: Option<String>,
     pub column_mapping_a: Option<ColumnMapping>,
     pub column_mapping_b: Option<ColumnMapping>,
+    /// 列名で指定して読み込んだ場合の列指定
+    #[serde(default)]
+    pub column_mapping_by_name_a: Option<ColumnMappingByName>,
+    #[serde(default)]
+    pub column_mapping_by_name_b: Option<ColumnMappingByName>,
     pub bom_a: Option<BomData>,
     pub bom_b: Option<BomData>,
     pub comparison_result: Option<ComparisonResult>,
     pub synthesis_result: Option<SynthesisResult>,
     pub registered_name_list: Option<RegisteredNameList>,
     pub override_list: Option<OverrideList>,
+    /// 読み込み後に適用した操作（古いセッションには含まれない）
+    #[serde(default)]
+    pub operations_a: Vec<BomOperation>,
+    #[serde(default)]
+    pub operations_b: Vec<BomOperation>,
+    /// 比較結果を作成したときの比較オプション（比較していない場合は None）
+    #[serde(default)]
+    pub compare_options: Option<CompareOptions>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,11 +108,12 @@ impl SessionKind {
     }
 }
 
-fn ensure_directory(path: &Path) -> Result<(), String> {
-    fs::create_dir_all(path).map_err(|e| format!("ディレクトリ作成に失敗しました: {e}"))
+fn ensure_directory(path: &Path) -> Result<(), AppError> {
+    fs::create_dir_all(path)
+        .map_err(|e| AppError::write_failed("ディレクトリ作成に失敗しました", e))
 }
 
-fn session_dir(kind: SessionKind) -> Result<PathBuf, String> {
+fn session_dir(kind: SessionKind) -> Result<PathBuf, AppError> {
     let dir = PathBuf::from(kind.directory());
     ensure_directory(&dir)?;
     Ok(dir)
@@ -102,18 +157,17 @@ fn snapshot_to_summary(snapshot: &SessionSnapshot) -> SessionSummary {
 pub fn save_snapshot(
     mut snapshot: SessionSnapshot,
     kind: SessionKind,
-) -> Result<SessionSummary, String> {
+) -> Result<SessionSummary, AppError> {
     if snapshot.id.is_empty() {
         snapshot.id = generate_id();
     }
-    let dir = session_dir(kind)?;
-    let path = dir.join(format!("{}.json", snapshot.id));
-    let mut file =
-        File::create(&path).map_err(|e| format!("セッション保存ファイルを作成できません: {e}"))?;
+    let path = snapshot_path(kind, &snapshot.id)?;
+    let mut file = File::create(&path)
+        .map_err(|e| AppError::write_failed("セッション保存ファイルを作成できません", e))?;
     let json = serde_json::to_string_pretty(&snapshot)
-        .map_err(|e| format!("セッションのシリアライズに失敗しました: {e}"))?;
+        .map_err(|e| AppError::Internal(format!("セッションのシリアライズに失敗しました: {e}")))?;
     file.write_all(json.as_bytes())
-        .map_err(|e| format!("セッション保存に失敗しました: {e}"))?;
+        .map_err(|e| AppError::write_failed("セッション保存に失敗しました", e))?;
 
     if kind == SessionKind::Auto {
         prune_auto_sessions()?;
@@ -122,36 +176,40 @@ pub fn save_snapshot(
     Ok(snapshot_to_summary(&snapshot))
 }
 
-fn prune_auto_sessions() -> Result<(), String> {
-    let dir = session_dir(SessionKind::Auto)?;
+fn prune_auto_sessions() -> Result<(), AppError> {
     let mut snapshots = collect_snapshots(SessionKind::Auto)?;
     snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
     if snapshots.len() <= AUTO_LIMIT {
         return Ok(());
     }
     for summary in snapshots.into_iter().skip(AUTO_LIMIT) {
-        let path = dir.join(format!("{}.json", summary.id));
-        let _ = fs::remove_file(path);
+        // ファイル内のIDが不正な場合はセッションフォルダ外を削除しないよう読み飛ばす
+        if let Ok(path) = snapshot_path(SessionKind::Auto, &summary.id) {
+            let _ = fs::remove_file(path);
+        }
     }
     Ok(())
 }
 
-fn read_snapshot(path: &Path) -> Result<SessionSnapshot, String> {
-    let mut file = File::open(path).map_err(|e| format!("セッションを開けません: {e}"))?;
+fn read_snapshot(path: &Path) -> Result<SessionSnapshot, AppError> {
+    let mut file =
+        File::open(path).map_err(|e| AppError::read_failed("セッションを開けません", e))?;
     let mut content = String::new();
     file.read_to_string(&mut content)
-        .map_err(|e| format!("セッションの読み込みに失敗しました: {e}"))?;
-    serde_json::from_str(&content).map_err(|e| format!("セッションの解析に失敗しました: {e}"))
+        .map_err(|e| AppError::read_failed("セッションの読み込みに失敗しました", e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| AppError::UnsupportedFormat(format!("セッションの解析に失敗しました: {e}")))
 }
 
-pub fn collect_snapshots(kind: SessionKind) -> Result<Vec<SessionSummary>, String> {
+pub fn collect_snapshots(kind: SessionKind) -> Result<Vec<SessionSummary>, AppError> {
     let dir = session_dir(kind)?;
     let mut summaries = Vec::new();
     for entry in fs::read_dir(&dir)
-        .map_err(|e| format!("セッションディレクトリの読み込みに失敗しました: {e}"))?
+        .map_err(|e| AppError::read_failed("セッションディレクトリの読み込みに失敗しました", e))?
     {
-        let entry =
-            entry.map_err(|e| format!("ディレクトリエントリの読み込みに失敗しました: {e}"))?;
+        let entry = entry.map_err(|e| {
+            AppError::read_failed("ディレクトリエントリの読み込みに失敗しました", e)
+        })?;
         let path = entry.path();
         if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
             continue;
@@ -164,14 +222,113 @@ pub fn collect_snapshots(kind: SessionKind) -> Result<Vec<SessionSummary>, Strin
     Ok(summaries)
 }
 
-pub fn load_snapshot(kind: SessionKind, id: &str) -> Result<SessionSnapshot, String> {
-    let dir = session_dir(kind)?;
-    let path = dir.join(format!("{}.json", id));
+/// セッションIDが generate_id の形式（`{UNIX秒}-{英数字8文字}`）かを確認する
+/// IDはファイル名になるため、"../" などでセッションフォルダの外を指すIDを拒否する
+pub fn validate_session_id(id: &str) -> Result<(), AppError> {
+    let valid = match id.split_once('-') {
+        Some((timestamp, suffix)) => {
+            !timestamp.is_empty()
+                && timestamp.chars().all(|c| c.is_ascii_digit())
+                && suffix.len() == 8
+                && suffix.chars().all(|c| c.is_ascii_alphanumeric())
+        }
+        None => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::InvalidInput(format!(
+            "無効なセッションIDです: {id}"
+        )))
+    }
+}
+
+/// セッションIDを検証してからセッションファイルのパスを組み立てる
+fn snapshot_path(kind: SessionKind, id: &str) -> Result<PathBuf, AppError> {
+    validate_session_id(id)?;
+    Ok(session_dir(kind)?.join(format!("{}.json", id)))
+}
+
+pub fn load_snapshot(kind: SessionKind, id: &str) -> Result<SessionSnapshot, AppError> {
+    let path = snapshot_path(kind, id)?;
     read_snapshot(&path)
 }
 
-pub fn delete_snapshot(kind: SessionKind, id: &str) -> Result<(), String> {
-    let dir = session_dir(kind)?;
-    let path = dir.join(format!("{}.json", id));
-    fs::remove_file(&path).map_err(|e| format!("セッションの削除に失敗しました: {e}"))
+pub fn delete_snapshot(kind: SessionKind, id: &str) -> Result<(), AppError> {
+    let path = snapshot_path(kind, id)?;
+    fs::remove_file(&path).map_err(|e| AppError::read_failed("セッションの削除に失敗しました", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bom_snapshot_json_versioning() {
+        let legacy = r#"{"headers":["部品番号","型番"],"rows":[{"part_number":"C1","model_number":"CAP100","attributes":{}}]}"#;
+        let snapshot = BomSnapshot::from_json(legacy).unwrap();
+        assert_eq!(snapshot.schema_version, 0);
+        assert_eq!(snapshot.rows[0].quantity, None);
+        assert!(!snapshot.verbatim.part_number);
+
+        let json = snapshot.to_json().unwrap();
+        let round_trip = BomSnapshot::from_json(&json).unwrap();
+        assert_eq!(round_trip.schema_version, BOM_SNAPSHOT_SCHEMA_VERSION);
+        assert_eq!(round_trip.rows[0].model_number, "CAP100");
+
+        let future = format!(
+            r#"{{"schema_version":{},"headers":[],"rows":"new-format"}}"#,
+            BOM_SNAPSHOT_SCHEMA_VERSION + 1
+        );
+        let err = BomSnapshot::from_json(&future).unwrap_err();
+        assert!(err.contains("バージョン"));
+
+        let mut newer = round_trip;
+        newer.schema_version = BOM_SNAPSHOT_SCHEMA_VERSION + 1;
+        assert!(newer.check_schema_version().is_err());
+    }
+
+    #[test]
+    fn test_session_operations_are_optional() {
+        let legacy = r#"{"id":"1700000000-abcd1234","label":null,"created_at":"2024-01-01T00:00:00Z","file_a_path":null,"file_b_path":null,"column_mapping_a":null,"column_mapping_b":null,"bom_a":null,"bom_b":null,"comparison_result":null,"synthesis_result":null,"registered_name_list":null,"override_list":null}"#;
+        let mut snapshot: SessionSnapshot = serde_json::from_str(legacy).unwrap();
+        assert!(snapshot.operations_a.is_empty());
+        assert!(snapshot.compare_options.is_none());
+
+        snapshot.operations_b = vec![crate::BomOperation::ApplyRegisteredNames {
+            policy: Default::default(),
+        }];
+        snapshot.compare_options = Some(CompareOptions {
+            exclude_patterns: vec!["TP*".to_string()],
+            ..CompareOptions::default()
+        });
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.contains(r#""kind":"apply_registered_names""#));
+        let round_trip: SessionSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_trip.operations_b.len(), 1);
+        assert_eq!(
+            round_trip.compare_options.unwrap().exclude_patterns,
+            vec!["TP*".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_validate_session_id_rejects_paths() {
+        assert!(validate_session_id(&generate_id()).is_ok());
+        assert!(validate_session_id("1700000000-abcD1234").is_ok());
+        for id in [
+            "",
+            "../1700000000-abcd1234",
+            "1700000000-abcd1234/../../x",
+            "..\\settings",
+            "1700000000-abc",
+            "1700000000-abcd12345",
+            "abc-abcd1234",
+            "-abcd1234",
+        ] {
+            assert!(validate_session_id(id).is_err(), "{id}");
+        }
+        let err = load_snapshot(SessionKind::Manual, "../../bom_settings").unwrap_err();
+        assert_eq!(err.code(), "invalid_input");
+    }
 }
@@ -1,9 +1,78 @@
-use crate::{BomData, SynthesisResult, SynthesisRow};
+use crate::comparison::check_output_encoding;
+use crate::error::AppError;
+use crate::file_handler;
+use crate::{BomData, BomRow, RegisteredNameList, SynthesisResult, SynthesisRow, VerbatimColumns};
+use indexmap::IndexMap;
 use rayon::prelude::*;
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoverageRow {
+    pub part_number: String,
+    /// ファイル名 → 型番（そのファイルに部品が存在しない場合は None）
+    pub files: BTreeMap<String, Option<String>>,
+    pub present_count: usize,
+}
+
+/// 合成時に採用する型番（preferred_model）の選び方
+/// prefer-a: 部品表Aの型番を優先（Aにない部品はB）
+/// prefer-registered: 登録名リストに型番が載っている側を優先（どちらも載っていない・両方載っている場合はA）
+/// prefer-non-empty: Aの型番が空の場合のみBの型番を採用
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PreferenceStrategy {
+    #[default]
+    PreferA,
+    PreferRegistered,
+    PreferNonEmpty,
+}
+
+fn choose_preferred_model(
+    model_a: Option<&str>,
+    model_b: Option<&str>,
+    strategy: PreferenceStrategy,
+    registered_models: &HashSet<&str>,
+) -> String {
+    let preferred = match (model_a, model_b) {
+        (Some(a), Some(b)) => match strategy {
+            PreferenceStrategy::PreferA => a,
+            PreferenceStrategy::PreferRegistered => {
+                if !registered_models.contains(a.trim()) && registered_models.contains(b.trim()) {
+                    b
+                } else {
+                    a
+                }
+            }
+            PreferenceStrategy::PreferNonEmpty => {
+                if a.trim().is_empty() {
+                    b
+                } else {
+                    a
+                }
+            }
+        },
+        (Some(a), None) => a,
+        (None, Some(b)) => b,
+        (None, None) => "",
+    };
+    preferred.to_string()
+}
 
 /// 部品表AとBを合成して代替合成部品表を作成する
-pub fn perform_synthesis(bom_a: &BomData, bom_b: &BomData) -> SynthesisResult {
+/// 各部品の採用型番は `strategy` で選ぶ（prefer-registered の場合のみ登録名リストを参照）
+pub fn perform_synthesis(
+    bom_a: &BomData,
+    bom_b: &BomData,
+    strategy: PreferenceStrategy,
+    registered_name_list: &Option<RegisteredNameList>,
+) -> SynthesisResult {
+    let registered_models: HashSet<&str> = registered_name_list
+        .iter()
+        .flat_map(|list| list.entries.iter())
+        .map(|entry| entry.part_model.trim())
+        .collect();
+
     let map_a: HashMap<String, &crate::BomRow> = bom_a
         .rows
         .iter()
@@ -15,48 +84,126 @@ pub fn perform_synthesis(bom_a: &BomData, bom_b: &BomData) -> SynthesisResult {
         .map(|row| (row.part_number.clone(), row))
         .collect();
 
-    let mut all_part_numbers: HashSet<String> = HashSet::new();
-    all_part_numbers.extend(map_a.keys().cloned());
-    all_part_numbers.extend(map_b.keys().cloned());
+    // Aの全部品とAにないBの部品を走査するため、どちらにも存在しない部品（ステータス不明）は生じない
+    let from_a = map_a
+        .par_iter()
+        .map(|(part_number, a)| match map_b.get(part_number) {
+            Some(b) => SynthesisRow {
+                part_number: part_number.clone(),
+                model_a: a.model_number.clone(),
+                model_b: b.model_number.clone(),
+                status: "common".to_string(),
+                preferred_model: choose_preferred_model(
+                    Some(&a.model_number),
+                    Some(&b.model_number),
+                    strategy,
+                    &registered_models,
+                ),
+            },
+            None => SynthesisRow {
+                part_number: part_number.clone(),
+                model_a: a.model_number.clone(),
+                model_b: String::new(),
+                status: "missing_b".to_string(),
+                preferred_model: a.model_number.clone(),
+            },
+        });
+    let only_b = map_b
+        .par_iter()
+        .filter(|(part_number, _)| !map_a.contains_key(*part_number))
+        .map(|(part_number, b)| SynthesisRow {
+            part_number: part_number.clone(),
+            model_a: String::new(),
+            model_b: b.model_number.clone(),
+            status: "missing_a".to_string(),
+            preferred_model: b.model_number.clone(),
+        });
+    let mut rows: Vec<SynthesisRow> = from_a.chain(only_b).collect();
+
+    rows.par_sort_by(|a, b| a.part_number.cmp(&b.part_number));
+
+    SynthesisResult { rows }
+}
+
+/// 合成結果から1部品1型番の統合部品表を作成する
+/// 型番は採用型番（preferred_model）、「由来」列に both / from_a / from_b を記録する
+pub fn build_consolidated_bom(result: &SynthesisResult) -> BomData {
+    let headers = vec![
+        "部品番号".to_string(),
+        "型番".to_string(),
+        "由来".to_string(),
+    ];
+    let rows = result
+        .rows
+        .iter()
+        .map(|row| {
+            let provenance = match row.status.as_str() {
+                "common" => "both",
+                "missing_b" => "from_a",
+                _ => "from_b",
+            };
+            let mut attributes = IndexMap::new();
+            attributes.insert(headers[0].clone(), row.part_number.clone());
+            attributes.insert(headers[1].clone(), row.preferred_model.clone());
+            attributes.insert(headers[2].clone(), provenance.to_string());
+            BomRow {
+                part_number: row.part_number.clone(),
+                model_number: row.preferred_model.clone(),
+                attributes,
+                quantity: None,
+            }
+        })
+        .collect();
+
+    BomData {
+        headers,
+        rows,
+        verbatim: VerbatimColumns::default(),
+    }
+}
+
+/// 複数の部品表について、部品番号ごとにどのファイルに含まれるかを集計する
+pub fn build_coverage_matrix(boms: &HashMap<String, BomData>) -> Vec<CoverageRow> {
+    let maps: BTreeMap<&String, HashMap<&str, &crate::BomRow>> = boms
+        .iter()
+        .map(|(name, bom)| {
+            let map = bom
+                .rows
+                .iter()
+                .map(|row| (row.part_number.as_str(), row))
+                .collect();
+            (name, map)
+        })
+        .collect();
 
-    let mut rows: Vec<SynthesisRow> = all_part_numbers
+    let mut all_part_numbers: HashSet<&str> = HashSet::new();
+    for map in maps.values() {
+        all_part_numbers.extend(map.keys().copied());
+    }
+
+    let mut rows: Vec<CoverageRow> = all_part_numbers
         .par_iter()
         .map(|part_number| {
-            let row_a = map_a.get(part_number);
-            let row_b = map_b.get(part_number);
-
-            match (row_a, row_b) {
-                (Some(a), Some(b)) => SynthesisRow {
-                    part_number: part_number.clone(),
-                    model_a: a.model_number.clone(),
-                    model_b: b.model_number.clone(),
-                    status: "common".to_string(),
-                },
-                (Some(a), None) => SynthesisRow {
-                    part_number: part_number.clone(),
-                    model_a: a.model_number.clone(),
-                    model_b: String::new(),
-                    status: "missing_b".to_string(),
-                },
-                (None, Some(b)) => SynthesisRow {
-                    part_number: part_number.clone(),
-                    model_a: String::new(),
-                    model_b: b.model_number.clone(),
-                    status: "missing_a".to_string(),
-                },
-                (None, None) => SynthesisRow {
-                    part_number: part_number.clone(),
-                    model_a: String::new(),
-                    model_b: String::new(),
-                    status: "unknown".to_string(),
-                },
+            let files: BTreeMap<String, Option<String>> = maps
+                .iter()
+                .map(|(name, map)| {
+                    (
+                        (*name).clone(),
+                        map.get(part_number).map(|row| row.model_number.clone()),
+                    )
+                })
+                .collect();
+            let present_count = files.values().filter(|model| model.is_some()).count();
+            CoverageRow {
+                part_number: part_number.to_string(),
+                files,
+                present_count,
             }
         })
         .collect();
 
     rows.par_sort_by(|a, b| a.part_number.cmp(&b.part_number));
-
-    SynthesisResult { rows }
+    rows
 }
 
 /// 合成結果をCSV形式で保存
@@ -64,7 +211,16 @@ pub async fn save_synthesis_result(
     result: &SynthesisResult,
     file_path: &str,
     format: &str,
-) -> Result<String, String> {
+    append: bool,
+    encoding: Option<&str>,
+) -> Result<String, AppError> {
+    if append && format != "csv" {
+        return Err(AppError::InvalidInput(
+            "追記はCSV形式のみ対応しています".to_string(),
+        ));
+    }
+    check_output_encoding(format, append, encoding)?;
+
     match format {
         "csv" => {
             let mut csv_data = Vec::new();
@@ -73,6 +229,7 @@ pub async fn save_synthesis_result(
                 "型番A".to_string(),
                 "型番B".to_string(),
                 "ステータス".to_string(),
+                "採用型番".to_string(),
             ]);
 
             for row in &result.rows {
@@ -81,12 +238,21 @@ pub async fn save_synthesis_result(
                     row.model_a.clone(),
                     row.model_b.clone(),
                     get_status_text(&row.status),
+                    row.preferred_model.clone(),
                 ]);
             }
 
-            crate::file_handler::save_csv_file(&csv_data, file_path, "utf-8")
-                .await
-                .map_err(|e| format!("CSV保存エラー: {e}"))?;
+            if append {
+                let label = crate::file_handler::run_label();
+                crate::file_handler::append_csv_file(&csv_data, file_path, &label)
+                    .await
+                    .map_err(|e| AppError::write_failed("CSV追記エラー", e))?;
+            } else {
+                let encoding = encoding.unwrap_or(file_handler::DEFAULT_CSV_ENCODING);
+                file_handler::save_csv_file(&csv_data, file_path, encoding)
+                    .await
+                    .map_err(|e| AppError::write_failed("CSV保存エラー", e))?;
+            }
         }
         "txt" => {
             let mut content = String::new();
@@ -121,11 +287,16 @@ pub async fn save_synthesis_result(
                 ));
             }
 
-            crate::file_handler::save_txt_file(&content, file_path, "utf-8")
+            let encoding = encoding.unwrap_or(file_handler::DEFAULT_TEXT_ENCODING);
+            file_handler::save_txt_file(&content, file_path, encoding)
                 .await
-                .map_err(|e| format!("TXT保存エラー: {e}"))?;
+                .map_err(|e| AppError::write_failed("TXT保存エラー", e))?;
+        }
+        _ => {
+            return Err(AppError::UnsupportedFormat(
+                "サポートされていないフォーマットです".to_string(),
+            ))
         }
-        _ => return Err("サポートされていないフォーマットです".to_string()),
     }
 
     Ok("合成結果を保存しました".to_string())
@@ -209,8 +380,6 @@ fn get_status_text(status: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{BomData, BomRow};
-    use std::collections::HashMap;
 
     fn create_test_bom_a() -> BomData {
         BomData {
@@ -219,14 +388,17 @@ mod tests {
                 BomRow {
                     part_number: "PART001".to_string(),
                     model_number: "MODEL001".to_string(),
-                    attributes: HashMap::new(),
+                    attributes: IndexMap::new(),
+                    quantity: None,
                 },
                 BomRow {
                     part_number: "PART002".to_string(),
                     model_number: "MODEL002".to_string(),
-                    attributes: HashMap::new(),
+                    attributes: IndexMap::new(),
+                    quantity: None,
                 },
             ],
+            verbatim: VerbatimColumns::default(),
         }
     }
 
@@ -237,14 +409,17 @@ mod tests {
                 BomRow {
                     part_number: "PART001".to_string(),
                     model_number: "MODEL001".to_string(),
-                    attributes: HashMap::new(),
+                    attributes: IndexMap::new(),
+                    quantity: None,
                 },
                 BomRow {
                     part_number: "PART003".to_string(),
                     model_number: "MODEL003".to_string(),
-                    attributes: HashMap::new(),
+                    attributes: IndexMap::new(),
+                    quantity: None,
                 },
             ],
+            verbatim: VerbatimColumns::default(),
         }
     }
 
@@ -253,7 +428,7 @@ mod tests {
         let bom_a = create_test_bom_a();
         let bom_b = create_test_bom_b();
 
-        let result = perform_synthesis(&bom_a, &bom_b);
+        let result = perform_synthesis(&bom_a, &bom_b, PreferenceStrategy::default(), &None);
 
         assert_eq!(result.rows.len(), 3);
 
@@ -279,6 +454,106 @@ mod tests {
         assert_eq!(part003.status, "missing_a");
     }
 
+    #[test]
+    fn test_perform_synthesis_never_produces_unknown_status() {
+        let mut bom_a = create_test_bom_a();
+        bom_a.rows.push(bom_a.rows[0].clone());
+        let empty = BomData {
+            headers: Vec::new(),
+            rows: Vec::new(),
+            verbatim: VerbatimColumns::default(),
+        };
+
+        for (a, b) in [
+            (&bom_a, &create_test_bom_b()),
+            (&bom_a, &empty),
+            (&empty, &create_test_bom_b()),
+            (&empty, &empty),
+        ] {
+            let result = perform_synthesis(a, b, PreferenceStrategy::default(), &None);
+            assert!(result
+                .rows
+                .iter()
+                .all(|row| ["common", "missing_a", "missing_b"].contains(&row.status.as_str())));
+            let stats = get_synthesis_stats(&result);
+            assert_eq!(
+                stats["common"] + stats["missing_a"] + stats["missing_b"],
+                stats["total"]
+            );
+        }
+    }
+
+    #[test]
+    fn test_preference_strategies() {
+        let mut bom_a = create_test_bom_a();
+        bom_a.rows[0].model_number = String::new();
+        bom_a.rows[1].model_number = "MODEL002-A".to_string();
+        let mut bom_b = create_test_bom_a();
+        bom_b.rows[1].model_number = "MODEL002-B".to_string();
+        bom_b.rows.push(create_test_bom_b().rows[1].clone());
+        let registered = Some(RegisteredNameList {
+            entries: vec![crate::RegisteredNameEntry {
+                part_model: "MODEL002-B".to_string(),
+                registered_name: "REG".to_string(),
+                manufacturer: None,
+            }],
+        });
+
+        let preferred = |strategy: PreferenceStrategy| -> Vec<String> {
+            perform_synthesis(&bom_a, &bom_b, strategy, &registered)
+                .rows
+                .into_iter()
+                .map(|row| row.preferred_model)
+                .collect()
+        };
+
+        assert_eq!(
+            preferred(PreferenceStrategy::PreferA),
+            vec!["", "MODEL002-A", "MODEL003"]
+        );
+        assert_eq!(
+            preferred(PreferenceStrategy::PreferRegistered),
+            vec!["", "MODEL002-B", "MODEL003"]
+        );
+        assert_eq!(
+            preferred(PreferenceStrategy::PreferNonEmpty),
+            vec!["MODEL001", "MODEL002-A", "MODEL003"]
+        );
+    }
+
+    #[test]
+    fn test_build_consolidated_bom() {
+        let result = perform_synthesis(
+            &create_test_bom_a(),
+            &create_test_bom_b(),
+            PreferenceStrategy::default(),
+            &None,
+        );
+
+        let bom = build_consolidated_bom(&result);
+
+        assert_eq!(bom.headers, vec!["部品番号", "型番", "由来"]);
+        let rows: Vec<(&str, &str, &str)> = bom
+            .rows
+            .iter()
+            .map(|row| {
+                (
+                    row.part_number.as_str(),
+                    row.model_number.as_str(),
+                    row.attributes["由来"].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("PART001", "MODEL001", "both"),
+                ("PART002", "MODEL002", "from_a"),
+                ("PART003", "MODEL003", "from_b"),
+            ]
+        );
+    }
+
     #[test]
     fn test_get_synthesis_stats() {
         let result = SynthesisResult { rows: vec![] };
@@ -295,6 +570,7 @@ mod tests {
             model_a: "MODEL001".to_string(),
             model_b: "MODEL001".to_string(),
             status: "common".to_string(),
+            preferred_model: "MODEL001".to_string(),
         };
 
         let row2 = SynthesisRow {
@@ -302,6 +578,7 @@ mod tests {
             model_a: "MODEL002".to_string(),
             model_b: String::new(),
             status: "missing_b".to_string(),
+            preferred_model: "MODEL002".to_string(),
         };
 
         let result = SynthesisResult {
@@ -312,4 +589,23 @@ mod tests {
         assert_eq!(filtered.rows.len(), 1);
         assert_eq!(filtered.rows[0].part_number, "PART001");
     }
+
+    #[test]
+    fn test_build_coverage_matrix() {
+        let mut boms = HashMap::new();
+        boms.insert("a.csv".to_string(), create_test_bom_a());
+        boms.insert("b.csv".to_string(), create_test_bom_b());
+
+        let rows = build_coverage_matrix(&boms);
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].part_number, "PART001");
+        assert_eq!(rows[0].present_count, 2);
+        assert_eq!(rows[1].part_number, "PART002");
+        assert_eq!(rows[1].files.get("b.csv"), Some(&None));
+        assert_eq!(
+            rows[2].files.get("b.csv"),
+            Some(&Some("MODEL003".to_string()))
+        );
+    }
 }